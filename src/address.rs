@@ -0,0 +1,75 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::memory::ProgramMemory;
+
+/// A CPU address, optionally qualified by the bank mapped at it. Written
+/// `bank:addr` in hex like RGBDS symbol files, e.g. `03:4123`, or just
+/// `addr` to match whichever bank is mapped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address {
+    pub bank: Option<u16>,
+    pub addr: u16,
+}
+
+impl Address {
+    pub fn new(addr: u16) -> Self {
+        Self { bank: None, addr }
+    }
+
+    pub fn banked(bank: u16, addr: u16) -> Self {
+        Self {
+            bank: Some(bank),
+            addr,
+        }
+    }
+
+    /// The address as currently mapped, with its bank if it is in a banked
+    /// region.
+    pub fn current(addr: u16, mem: &impl ProgramMemory) -> Self {
+        Self {
+            bank: mem.bank(addr),
+            addr,
+        }
+    }
+}
+
+impl From<u16> for Address {
+    fn from(addr: u16) -> Self {
+        Self::new(addr)
+    }
+}
+
+/// Parse a hex number, with or without a `0x` or `$` prefix, into any
+/// integer type it fits in.
+pub fn parse_hex<T: TryFrom<u32>>(s: &str) -> Result<T, String> {
+    let digits = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix('$'))
+        .unwrap_or(s);
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or_else(|| format!("invalid number {:?}", s))
+}
+
+impl FromStr for Address {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.split_once(':') {
+            Some((bank, addr)) => Ok(Self::banked(parse_hex(bank)?, parse_hex(addr)?)),
+            None => Ok(Self::new(parse_hex(s)?)),
+        }
+    }
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.bank {
+            Some(bank) => write!(f, "{:02x}:{:04x}", bank, self.addr),
+            None => write!(f, "0x{:04x}", self.addr),
+        }
+    }
+}
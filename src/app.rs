@@ -3,8 +3,12 @@ use std::time::Duration;
 use egui::*;
 use egui_notify::{Anchor, Toasts};
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::dap::DapServer;
 use crate::dasm::Disassembly;
 use crate::gameboy::Gameboy;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::gdb::GdbServer;
 use crate::governor::Governor;
 use crate::loader::Loader;
 use crate::symbols::Symbols;
@@ -17,38 +21,77 @@ pub struct App<'a> {
     gameboy: Gameboy,
     loader: Loader,
     disassembly: Option<Disassembly>,
-    bp_string: String,
+    breakpoints_state: BreakpointsState,
+    wp_string: String,
+    event_string: String,
     toasts: Toasts,
     memory_viewer_state: MemoryViewerState,
     disasm_panel_state: DisasmPanelState,
+    profiler_panel_state: ProfilerPanelState,
+    command_console_state: CommandConsoleState,
     console: String,
+    log: String,
     screen: ViewerContext<'a>,
     viewer_select_state: ViewerSelectState<'a>,
     developer_mode: bool,
     governor: Governor,
     last_toast: Instant,
     show_about: bool,
+    trace_path: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    gdb: Option<GdbServer>,
+    #[cfg(not(target_arch = "wasm32"))]
+    dap: Option<DapServer>,
 }
 
 impl<'a> App<'a> {
     pub fn new(gameboy: Gameboy, loader: Loader, symbols: Option<Symbols>) -> Self {
+        let trace_path = gameboy
+            .cpu
+            .trace
+            .as_ref()
+            .map_or("trace.log", |trace| trace.path())
+            .to_owned();
+        let source_map = loader.source_map().cloned();
         Self {
             gameboy,
             loader,
             disassembly: None,
-            bp_string: "c000".to_owned(),
+            breakpoints_state: BreakpointsState::default(),
+            wp_string: String::default(),
+            event_string: String::default(),
             toasts: Toasts::default().with_anchor(Anchor::TopRight),
             memory_viewer_state: MemoryViewerState::default(),
-            disasm_panel_state: DisasmPanelState::new(symbols),
+            disasm_panel_state: DisasmPanelState::new(symbols.clone()),
+            profiler_panel_state: ProfilerPanelState::default(),
+            command_console_state: CommandConsoleState::new(symbols, source_map),
             console: String::default(),
+            log: String::default(),
             screen: ViewerContext::default(),
             viewer_select_state: ViewerSelectState::default(),
             developer_mode: false,
             governor: Governor::default(),
             last_toast: Instant::now(),
             show_about: true,
+            trace_path,
+            #[cfg(not(target_arch = "wasm32"))]
+            gdb: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            dap: None,
         }
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_gdb(mut self, gdb: Option<GdbServer>) -> Self {
+        self.gdb = gdb;
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_dap(mut self, dap: Option<DapServer>) -> Self {
+        self.dap = dap;
+        self
+    }
 }
 
 impl eframe::App for App<'_> {
@@ -57,6 +100,42 @@ impl eframe::App for App<'_> {
 
         self.governor.tick(&mut self.gameboy, &mut self.console);
 
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(ref mut gdb) = self.gdb {
+            gdb.poll(&mut self.gameboy);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(ref mut dap) = self.dap {
+            if dap.poll(&mut self.gameboy, &mut self.loader) {
+                self.disassembly = None;
+                let symbols = self.loader.symbols().cloned();
+                self.disasm_panel_state = DisasmPanelState::new(symbols.clone());
+                self.command_console_state =
+                    CommandConsoleState::new(symbols, self.loader.source_map().cloned());
+            }
+        }
+
+        if let Some(ref mut debugger) = self.gameboy.debugger {
+            while let Some(event) = debugger.poll_event() {
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(ref mut dap) = self.dap {
+                    dap.output(&event.to_string());
+                }
+                self.toasts
+                    .error(format!("{}", event))
+                    .set_duration(Some(Duration::from_secs(10)));
+            }
+            while let Some(message) = debugger.poll_log() {
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(ref mut dap) = self.dap {
+                    dap.output(&message);
+                }
+                self.log.push_str(&message);
+                self.log.push('\n');
+            }
+        }
+
         About::new(&mut self.show_about).ctx(ctx);
 
         egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
@@ -73,48 +152,116 @@ impl eframe::App for App<'_> {
             Status::new(&mut self.toasts, &mut self.governor).ui(ui);
         });
 
+        // Registers and memory can be edited while the debugger is paused.
+        let paused = self
+            .gameboy
+            .debugger
+            .as_ref()
+            .is_some_and(|debugger| !debugger.is_running());
+
         if self.developer_mode {
             egui::SidePanel::left("dism_panel")
                 .min_width(250.)
                 .show(ctx, |ui| {
                     DisasmPanel::new(
                         &mut self.disassembly,
-                        &self.gameboy.mem,
+                        &mut self.gameboy.mem,
                         self.gameboy.cpu.pc,
+                        &mut self.gameboy.debugger,
                         &mut self.disasm_panel_state,
                         &mut self.toasts,
                     )
+                    .with_source_map(self.loader.source_map())
                     .ui(ui);
                     ui.separator();
                     Breakpoints::new(
                         &mut self.gameboy.debugger,
                         &mut self.toasts,
-                        &mut self.bp_string,
+                        &mut self.breakpoints_state,
+                        self.loader.symbols(),
+                    )
+                    .with_source_map(self.loader.source_map())
+                    .ui(ui);
+                    ui.separator();
+                    Watchpoints::new(
+                        &mut self.gameboy.debugger,
+                        &mut self.toasts,
+                        &mut self.wp_string,
+                    )
+                    .ui(ui);
+                    ui.separator();
+                    HardwareEvents::new(
+                        &mut self.gameboy.debugger,
+                        &mut self.toasts,
+                        &mut self.event_string,
                     )
                     .ui(ui);
                     ui.separator();
                     Callstack::new(&mut self.gameboy.debugger).ui(ui);
+                    ui.separator();
+                    References::new(
+                        &self.disassembly,
+                        &self.gameboy.mem,
+                        self.gameboy.cpu.pc,
+                        &self.gameboy.debugger,
+                        &mut self.disasm_panel_state,
+                        self.loader.symbols(),
+                    )
+                    .ui(ui);
                 });
 
             egui::SidePanel::right("cpu_panel")
                 .min_width(250.)
                 .show(ctx, |ui| {
-                    CpuPanel::new(&self.gameboy.cpu).ui(ui);
+                    CpuPanel::new(
+                        &mut self.gameboy.cpu,
+                        &mut self.trace_path,
+                        &mut self.toasts,
+                        paused,
+                    )
+                    .ui(ui);
+                    ui.separator();
+                    Registers::new(&mut self.gameboy.mem, paused).ui(ui);
                     ui.separator();
-                    Registers::new(&self.gameboy.mem).ui(ui);
+                    ProfilerPanel::new(
+                        &mut self.gameboy.debugger,
+                        &mut self.profiler_panel_state,
+                        &mut self.toasts,
+                        self.loader.symbols(),
+                    )
+                    .ui(ui);
                 });
 
             egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
                 ui.horizontal(|ui| {
                     MemoryViewer::new(
-                        &self.gameboy.mem,
+                        &mut self.gameboy.mem,
                         &self.gameboy.cpu,
                         &mut self.toasts,
                         &mut self.memory_viewer_state,
+                        paused,
+                        self.loader.symbols(),
+                    )
+                    .with_coverage(
+                        self.gameboy
+                            .debugger
+                            .as_ref()
+                            .map(|debugger| debugger.coverage()),
                     )
                     .ui(ui);
                     ui.separator();
-                    SerialConsole::new(&mut self.console).ui(ui);
+                    let size = vec2(ui.available_width() / 3., ui.available_height());
+                    ui.allocate_ui(size, |ui| SerialConsole::new(&mut self.console).ui(ui));
+                    ui.separator();
+                    let size = vec2(ui.available_width() / 2., ui.available_height());
+                    ui.allocate_ui(size, |ui| LogConsole::new(&mut self.log).ui(ui));
+                    ui.separator();
+                    CommandConsole::new(
+                        &mut self.gameboy,
+                        &self.loader,
+                        &mut self.command_console_state,
+                    )
+                    .ui(ui);
                 });
             });
         }
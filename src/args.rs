@@ -10,4 +10,25 @@ pub struct Args {
     /// Symbol file to load
     #[arg(short, long)]
     pub symbols: Option<String>,
+    /// Line info file mapping addresses to source lines
+    #[arg(short, long)]
+    pub lines: Option<String>,
+    /// Write a Gameboy Doctor instruction trace to this file
+    #[arg(short, long)]
+    pub trace: Option<String>,
+    /// Read LY as 0x90 while tracing, as the Gameboy Doctor reference logs do
+    #[arg(long, requires = "trace")]
+    pub trace_doctor: bool,
+    /// Listen for a GDB remote debugger on this localhost port
+    #[arg(short, long)]
+    pub gdb: Option<u16>,
+    /// Listen for a Debug Adapter Protocol client on this localhost port
+    #[arg(short, long)]
+    pub dap: Option<u16>,
+    /// Write the ROM as RGBDS assembly source to this file and exit
+    #[arg(long)]
+    pub export_asm: Option<String>,
+    /// Run without a window, reading debugger commands from stdin
+    #[arg(long)]
+    pub headless: bool,
 }
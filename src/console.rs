@@ -0,0 +1,556 @@
+//! Text commands for keyboard-driven debugging, shared by the command console
+//! panel and the headless REPL.
+//!
+//! A location is the first of these it can be read as:
+//!
+//! 1. A register, standing for its value: `x hl`, and `b c` breaks at the
+//!    value of C. Write `b 0c` for 0x000c.
+//! 2. A symbol name.
+//! 3. `file:line`, with line info.
+//! 4. A hex address such as `0150` or `01:4000`.
+//! 5. An expression, e.g. `hl + 2`.
+//!
+//! Values are expressions, where bare numbers are decimal outside brackets:
+//! `b 100` breaks at 0x0100, `p 100` prints 100 and `p [c0a0]` reads 0xc0a0.
+//! An empty line repeats the last step, so `s` followed by Enter keeps
+//! stepping.
+
+use std::fmt::Write;
+
+use crate::address::Address;
+use crate::dasm::{Analysis, Disassembly};
+use crate::debugger::{Breakpoint, Command, Expr, Register, Watchpoint, IO_REGISTERS};
+use crate::gameboy::Gameboy;
+use crate::loader::Loader;
+use crate::memory::ProgramMemory;
+use crate::source_map::SourceMap;
+use crate::symbols::Symbols;
+
+const HELP: &str = "\
+b <loc> [if <cond>]     set a breakpoint
+d <loc>                 delete a breakpoint
+wp <r|w|rw> <addr>[-<end>] [== <value>|changed]
+                        set a watchpoint
+d wp <watchpoint>       delete a watchpoint
+info b|wp|r             list breakpoints, watchpoints or registers
+c                       continue
+pause                   pause
+s                       step one instruction
+n                       step over calls
+finish                  run until the current function returns
+until <loc>             run to a location
+bt                      show the callstack
+x[/<count>] <loc>       dump memory
+disas [<loc> [<count>]] disassemble
+list [<loc>]            show the source around a location
+p <expr>                print an expression
+coverage on|off         start or stop recording code coverage
+coverage <file>         write a code coverage report
+set <reg|[addr]|io>=<expr>
+                        write a register or memory
+reset                   reset the Gameboy
+q                       quit (headless only)
+
+<loc> is a register's value, symbol, file:line, hex address or expression,
+whichever comes first: b c breaks at C's value, b 0c at 0x000c.
+<expr> numbers are decimal except inside [...]: p 100 is 100.";
+
+/// Commands an empty line repeats.
+const REPEATABLE: &[&str] = &["s", "step", "n", "next", "finish"];
+
+/// Bytes shown by `x` without a count.
+const DUMP_BYTES: usize = 16;
+/// Instructions shown by `disas` without a count.
+const DISAS_COUNT: usize = 10;
+/// Source lines shown by `list` before the location.
+const LIST_BEFORE: usize = 4;
+/// Source lines shown by `list` in total.
+const LIST_COUNT: usize = 10;
+
+#[derive(Default)]
+pub struct Console {
+    symbols: Option<Symbols>,
+    source_map: Option<SourceMap>,
+    /// Last step command, repeated by an empty line.
+    last_command: String,
+    /// A command resumed the game; report where it stops.
+    waiting: bool,
+}
+
+impl Console {
+    pub fn new(symbols: Option<Symbols>) -> Self {
+        Self {
+            symbols,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_source_map(mut self, source_map: Option<SourceMap>) -> Self {
+        self.source_map = source_map;
+        self
+    }
+
+    /// Run a command, returning its output.
+    pub fn execute(
+        &mut self,
+        line: &str,
+        gameboy: &mut Gameboy,
+        loader: &Loader,
+    ) -> Result<String, String> {
+        let line = match line.trim() {
+            "" => self.last_command.clone(),
+            line => line.to_owned(),
+        };
+        let (command, args) = line.split_once(' ').unwrap_or((&line, ""));
+        let args = args.trim();
+        if REPEATABLE.contains(&command) {
+            self.last_command.clone_from(&line);
+        }
+
+        if let Some(count) = command.strip_prefix("x") {
+            let count = match count.strip_prefix('/') {
+                Some(count) => count
+                    .parse()
+                    .map_err(|_| format!("invalid count {:?}", count))?,
+                None if count.is_empty() => DUMP_BYTES,
+                None => return Err(format!("unknown command {:?}", command)),
+            };
+            let addr = self.address(args, gameboy)?;
+            return Ok(Self::dump(gameboy, addr.addr, count));
+        }
+
+        match command {
+            "help" | "h" => Ok(HELP.to_owned()),
+            "b" | "break" => {
+                let (loc, condition) = match args.split_once(" if ") {
+                    Some((loc, condition)) => (loc, Some(condition)),
+                    None => (args, None),
+                };
+                let mut breakpoint = Breakpoint::new(self.address(loc, gameboy)?);
+                if let Some(condition) = condition {
+                    breakpoint = breakpoint.with_condition(condition)?;
+                }
+                let message = format!("Breakpoint at {}", breakpoint);
+                Self::command(gameboy, Command::InsertBreakpoint(breakpoint))?;
+                Ok(message)
+            }
+            "d" | "delete" => {
+                if let Some(spec) = args.strip_prefix("wp ") {
+                    let watchpoint: Watchpoint = spec.parse()?;
+                    Self::command(gameboy, Command::RemoveWatchpoint(watchpoint))?;
+                } else {
+                    let addr = self.address(args, gameboy)?;
+                    Self::command(gameboy, Command::RemoveBreakpoint(addr))?;
+                }
+                Ok(String::new())
+            }
+            "wp" | "watch" => {
+                let watchpoint: Watchpoint = args.parse()?;
+                let message = format!("Watchpoint {}", watchpoint);
+                Self::command(gameboy, Command::InsertWatchpoint(watchpoint))?;
+                Ok(message)
+            }
+            "info" | "i" => match args {
+                "b" | "break" => {
+                    let debugger = gameboy.debugger.as_ref().ok_or("no debugger attached")?;
+                    Ok(debugger
+                        .get_breakpoints()
+                        .map(|bp| bp.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"))
+                }
+                "wp" | "watch" => {
+                    let debugger = gameboy.debugger.as_ref().ok_or("no debugger attached")?;
+                    Ok(debugger
+                        .get_watchpoints()
+                        .iter()
+                        .map(|wp| wp.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"))
+                }
+                "r" | "registers" => Ok(Self::registers(gameboy)),
+                _ => Err("expected info b, wp or r".to_owned()),
+            },
+            "c" | "continue" => self.resume(gameboy, Command::Continue),
+            "pause" => self.resume(gameboy, Command::Pause),
+            "s" | "step" => self.resume(gameboy, Command::Step),
+            "n" | "next" => self.resume(gameboy, Command::StepOver),
+            "finish" => self.resume(gameboy, Command::StepOut),
+            "until" | "u" => {
+                let addr = self.address(args, gameboy)?;
+                self.resume(gameboy, Command::RunTo(addr))
+            }
+            "bt" | "backtrace" => {
+                let debugger = gameboy.debugger.as_ref().ok_or("no debugger attached")?;
+                let mut output = String::new();
+                for (idx, call) in debugger.get_callstack().iter().rev().enumerate() {
+                    writeln!(output, "#{} {}", idx, call).unwrap();
+                }
+                Ok(output.trim_end().to_owned())
+            }
+            "disas" => {
+                let mut words = args.split_whitespace();
+                let addr = match words.next() {
+                    Some(loc) => self.address(loc, gameboy)?.addr,
+                    None => gameboy.cpu.pc,
+                };
+                let count = match words.next() {
+                    Some(count) => count
+                        .parse()
+                        .map_err(|_| format!("invalid count {:?}", count))?,
+                    None => DISAS_COUNT,
+                };
+                let pc = gameboy.cpu.pc;
+                Ok(
+                    Disassembly::decode(&gameboy.mem, addr, count, self.symbols.as_ref())
+                        .into_iter()
+                        .map(|instr| {
+                            let marker = if instr.addr == pc { "=>" } else { "  " };
+                            format!("{} {}", marker, instr)
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                )
+            }
+            "l" | "list" => {
+                let addr = match args {
+                    "" => Address::current(gameboy.cpu.pc, &gameboy.mem),
+                    loc => self.address(loc, gameboy)?,
+                };
+                self.list(addr)
+            }
+            "p" | "print" => {
+                let value = self.value(args, gameboy)?;
+                Ok(format!("0x{:04x} ({})", value, value))
+            }
+            "set" => {
+                let (target, value) = args.split_once('=').ok_or("expected <target>=<value>")?;
+                let target = target.trim();
+                let value = self.value(value, gameboy)?;
+                self.set(gameboy, target, value)?;
+                Ok(String::new())
+            }
+            "coverage" => match args {
+                "" => Err("expected on, off or a file to write the report to".to_owned()),
+                "on" | "off" => {
+                    Self::command(gameboy, Command::Coverage(args == "on"))?;
+                    Ok(format!("Coverage {}", args))
+                }
+                path => {
+                    self.coverage(gameboy, path)?;
+                    Ok(format!("Wrote {}", path))
+                }
+            },
+            "reset" => {
+                loader
+                    .reset_gameboy(gameboy)
+                    .map_err(|e| format!("reset: {}", e))?;
+                Ok("Reset".to_owned())
+            }
+            _ => Err(format!("unknown command {:?}, try help", command)),
+        }
+    }
+
+    /// Where the game stopped, once a command that resumed it has paused.
+    pub fn poll(&mut self, gameboy: &Gameboy) -> Option<String> {
+        let paused = gameboy
+            .debugger
+            .as_ref()
+            .is_some_and(|debugger| debugger.is_paused());
+        if !self.waiting || !paused {
+            return None;
+        }
+        self.waiting = false;
+        let pc = gameboy.cpu.pc;
+        let stopped = Disassembly::decode(&gameboy.mem, pc, 1, self.symbols.as_ref())
+            .first()
+            .map(|instr| format!("Stopped at {}", instr))?;
+        let location = self
+            .source_map
+            .as_ref()
+            .and_then(|source_map| source_map.location(Address::current(pc, &gameboy.mem)));
+        match location {
+            Some((file, line)) => Some(format!(
+                "{}\n{}:{}  {}",
+                stopped,
+                file.path,
+                line,
+                file.line(line).unwrap_or_default().trim()
+            )),
+            None => Some(stopped),
+        }
+    }
+
+    /// Source lines around the one `addr` was assembled from.
+    fn list(&self, addr: Address) -> Result<String, String> {
+        let source_map = self.source_map.as_ref().ok_or("no line info loaded")?;
+        let (file, current) = source_map
+            .location(addr)
+            .ok_or_else(|| format!("no source line for {}", addr))?;
+        if file.is_empty() {
+            return Ok(format!("{}:{}", file.path, current));
+        }
+        let start = current.saturating_sub(LIST_BEFORE).max(1);
+        let mut output = String::new();
+        for line in start..(start + LIST_COUNT).min(file.len() + 1) {
+            let marker = if line == current { "=>" } else { "  " };
+            let text = file.line(line).unwrap_or_default();
+            writeln!(output, "{} {:>4} {}", marker, line, text).unwrap();
+        }
+        Ok(output.trim_end().to_owned())
+    }
+
+    /// Write the code coverage so far, of the code found by analysis from
+    /// what was executed.
+    fn coverage(&self, gameboy: &Gameboy, path: &str) -> Result<(), String> {
+        let debugger = gameboy.debugger.as_ref().ok_or("no debugger attached")?;
+        let executed = debugger.get_executed().iter().copied();
+        let analysis = Analysis::new(&gameboy.mem, executed);
+        let mut out = Vec::new();
+        debugger
+            .coverage()
+            .report(
+                &gameboy.mem,
+                &analysis,
+                self.symbols.as_ref(),
+                self.source_map.as_ref(),
+                &mut out,
+            )
+            .and_then(|()| std::fs::write(path, out))
+            .map_err(|e| format!("could not write {}: {}", path, e))
+    }
+
+    fn command(gameboy: &mut Gameboy, command: Command) -> Result<(), String> {
+        let debugger = gameboy.debugger.as_mut().ok_or("no debugger attached")?;
+        debugger.command(command);
+        Ok(())
+    }
+
+    fn resume(&mut self, gameboy: &mut Gameboy, command: Command) -> Result<String, String> {
+        Self::command(gameboy, command)?;
+        self.waiting = true;
+        Ok(String::new())
+    }
+
+    /// Symbol address or expression value.
+    fn value(&self, s: &str, gameboy: &Gameboy) -> Result<u16, String> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("expected a location or value".to_owned());
+        }
+        if let Some(addr) = self
+            .symbols
+            .as_ref()
+            .and_then(|symbols| symbols.get_address(s))
+        {
+            return Ok(addr.addr);
+        }
+        let expr: Expr = s.parse()?;
+        Ok(expr.eval(&gameboy.cpu, &gameboy.mem))
+    }
+
+    /// Location given as a register, symbol, hex address such as `0150` or
+    /// `01:4000`, `file:line`, or expression.
+    fn address(&self, s: &str, gameboy: &Gameboy) -> Result<Address, String> {
+        let s = s.trim();
+        if let Ok(register) = s.parse::<Register>() {
+            return Ok(Address::new(register.get(&gameboy.cpu)));
+        }
+        if let Some(addr) = self
+            .symbols
+            .as_ref()
+            .and_then(|symbols| symbols.get_address(s))
+        {
+            return Ok(addr);
+        }
+        // File names have an extension, which tells them from banks.
+        let is_location = s
+            .rsplit_once(':')
+            .is_some_and(|(name, line)| name.contains('.') && line.parse::<usize>().is_ok());
+        if let (Some(source_map), true) = (&self.source_map, is_location) {
+            return source_map
+                .parse_location(s)
+                .ok_or_else(|| format!("no code at or after {}", s));
+        }
+        s.parse()
+            .or_else(|_| self.value(s, gameboy).map(Address::new))
+    }
+
+    fn set(&self, gameboy: &mut Gameboy, target: &str, value: u16) -> Result<(), String> {
+        if let Ok(register) = target.parse::<Register>() {
+            register.set(&mut gameboy.cpu, value);
+            return Ok(());
+        }
+        let addr = if let Some(addr) = target
+            .strip_prefix('[')
+            .and_then(|target| target.strip_suffix(']'))
+        {
+            self.address(addr, gameboy)?.addr
+        } else if let Some((_, addr)) = IO_REGISTERS.iter().find(|(name, _)| *name == target) {
+            *addr
+        } else {
+            self.symbols
+                .as_ref()
+                .and_then(|symbols| symbols.get_address(target))
+                .ok_or_else(|| format!("can't set {:?}", target))?
+                .addr
+        };
+        ProgramMemory::set_u8(&mut gameboy.mem, addr, value as u8);
+        Ok(())
+    }
+
+    fn dump(gameboy: &Gameboy, addr: u16, count: usize) -> String {
+        let mut output = String::new();
+        for row in (0..count).step_by(16) {
+            let start = addr.wrapping_add(row as u16);
+            write!(output, "0x{:04x}:", start).unwrap();
+            for i in 0..16.min(count - row) {
+                let value = ProgramMemory::get_u8(&gameboy.mem, start.wrapping_add(i as u16));
+                write!(output, " {:02x}", value).unwrap();
+            }
+            output.push('\n');
+        }
+        output.trim_end().to_owned()
+    }
+
+    fn registers(gameboy: &Gameboy) -> String {
+        let cpu = &gameboy.cpu;
+        format!(
+            "af=0x{:04x} bc=0x{:04x} de=0x{:04x} hl=0x{:04x} sp=0x{:04x} pc=0x{:04x} ime={} flags={}",
+            cpu.get_af(),
+            cpu.get_bc(),
+            cpu.get_de(),
+            cpu.get_hl(),
+            cpu.sp,
+            cpu.pc,
+            u8::from(cpu.ie),
+            cpu.f
+        )
+    }
+}
+
+/// Run the game without a window, reading commands from stdin. The game
+/// starts paused so breakpoints can be set first.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn repl(mut gameboy: Gameboy, loader: Loader, symbols: Option<Symbols>) -> std::io::Result<()> {
+    use std::io::BufRead;
+    use std::sync::mpsc::{self, TryRecvError};
+
+    // M-cycles run between checks for input while the game is running.
+    const CHUNK: usize = 17556;
+
+    let mut console = Console::new(symbols).with_source_map(loader.source_map().cloned());
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    if let Some(debugger) = gameboy.debugger.as_mut() {
+        debugger.command(Command::Pause);
+    }
+    let mut prompted = false;
+    loop {
+        let paused = gameboy
+            .debugger
+            .as_ref()
+            .is_none_or(|debugger| debugger.is_paused());
+        let line = if paused {
+            if !prompted {
+                print!("(egb) ");
+                std::io::Write::flush(&mut std::io::stdout())?;
+                prompted = true;
+            }
+            match rx.recv() {
+                Ok(line) => Some(line),
+                Err(_) => return Ok(()),
+            }
+        } else {
+            match rx.try_recv() {
+                Ok(line) => Some(line),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        };
+        if let Some(line) = line {
+            prompted = false;
+            if matches!(line.trim(), "q" | "quit") {
+                return Ok(());
+            }
+            match console.execute(&line, &mut gameboy, &loader) {
+                Ok(output) if output.is_empty() => (),
+                Ok(output) => println!("{}", output),
+                Err(e) => println!("Error: {}", e),
+            }
+        }
+
+        for _ in 0..CHUNK {
+            if let Some(c) = gameboy.tick() {
+                print!("{}", c as char);
+            }
+        }
+        if let Some(debugger) = gameboy.debugger.as_mut() {
+            while let Some(event) = debugger.poll_event() {
+                println!("{}", event);
+            }
+            while let Some(message) = debugger.poll_log() {
+                println!("{}", message);
+            }
+        }
+        if let Some(stop) = console.poll(&gameboy) {
+            println!("{}", stop);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Memory;
+
+    fn gameboy() -> Gameboy {
+        let mut gameboy = Gameboy::new(Memory::from(vec![0; 0x8000]));
+        gameboy.cpu.c = 0x42;
+        gameboy.cpu.set_de(0x1234);
+        gameboy
+    }
+
+    fn console() -> Console {
+        Console::new(Some("00:0150 cafe\n".parse().unwrap()))
+    }
+
+    #[test]
+    fn location_precedence() {
+        let (console, gameboy) = (console(), gameboy());
+        let address = |s| console.address(s, &gameboy).map(|addr| addr.addr);
+        // Registers first, then symbols, then hex.
+        assert_eq!(address("c"), Ok(0x0042));
+        assert_eq!(address("de"), Ok(0x1234));
+        assert_eq!(address("cafe"), Ok(0x0150));
+        assert_eq!(address("0c"), Ok(0x000c));
+        assert_eq!(address("0xde"), Ok(0x00de));
+        assert_eq!(address("100"), Ok(0x0100));
+        assert_eq!(address("cafd"), Ok(0xcafd));
+        assert_eq!(address("de + 2"), Ok(0x1236));
+        let banked = console.address("01:4000", &gameboy).unwrap();
+        assert_eq!(banked, Address::banked(1, 0x4000));
+    }
+
+    #[test]
+    fn values_are_decimal_outside_brackets() {
+        let (console, mut gameboy) = (console(), gameboy());
+        ProgramMemory::set_u8(&mut gameboy.mem, 0xc0a0_u16, 7);
+        let value = |s| console.value(s, &gameboy);
+        assert_eq!(value("100"), Ok(100));
+        assert_eq!(value("0x100"), Ok(0x100));
+        assert_eq!(value("[c0a0]"), Ok(7));
+        assert_eq!(value("cafe"), Ok(0x0150));
+        assert!(value("0150").is_err());
+    }
+}
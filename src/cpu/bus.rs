@@ -0,0 +1,89 @@
+use std::cell::Cell;
+
+use crate::memory::{Memory, ProgramMemory};
+use crate::memory_map::MemoryMap;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccessKind {
+    Read,
+    Write { old: u8 },
+}
+
+/// A single memory access made by the CPU.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Access {
+    /// Address of the instruction that made the access.
+    pub pc: u16,
+    pub addr: u16,
+    /// Bank mapped at `addr` when it was accessed.
+    pub bank: Option<u16>,
+    pub value: u8,
+    pub kind: AccessKind,
+}
+
+/// The CPU's view of memory. Records the access made during an M-cycle so
+/// it can be inspected by the debugger.
+pub struct Bus<'a> {
+    mem: &'a mut Memory,
+    pc: u16,
+    access: Cell<Option<Access>>,
+    /// LY reads as 0x90.
+    stub_ly: bool,
+}
+
+impl<'a> Bus<'a> {
+    pub fn new(mem: &'a mut Memory, pc: u16) -> Self {
+        Self {
+            mem,
+            pc,
+            access: Cell::new(None),
+            stub_ly: false,
+        }
+    }
+
+    /// Read LY as 0x90, as in the logs Gameboy Doctor compares traces to.
+    pub fn with_stub_ly(mut self, stub_ly: bool) -> Self {
+        self.stub_ly = stub_ly;
+        self
+    }
+
+    pub fn access(&self) -> Option<Access> {
+        self.access.get()
+    }
+}
+
+impl ProgramMemory for Bus<'_> {
+    fn set_u8(&mut self, address: impl Into<u16>, value: u8) {
+        let addr = address.into();
+        let old = ProgramMemory::get_u8(self.mem, addr);
+        self.access.set(Some(Access {
+            pc: self.pc,
+            addr,
+            bank: self.mem.bank(addr),
+            value,
+            kind: AccessKind::Write { old },
+        }));
+        ProgramMemory::set_u8(self.mem, addr, value);
+    }
+
+    fn get_u8(&self, address: impl Into<u16>) -> u8 {
+        let addr = address.into();
+        let value = if self.stub_ly && addr == u16::from(MemoryMap::LY) {
+            0x90
+        } else {
+            ProgramMemory::get_u8(self.mem, addr)
+        };
+        self.access.set(Some(Access {
+            pc: self.pc,
+            addr,
+            bank: self.mem.bank(addr),
+            value,
+            kind: AccessKind::Read,
+        }));
+        value
+    }
+
+    fn bank(&self, address: u16) -> Option<u16> {
+        self.mem.bank(address)
+    }
+}
@@ -1,10 +1,13 @@
-use crate::cpu::{Cpu, Cycles};
+use crate::cpu::{Cpu, Step};
 use crate::memory::ProgramMemory;
 use crate::registers::CpuFlags;
 
 macro_rules! add16 {
     ( $name:ident, $src:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &mut impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &mut impl ProgramMemory) -> Step {
+            if cpu.m_cycle < 1 {
+                return Step::Continue;
+            }
             let l = cpu.get_hl() as u32;
             let r = cpu.$src() as u32;
             let result = l + r;
@@ -13,8 +16,7 @@ macro_rules! add16 {
             cpu.f.set(CpuFlags::H, h);
             cpu.f.set(CpuFlags::C, result & 0x10000 != 0);
             cpu.set_hl(result as u16);
-            cpu.pc += 1;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -23,9 +25,17 @@ add16!(op19, get_de);
 add16!(op29, get_hl);
 add16!(op39, get_sp);
 
-pub fn ope8(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
+pub fn ope8(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+            return Step::Continue;
+        }
+        3 => (),
+        _ => return Step::Continue,
+    }
     let l = cpu.get_sp();
-    let r_u8 = mem.get_u8(cpu.pc.wrapping_add(1));
+    let r_u8 = cpu.z;
     let r: i16 = (r_u8 as i8).into();
 
     let result = l.wrapping_add_signed(r);
@@ -44,17 +54,18 @@ pub fn ope8(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
     cpu.f.set(CpuFlags::H, h);
 
     cpu.sp = result;
-    cpu.pc += 2;
-    Cycles(16)
+    Step::Done
 }
 
 macro_rules! inc16 {
     ( $name:ident, $dst:ident, $src:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
+            if cpu.m_cycle < 1 {
+                return Step::Continue;
+            }
             let val = cpu.$src().wrapping_add(1);
             cpu.$dst(val);
-            cpu.pc += 1;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -65,11 +76,13 @@ inc16!(op33, set_sp, get_sp);
 
 macro_rules! dec16 {
     ( $name:ident, $dst:ident, $src:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
+            if cpu.m_cycle < 1 {
+                return Step::Continue;
+            }
             let val = cpu.$src().wrapping_sub(1);
             cpu.$dst(val);
-            cpu.pc += 1;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -1,37 +1,40 @@
-use crate::cpu::{Cpu, Cycles};
+use crate::cpu::{Cpu, Step};
 use crate::memory::ProgramMemory;
 use crate::registers::CpuFlags;
 
 macro_rules! op8_reg {
     ( $name:ident, $op:ident, $src:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             let l = cpu.a;
             let r = cpu.$src;
             cpu.a = $op(&mut cpu.f, l, r);
-            cpu.pc += 1;
-            Cycles(4)
+            Step::Done
         }
     };
 }
 macro_rules! op8_mem {
     ( $name:ident, $op:ident ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            if cpu.m_cycle < 1 {
+                return Step::Continue;
+            }
             let l = cpu.a;
             let r = mem.get_u8(cpu.get_hl());
             cpu.a = $op(&mut cpu.f, l, r);
-            cpu.pc += 1;
-            Cycles(8)
+            Step::Done
         }
     };
 }
 macro_rules! op8_imm {
     ( $name:ident, $op:ident ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            if cpu.m_cycle < 1 {
+                return Step::Continue;
+            }
             let l = cpu.a;
             let r = mem.get_u8(cpu.pc.wrapping_add(1));
             cpu.a = $op(&mut cpu.f, l, r);
-            cpu.pc += 2;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -183,34 +186,37 @@ op8_imm!(opee, xor8);
 
 macro_rules! cp8_reg {
     ( $name:ident, $src:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             let l = cpu.a;
             let r = cpu.$src;
             cp8(&mut cpu.f, l, r);
-            cpu.pc += 1;
-            Cycles(4)
+            Step::Done
         }
     };
 }
 macro_rules! cp8_mem {
     ( $name:ident ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            if cpu.m_cycle < 1 {
+                return Step::Continue;
+            }
             let l = cpu.a;
             let r = mem.get_u8(cpu.get_hl());
             cp8(&mut cpu.f, l, r);
-            cpu.pc += 1;
-            Cycles(8)
+            Step::Done
         }
     };
 }
 macro_rules! cp8_imm {
     ( $name:ident ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            if cpu.m_cycle < 1 {
+                return Step::Continue;
+            }
             let l = cpu.a;
             let r = mem.get_u8(cpu.pc.wrapping_add(1));
             cp8(&mut cpu.f, l, r);
-            cpu.pc += 2;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -242,10 +248,9 @@ fn inc_flags(flags: &mut CpuFlags, input: u8) -> u8 {
 }
 macro_rules! inc_reg {
     ( $name:ident, $dst:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             cpu.$dst = inc_flags(&mut cpu.f, cpu.$dst);
-            cpu.pc += 1;
-            Cycles(4)
+            Step::Done
         }
     };
 }
@@ -256,11 +261,19 @@ inc_reg!(op14, d);
 inc_reg!(op1c, e);
 inc_reg!(op24, h);
 inc_reg!(op2c, l);
-pub fn op34(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-    let input = mem.get_u8(cpu.get_hl());
-    mem.set_u8(cpu.get_hl(), inc_flags(&mut cpu.f, input));
-    cpu.pc += 1;
-    Cycles(12)
+pub fn op34(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.get_hl());
+            Step::Continue
+        }
+        2 => {
+            let input = cpu.z;
+            mem.set_u8(cpu.get_hl(), inc_flags(&mut cpu.f, input));
+            Step::Done
+        }
+        _ => Step::Continue,
+    }
 }
 
 #[inline]
@@ -273,10 +286,9 @@ fn dec_flags(flags: &mut CpuFlags, input: u8) -> u8 {
 }
 macro_rules! dec_reg {
     ( $name:ident, $dst:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             cpu.$dst = dec_flags(&mut cpu.f, cpu.$dst);
-            cpu.pc += 1;
-            Cycles(4)
+            Step::Done
         }
     };
 }
@@ -287,9 +299,17 @@ dec_reg!(op15, d);
 dec_reg!(op1d, e);
 dec_reg!(op25, h);
 dec_reg!(op2d, l);
-pub fn op35(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-    let input = mem.get_u8(cpu.get_hl());
-    mem.set_u8(cpu.get_hl(), dec_flags(&mut cpu.f, input));
-    cpu.pc += 1;
-    Cycles(12)
+pub fn op35(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.get_hl());
+            Step::Continue
+        }
+        2 => {
+            let input = cpu.z;
+            mem.set_u8(cpu.get_hl(), dec_flags(&mut cpu.f, input));
+            Step::Done
+        }
+        _ => Step::Continue,
+    }
 }
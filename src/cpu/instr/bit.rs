@@ -1,26 +1,27 @@
-use crate::cpu::{Cpu, Cycles};
+use crate::cpu::{Cpu, Step};
 use crate::memory::ProgramMemory;
 use crate::registers::CpuFlags;
 
 macro_rules! bit {
     ( $name:ident, hl, $bit:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            if cpu.m_cycle < 2 {
+                return Step::Continue;
+            }
             let reg = mem.get_u8(cpu.get_hl()) & 1 << $bit;
             cpu.f.set(CpuFlags::Z, reg == 0);
             cpu.f.set(CpuFlags::N, false);
             cpu.f.set(CpuFlags::H, true);
-            cpu.pc += 2;
-            Cycles(16)
+            Step::Done
         }
     };
     ( $name:ident, $dst:ident, $bit:literal ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             let reg = cpu.$dst & 1 << $bit;
             cpu.f.set(CpuFlags::Z, reg == 0);
             cpu.f.set(CpuFlags::N, false);
             cpu.f.set(CpuFlags::H, true);
-            cpu.pc += 2;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -91,17 +92,24 @@ bit!(opcb7e, hl, 7);
 
 macro_rules! set {
     ( $name:ident, hl, $bit:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            mem.set_u8(cpu.get_hl(), mem.get_u8(cpu.get_hl()) | 1 << $bit);
-            cpu.pc += 2;
-            Cycles(16)
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                2 => {
+                    cpu.z = mem.get_u8(cpu.get_hl());
+                    Step::Continue
+                }
+                3 => {
+                    mem.set_u8(cpu.get_hl(), cpu.z | 1 << $bit);
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
     ( $name:ident, $dst:ident, $bit:literal ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             cpu.$dst |= 1 << $bit;
-            cpu.pc += 2;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -172,19 +180,24 @@ set!(opcbfe, hl, 7);
 
 macro_rules! res {
     ( $name:ident, hl, $bit:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            let mut reg = mem.get_u8(cpu.get_hl());
-            reg &= !(1 << $bit);
-            mem.set_u8(cpu.get_hl(), reg);
-            cpu.pc += 2;
-            Cycles(16)
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                2 => {
+                    cpu.z = mem.get_u8(cpu.get_hl());
+                    Step::Continue
+                }
+                3 => {
+                    mem.set_u8(cpu.get_hl(), cpu.z & !(1 << $bit));
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
     ( $name:ident, $dst:ident, $bit:literal ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             cpu.$dst &= !(1 << $bit);
-            cpu.pc += 2;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -1,24 +1,52 @@
-use std::ops::Not;
-
-use crate::cpu::{Cpu, CpuFlags, Cycles};
+use crate::cpu::{Cpu, CpuFlags, Step};
 use crate::memory::ProgramMemory;
 
-pub fn opc3(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-    let addr = mem.get_u16(cpu.pc.wrapping_add(1));
-    cpu.pc = addr;
-    Cycles(16)
+#[inline]
+fn jr_target(cpu: &Cpu) -> u16 {
+    let n_i8 = cpu.z as i8;
+    cpu.pc.wrapping_add(2).wrapping_add_signed(n_i8.into())
+}
+
+pub fn opc3(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+            Step::Continue
+        }
+        2 => {
+            cpu.w = mem.get_u8(cpu.pc.wrapping_add(2));
+            Step::Continue
+        }
+        3 => {
+            cpu.pc = cpu.get_wz();
+            Step::Done
+        }
+        _ => Step::Continue,
+    }
 }
 
 macro_rules! jp_cc {
     ( $name:ident, $bit:ident, $tf:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-            if cpu.f.contains(CpuFlags::$bit) == $tf {
-                let addr = mem.get_u16(cpu.pc.wrapping_add(1));
-                cpu.pc = addr;
-                Cycles(16)
-            } else {
-                cpu.pc += 3;
-                Cycles(12)
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                1 => {
+                    cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+                    Step::Continue
+                }
+                2 => {
+                    cpu.w = mem.get_u8(cpu.pc.wrapping_add(2));
+                    if cpu.f.contains(CpuFlags::$bit) == $tf {
+                        Step::Continue
+                    } else {
+                        cpu.pc = cpu.pc.wrapping_add(3);
+                        Step::Done
+                    }
+                }
+                3 => {
+                    cpu.pc = cpu.get_wz();
+                    Step::Done
+                }
+                _ => Step::Continue,
             }
         }
     };
@@ -28,43 +56,44 @@ jp_cc!(opca, Z, true);
 jp_cc!(opd2, C, false);
 jp_cc!(opda, C, true);
 
-pub fn ope9(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+pub fn ope9(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
     cpu.pc = cpu.get_hl();
-    Cycles(4)
+    Step::Done
 }
 
-pub fn op18(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-    let n_u8 = mem.get_u8(cpu.pc.wrapping_add(1));
-    let n_u16 = n_u8 as u16;
-    let n_i8 = n_u8 as i8;
-    if n_i8 < 0 {
-        cpu.pc -= n_u8.not().wrapping_add(1) as u16;
-    } else {
-        cpu.pc += n_u16;
+pub fn op18(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+            Step::Continue
+        }
+        2 => {
+            cpu.pc = jr_target(cpu);
+            Step::Done
+        }
+        _ => Step::Continue,
     }
-    cpu.pc += 2;
-    Cycles(12)
 }
 
 macro_rules! jp_cc_n {
     ( $name:ident, $bit:ident, $tf:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-            let cycles = if cpu.f.contains(CpuFlags::$bit) == $tf {
-                let n_u8 = mem.get_u8(cpu.pc.wrapping_add(1));
-                let n_u16 = n_u8 as u16;
-                let n_i8 = n_u8 as i8;
-                if n_i8 < 0 {
-                    cpu.pc -= n_u8.not().wrapping_add(1) as u16;
-                } else {
-                    cpu.pc += n_u16;
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                1 => {
+                    cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+                    if cpu.f.contains(CpuFlags::$bit) == $tf {
+                        Step::Continue
+                    } else {
+                        cpu.pc = cpu.pc.wrapping_add(2);
+                        Step::Done
+                    }
                 }
-                Cycles(20)
-            } else {
-                Cycles(8)
-            };
-
-            cpu.pc += 2;
-            cycles
+                2 => {
+                    cpu.pc = jr_target(cpu);
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
 }
@@ -73,28 +102,69 @@ jp_cc_n!(op28, Z, true);
 jp_cc_n!(op30, C, false);
 jp_cc_n!(op38, C, true);
 
-pub fn opcd(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-    cpu.sp -= 2;
-    let next_inst = cpu.pc.wrapping_add(3);
-    mem.set_u16(cpu.sp, next_inst);
-    let addr = mem.get_u16(cpu.pc.wrapping_add(1));
-    cpu.pc = addr;
-    Cycles(24)
+// Pushes the return address over two M-cycles, high byte first, then
+// jumps. Shared by call, call cc and rst.
+#[inline]
+fn push_pc_and_jump(
+    cpu: &mut Cpu,
+    mem: &mut impl ProgramMemory,
+    first_cycle: u8,
+    len: u16,
+    target: u16,
+) -> Step {
+    let next_inst = cpu.pc.wrapping_add(len);
+    if cpu.m_cycle == first_cycle {
+        cpu.sp = cpu.sp.wrapping_sub(1);
+        mem.set_u8(cpu.sp, (next_inst >> 8) as u8);
+        Step::Continue
+    } else {
+        cpu.sp = cpu.sp.wrapping_sub(1);
+        mem.set_u8(cpu.sp, next_inst as u8);
+        cpu.pc = target;
+        Step::Done
+    }
+}
+
+pub fn opcd(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+            Step::Continue
+        }
+        2 => {
+            cpu.w = mem.get_u8(cpu.pc.wrapping_add(2));
+            Step::Continue
+        }
+        4 | 5 => {
+            let target = cpu.get_wz();
+            push_pc_and_jump(cpu, mem, 4, 3, target)
+        }
+        _ => Step::Continue,
+    }
 }
 
 macro_rules! call_cc_n {
     ( $name:ident, $bit:ident, $tf:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            if cpu.f.contains(CpuFlags::$bit) == $tf {
-                cpu.sp -= 2;
-                let next_inst = cpu.pc.wrapping_add(3);
-                mem.set_u16(cpu.sp, next_inst);
-                let addr = mem.get_u16(cpu.pc.wrapping_add(1));
-                cpu.pc = addr;
-                Cycles(24)
-            } else {
-                cpu.pc += 3;
-                Cycles(12)
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                1 => {
+                    cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+                    Step::Continue
+                }
+                2 => {
+                    cpu.w = mem.get_u8(cpu.pc.wrapping_add(2));
+                    if cpu.f.contains(CpuFlags::$bit) == $tf {
+                        Step::Continue
+                    } else {
+                        cpu.pc = cpu.pc.wrapping_add(3);
+                        Step::Done
+                    }
+                }
+                4 | 5 => {
+                    let target = cpu.get_wz();
+                    push_pc_and_jump(cpu, mem, 4, 3, target)
+                }
+                _ => Step::Continue,
             }
         }
     };
@@ -106,12 +176,11 @@ call_cc_n!(opdc, C, true);
 
 macro_rules! rst {
     ( $name:ident, $offset:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            cpu.sp -= 2;
-            let next_inst = cpu.pc.wrapping_add(1);
-            mem.set_u16(cpu.sp, next_inst);
-            cpu.pc = $offset;
-            Cycles(16)
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                2 | 3 => push_pc_and_jump(cpu, mem, 2, 1, $offset),
+                _ => Step::Continue,
+            }
         }
     };
 }
@@ -124,25 +193,50 @@ rst!(opef, 0x28);
 rst!(opf7, 0x30);
 rst!(opff, 0x38);
 
+// Pops the return address over two M-cycles and jumps after one internal
+// M-cycle. Shared by ret, ret cc and reti.
+#[inline]
+fn pop_pc(cpu: &mut Cpu, mem: &impl ProgramMemory, first_cycle: u8) -> Step {
+    match cpu.m_cycle - first_cycle {
+        0 => {
+            cpu.z = mem.get_u8(cpu.sp);
+            cpu.sp = cpu.sp.wrapping_add(1);
+            Step::Continue
+        }
+        1 => {
+            cpu.w = mem.get_u8(cpu.sp);
+            cpu.sp = cpu.sp.wrapping_add(1);
+            Step::Continue
+        }
+        _ => {
+            cpu.pc = cpu.get_wz();
+            Step::Done
+        }
+    }
+}
+
 // ret
-pub fn opc9(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-    let addr = mem.get_u16(cpu.sp);
-    cpu.sp += 2;
-    cpu.pc = addr;
-    Cycles(16)
+pub fn opc9(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1..=3 => pop_pc(cpu, mem, 1),
+        _ => Step::Continue,
+    }
 }
 
 macro_rules! ret_cc {
     ( $name:ident, $bit:ident, $tf:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-            if cpu.f.contains(CpuFlags::$bit) == $tf {
-                let addr = mem.get_u16(cpu.sp);
-                cpu.sp += 2;
-                cpu.pc = addr;
-                Cycles(20)
-            } else {
-                cpu.pc += 1;
-                Cycles(8)
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                1 => {
+                    if cpu.f.contains(CpuFlags::$bit) == $tf {
+                        Step::Continue
+                    } else {
+                        cpu.pc = cpu.pc.wrapping_add(1);
+                        Step::Done
+                    }
+                }
+                2..=4 => pop_pc(cpu, mem, 2),
+                _ => Step::Continue,
             }
         }
     };
@@ -153,10 +247,15 @@ ret_cc!(opd0, C, false);
 ret_cc!(opd8, C, true);
 
 // reti
-pub fn opd9(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-    let addr = mem.get_u16(cpu.sp);
-    cpu.sp += 2;
-    cpu.pc = addr;
-    cpu.ie = true;
-    Cycles(16)
+pub fn opd9(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1..=3 => {
+            let step = pop_pc(cpu, mem, 1);
+            if step == Step::Done {
+                cpu.ie = true;
+            }
+            step
+        }
+        _ => Step::Continue,
+    }
 }
@@ -1,13 +1,22 @@
-use crate::cpu::{Cpu, Cycles};
+use crate::cpu::{Cpu, Step};
 use crate::memory::ProgramMemory;
 use crate::registers::CpuFlags;
 
 macro_rules! ld_n_nn {
     ( $name:ident, $dst:ident ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-            cpu.$dst(mem.get_u16(cpu.pc.wrapping_add(1)));
-            cpu.pc += 3;
-            Cycles(12)
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                1 => {
+                    cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+                    Step::Continue
+                }
+                2 => {
+                    cpu.w = mem.get_u8(cpu.pc.wrapping_add(2));
+                    cpu.$dst(cpu.get_wz());
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
 }
@@ -16,15 +25,25 @@ ld_n_nn!(op11, set_de);
 ld_n_nn!(op21, set_hl);
 ld_n_nn!(op31, set_sp);
 
-pub fn opf9(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+pub fn opf9(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
+    if cpu.m_cycle < 1 {
+        return Step::Continue;
+    }
     cpu.set_sp(cpu.get_hl());
-    cpu.pc += 1;
-    Cycles(8)
+    Step::Done
 }
 
-pub fn opf8(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
+pub fn opf8(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+            return Step::Continue;
+        }
+        2 => (),
+        _ => return Step::Continue,
+    }
     let l = cpu.get_sp();
-    let r_u8 = mem.get_u8(cpu.pc.wrapping_add(1));
+    let r_u8 = cpu.z;
     let r: i16 = (r_u8 as i8).into();
 
     let result = l.wrapping_add_signed(r);
@@ -43,24 +62,47 @@ pub fn opf8(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
     cpu.f.set(CpuFlags::H, h);
 
     cpu.set_hl(result);
-    cpu.pc += 2;
-    Cycles(12)
+    Step::Done
 }
 
-pub fn op08(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-    let addr = mem.get_u16(cpu.pc.wrapping_add(1));
-    mem.set_u16(addr, cpu.sp);
-    cpu.pc += 3;
-    Cycles(20)
+pub fn op08(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+            Step::Continue
+        }
+        2 => {
+            cpu.w = mem.get_u8(cpu.pc.wrapping_add(2));
+            Step::Continue
+        }
+        3 => {
+            mem.set_u8(cpu.get_wz(), cpu.sp as u8);
+            Step::Continue
+        }
+        4 => {
+            mem.set_u8(cpu.get_wz().wrapping_add(1), (cpu.sp >> 8) as u8);
+            Step::Done
+        }
+        _ => Step::Continue,
+    }
 }
 
 macro_rules! push {
     ( $name:ident, $src:ident ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            cpu.sp -= 2;
-            mem.set_u16(cpu.sp, cpu.$src());
-            cpu.pc += 1;
-            Cycles(16)
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                2 => {
+                    cpu.sp = cpu.sp.wrapping_sub(1);
+                    mem.set_u8(cpu.sp, (cpu.$src() >> 8) as u8);
+                    Step::Continue
+                }
+                3 => {
+                    cpu.sp = cpu.sp.wrapping_sub(1);
+                    mem.set_u8(cpu.sp, cpu.$src() as u8);
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
 }
@@ -71,20 +113,40 @@ push!(ope5, get_hl);
 
 macro_rules! pop {
     ( $name:ident, $dst:ident ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-            cpu.$dst(mem.get_u16(cpu.sp));
-            cpu.sp += 2;
-            cpu.pc += 1;
-            Cycles(12)
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                1 => {
+                    cpu.z = mem.get_u8(cpu.sp);
+                    cpu.sp = cpu.sp.wrapping_add(1);
+                    Step::Continue
+                }
+                2 => {
+                    cpu.w = mem.get_u8(cpu.sp);
+                    cpu.sp = cpu.sp.wrapping_add(1);
+                    cpu.$dst(cpu.get_wz());
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
 }
 pop!(opc1, set_bc);
 pop!(opd1, set_de);
 pop!(ope1, set_hl);
-pub fn opf1(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-    cpu.set_af(mem.get_u16(cpu.sp) & 0xfff0);
-    cpu.sp += 2;
-    cpu.pc += 1;
-    Cycles(12)
+pub fn opf1(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.sp);
+            cpu.sp = cpu.sp.wrapping_add(1);
+            Step::Continue
+        }
+        2 => {
+            cpu.w = mem.get_u8(cpu.sp);
+            cpu.sp = cpu.sp.wrapping_add(1);
+            cpu.set_af(cpu.get_wz() & 0xfff0);
+            Step::Done
+        }
+        _ => Step::Continue,
+    }
 }
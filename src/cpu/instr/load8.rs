@@ -1,12 +1,14 @@
-use crate::cpu::{Cpu, Cycles};
+use crate::cpu::{Cpu, Step};
 use crate::memory::ProgramMemory;
 
 macro_rules! l8_imm {
     ( $name:ident, $dst:ident ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            if cpu.m_cycle < 1 {
+                return Step::Continue;
+            }
             cpu.$dst = mem.get_u8(cpu.pc.wrapping_add(1));
-            cpu.pc += 2;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -19,10 +21,9 @@ l8_imm!(op2e, l);
 
 macro_rules! l8_reg {
     ( $name:ident, $dst:ident, $src:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             cpu.$dst = cpu.$src;
-            cpu.pc += 1;
-            Cycles(4)
+            Step::Done
         }
     };
 }
@@ -78,10 +79,12 @@ l8_reg!(op6f, l, a);
 
 macro_rules! l8_from_mem {
     ( $name:ident, $dst:ident, $src:ident ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+            if cpu.m_cycle < 1 {
+                return Step::Continue;
+            }
             cpu.$dst = mem.get_u8(cpu.$src());
-            cpu.pc += 1;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -95,10 +98,12 @@ l8_from_mem!(op6e, l, get_hl);
 
 macro_rules! l8_to_mem {
     ( $name:ident, $src:ident, $dst:ident ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            if cpu.m_cycle < 1 {
+                return Step::Continue;
+            }
             mem.set_u8(cpu.$dst(), cpu.$src);
-            cpu.pc += 1;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -109,104 +114,154 @@ l8_to_mem!(op73, e, get_hl);
 l8_to_mem!(op74, h, get_hl);
 l8_to_mem!(op75, l, get_hl);
 
-pub fn op36(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-    mem.set_u8(cpu.get_hl(), mem.get_u8(cpu.pc.wrapping_add(1)));
-    cpu.pc += 2;
-    Cycles(12)
+pub fn op36(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+            Step::Continue
+        }
+        2 => {
+            mem.set_u8(cpu.get_hl(), cpu.z);
+            Step::Done
+        }
+        _ => Step::Continue,
+    }
 }
 
 l8_from_mem!(op0a, a, get_bc);
 l8_from_mem!(op1a, a, get_de);
-pub fn opfa(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-    let low = mem.get_u8(cpu.pc.wrapping_add(1));
-    let high = mem.get_u8(cpu.pc.wrapping_add(2));
-    let addr = (high as u16) << 8 | low as u16;
-    cpu.a = mem.get_u8(addr);
-    cpu.pc += 3;
-    Cycles(16)
-}
-pub fn op3e(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-    cpu.a = mem.get_u8(cpu.pc.wrapping_add(1));
-    cpu.pc += 2;
-    Cycles(8)
+pub fn opfa(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+            Step::Continue
+        }
+        2 => {
+            cpu.w = mem.get_u8(cpu.pc.wrapping_add(2));
+            Step::Continue
+        }
+        3 => {
+            cpu.a = mem.get_u8(cpu.get_wz());
+            Step::Done
+        }
+        _ => Step::Continue,
+    }
 }
+l8_imm!(op3e, a);
 
 l8_to_mem!(op02, a, get_bc);
 l8_to_mem!(op12, a, get_de);
 l8_to_mem!(op77, a, get_hl);
-pub fn opea(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-    let low = mem.get_u8(cpu.pc.wrapping_add(1));
-    let high = mem.get_u8(cpu.pc.wrapping_add(2));
-    let addr = (high as u16) << 8 | low as u16;
-    mem.set_u8(addr, cpu.a);
-    cpu.pc += 3;
-    Cycles(16)
+pub fn opea(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+            Step::Continue
+        }
+        2 => {
+            cpu.w = mem.get_u8(cpu.pc.wrapping_add(2));
+            Step::Continue
+        }
+        3 => {
+            mem.set_u8(cpu.get_wz(), cpu.a);
+            Step::Done
+        }
+        _ => Step::Continue,
+    }
 }
 
-pub fn opf2(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
+pub fn opf2(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+    if cpu.m_cycle < 1 {
+        return Step::Continue;
+    }
     let low = cpu.c;
     let high = 0xff;
     let addr = (high as u16) << 8 | low as u16;
     cpu.a = mem.get_u8(addr);
-    cpu.pc += 1;
-    Cycles(8)
+    Step::Done
 }
 
-pub fn ope2(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
+pub fn ope2(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    if cpu.m_cycle < 1 {
+        return Step::Continue;
+    }
     let low = cpu.c;
     let high = 0xff;
     let addr = (high as u16) << 8 | low as u16;
     mem.set_u8(addr, cpu.a);
-    cpu.pc += 1;
-    Cycles(8)
+    Step::Done
 }
 
-pub fn op3a(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
+pub fn op3a(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+    if cpu.m_cycle < 1 {
+        return Step::Continue;
+    }
     let hl = cpu.get_hl();
     cpu.a = mem.get_u8(hl);
-    cpu.set_hl(hl - 1);
-    cpu.pc += 1;
-    Cycles(8)
+    cpu.set_hl(hl.wrapping_sub(1));
+    Step::Done
 }
 
-pub fn op32(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
+pub fn op32(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    if cpu.m_cycle < 1 {
+        return Step::Continue;
+    }
     let hl = cpu.get_hl();
     mem.set_u8(hl, cpu.a);
-    cpu.set_hl(hl - 1);
-    cpu.pc += 1;
-    Cycles(8)
+    cpu.set_hl(hl.wrapping_sub(1));
+    Step::Done
 }
 
-pub fn op2a(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
+pub fn op2a(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+    if cpu.m_cycle < 1 {
+        return Step::Continue;
+    }
     let hl = cpu.get_hl();
     cpu.a = mem.get_u8(hl);
     cpu.set_hl(hl.wrapping_add(1));
-    cpu.pc += 1;
-    Cycles(8)
+    Step::Done
 }
 
-pub fn op22(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
+pub fn op22(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    if cpu.m_cycle < 1 {
+        return Step::Continue;
+    }
     let hl = cpu.get_hl();
     mem.set_u8(hl, cpu.a);
     cpu.set_hl(hl.wrapping_add(1));
-    cpu.pc += 1;
-    Cycles(8)
+    Step::Done
 }
 
-pub fn ope0(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-    let low = mem.get_u8(cpu.pc.wrapping_add(1));
-    let high = 0xff;
-    let addr = (high as u16) << 8 | low as u16;
-    mem.set_u8(addr, cpu.a);
-    cpu.pc += 2;
-    Cycles(12)
+pub fn ope0(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+            Step::Continue
+        }
+        2 => {
+            let low = cpu.z;
+            let high = 0xff;
+            let addr = (high as u16) << 8 | low as u16;
+            mem.set_u8(addr, cpu.a);
+            Step::Done
+        }
+        _ => Step::Continue,
+    }
 }
 
-pub fn opf0(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Cycles {
-    let low = mem.get_u8(cpu.pc.wrapping_add(1));
-    let high = 0xff;
-    let addr = (high as u16) << 8 | low as u16;
-    cpu.a = mem.get_u8(addr);
-    cpu.pc += 2;
-    Cycles(12)
+pub fn opf0(cpu: &mut Cpu, mem: &impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        1 => {
+            cpu.z = mem.get_u8(cpu.pc.wrapping_add(1));
+            Step::Continue
+        }
+        2 => {
+            let low = cpu.z;
+            let high = 0xff;
+            let addr = (high as u16) << 8 | low as u16;
+            cpu.a = mem.get_u8(addr);
+            Step::Done
+        }
+        _ => Step::Continue,
+    }
 }
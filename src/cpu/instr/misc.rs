@@ -1,4 +1,4 @@
-use crate::cpu::{Cpu, Cycles, InterruptChange, State};
+use crate::cpu::{Cpu, InterruptChange, State, Step};
 use crate::memory::ProgramMemory;
 use crate::registers::CpuFlags;
 
@@ -13,11 +13,10 @@ fn swap_impl(flags: &mut CpuFlags, reg: u8) -> u8 {
 }
 macro_rules! swap {
     ( $name:ident, $dst:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             let reg = cpu.$dst;
             cpu.$dst = swap_impl(&mut cpu.f, reg);
-            cpu.pc += 2;
-            Cycles(8)
+            Step::Done
         }
     };
 }
@@ -28,14 +27,22 @@ swap!(opcb32, d);
 swap!(opcb33, e);
 swap!(opcb34, h);
 swap!(opcb35, l);
-pub fn opcb36(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-    let reg = mem.get_u8(cpu.get_hl());
-    mem.set_u8(cpu.get_hl(), swap_impl(&mut cpu.f, reg));
-    cpu.pc += 2;
-    Cycles(16)
+pub fn opcb36(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+    match cpu.m_cycle {
+        2 => {
+            cpu.z = mem.get_u8(cpu.get_hl());
+            Step::Continue
+        }
+        3 => {
+            let reg = cpu.z;
+            mem.set_u8(cpu.get_hl(), swap_impl(&mut cpu.f, reg));
+            Step::Done
+        }
+        _ => Step::Continue,
+    }
 }
 
-pub fn op27(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+pub fn op27(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
     if !cpu.f.contains(CpuFlags::N) {
         // Last operation was an additon.
         if cpu.f.contains(CpuFlags::C) || cpu.a > 0x99 {
@@ -56,64 +63,56 @@ pub fn op27(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
     }
     cpu.f.set(CpuFlags::Z, cpu.a == 0);
     cpu.f.set(CpuFlags::H, false);
-    cpu.pc += 1;
-    Cycles(4)
+    Step::Done
 }
 
-pub fn op2f(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+pub fn op2f(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
     cpu.a = !cpu.a;
     cpu.f.set(CpuFlags::N, true);
     cpu.f.set(CpuFlags::H, true);
-    cpu.pc += 1;
-    Cycles(4)
+    Step::Done
 }
 
-pub fn op3f(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+pub fn op3f(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
     cpu.f.set(CpuFlags::N, false);
     cpu.f.set(CpuFlags::H, false);
     cpu.f.set(CpuFlags::C, !cpu.f.contains(CpuFlags::C));
-    cpu.pc += 1;
-    Cycles(4)
+    Step::Done
 }
 
-pub fn op37(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+pub fn op37(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
     cpu.f.set(CpuFlags::N, false);
     cpu.f.set(CpuFlags::H, false);
     cpu.f.set(CpuFlags::C, true);
-    cpu.pc += 1;
-    Cycles(4)
+    Step::Done
 }
 
 // Nop
-pub fn op00(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
-    cpu.pc += 1;
-    Cycles(4)
+pub fn op00(_cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
+    Step::Done
 }
 
 // Halt
-pub fn op76(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+pub fn op76(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
     cpu.state = State::Halted;
-    cpu.pc += 1;
-    Cycles(4)
+    Step::Done
 }
 
 // Stop
-pub fn op10(cpu: &mut Cpu, _mem: &mut impl ProgramMemory) -> Cycles {
+pub fn op10(cpu: &mut Cpu, _mem: &mut impl ProgramMemory) -> Step {
     cpu.state = State::Stopped;
-    cpu.pc += 1;
-    Cycles(4)
+    Step::Done
 }
 
-// DI
-pub fn opf3(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
-    cpu.interrupt_change = Some(InterruptChange::di());
-    cpu.pc += 1;
-    Cycles(4)
+// DI takes effect immediately, cancelling a pending EI
+pub fn opf3(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
+    cpu.ie = false;
+    cpu.interrupt_change = None;
+    Step::Done
 }
 
 // EI
-pub fn opfb(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+pub fn opfb(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
     cpu.interrupt_change = Some(InterruptChange::ei());
-    cpu.pc += 1;
-    Cycles(4)
+    Step::Done
 }
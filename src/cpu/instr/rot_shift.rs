@@ -1,4 +1,4 @@
-use crate::cpu::{Cpu, Cycles};
+use crate::cpu::{Cpu, Step};
 use crate::memory::ProgramMemory;
 use crate::registers::CpuFlags;
 
@@ -19,32 +19,39 @@ fn rlc_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     result
 }
 macro_rules! rlc {
-    ( $name:ident, hl, $size:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            let value = mem.get_u8(cpu.get_hl());
-            mem.set_u8(cpu.get_hl(), rlc_flags(&mut cpu.f, value));
-            cpu.pc += $size;
-            Cycles(16)
+    ( $name:ident, hl ) => {
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                2 => {
+                    cpu.z = mem.get_u8(cpu.get_hl());
+                    Step::Continue
+                }
+                3 => {
+                    let value = cpu.z;
+                    mem.set_u8(cpu.get_hl(), rlc_flags(&mut cpu.f, value));
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
-    ( $name:ident, $dst:ident, $size:literal, $flags:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+    ( $name:ident, $dst:ident, $flags:ident ) => {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             let value = cpu.$dst;
             cpu.$dst = $flags(&mut cpu.f, value);
-            cpu.pc += $size;
-            Cycles(8)
+            Step::Done
         }
     };
 }
-rlc!(op07, a, 1, rlca_flags);
-rlc!(opcb07, a, 2, rlc_flags);
-rlc!(opcb00, b, 2, rlc_flags);
-rlc!(opcb01, c, 2, rlc_flags);
-rlc!(opcb02, d, 2, rlc_flags);
-rlc!(opcb03, e, 2, rlc_flags);
-rlc!(opcb04, h, 2, rlc_flags);
-rlc!(opcb05, l, 2, rlc_flags);
-rlc!(opcb06, hl, 2);
+rlc!(op07, a, rlca_flags);
+rlc!(opcb07, a, rlc_flags);
+rlc!(opcb00, b, rlc_flags);
+rlc!(opcb01, c, rlc_flags);
+rlc!(opcb02, d, rlc_flags);
+rlc!(opcb03, e, rlc_flags);
+rlc!(opcb04, h, rlc_flags);
+rlc!(opcb05, l, rlc_flags);
+rlc!(opcb06, hl);
 
 fn rla_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     let c_in = if flags.contains(CpuFlags::C) { 1 } else { 0 };
@@ -65,32 +72,39 @@ fn rl_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     result
 }
 macro_rules! rl {
-    ( $name:ident, hl, $size:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            let value = mem.get_u8(cpu.get_hl());
-            mem.set_u8(cpu.get_hl(), rl_flags(&mut cpu.f, value));
-            cpu.pc += $size;
-            Cycles(16)
+    ( $name:ident, hl ) => {
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                2 => {
+                    cpu.z = mem.get_u8(cpu.get_hl());
+                    Step::Continue
+                }
+                3 => {
+                    let value = cpu.z;
+                    mem.set_u8(cpu.get_hl(), rl_flags(&mut cpu.f, value));
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
-    ( $name:ident, $dst:ident, $size:literal, $cycles:literal, $flags:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+    ( $name:ident, $dst:ident, $flags:ident ) => {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             let value = cpu.$dst;
             cpu.$dst = $flags(&mut cpu.f, value);
-            cpu.pc += $size;
-            Cycles($cycles)
+            Step::Done
         }
     };
 }
-rl!(op17, a, 1, 4, rla_flags);
-rl!(opcb17, a, 2, 8, rl_flags);
-rl!(opcb10, b, 2, 8, rl_flags);
-rl!(opcb11, c, 2, 8, rl_flags);
-rl!(opcb12, d, 2, 8, rl_flags);
-rl!(opcb13, e, 2, 8, rl_flags);
-rl!(opcb14, h, 2, 8, rl_flags);
-rl!(opcb15, l, 2, 8, rl_flags);
-rl!(opcb16, hl, 2);
+rl!(op17, a, rla_flags);
+rl!(opcb17, a, rl_flags);
+rl!(opcb10, b, rl_flags);
+rl!(opcb11, c, rl_flags);
+rl!(opcb12, d, rl_flags);
+rl!(opcb13, e, rl_flags);
+rl!(opcb14, h, rl_flags);
+rl!(opcb15, l, rl_flags);
+rl!(opcb16, hl);
 
 fn rrca_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     let result = value.rotate_right(1);
@@ -109,32 +123,39 @@ fn rrc_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     result
 }
 macro_rules! rrc {
-    ( $name:ident, hl, $size:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            let value = mem.get_u8(cpu.get_hl());
-            mem.set_u8(cpu.get_hl(), rrc_flags(&mut cpu.f, value));
-            cpu.pc += $size;
-            Cycles(16)
+    ( $name:ident, hl ) => {
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                2 => {
+                    cpu.z = mem.get_u8(cpu.get_hl());
+                    Step::Continue
+                }
+                3 => {
+                    let value = cpu.z;
+                    mem.set_u8(cpu.get_hl(), rrc_flags(&mut cpu.f, value));
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
-    ( $name:ident, $dst:ident, $size:literal, $cycles: literal, $flags:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+    ( $name:ident, $dst:ident, $flags:ident ) => {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             let value = cpu.$dst;
             cpu.$dst = $flags(&mut cpu.f, value);
-            cpu.pc += $size;
-            Cycles($cycles)
+            Step::Done
         }
     };
 }
-rrc!(op0f, a, 1, 4, rrca_flags);
-rrc!(opcb0f, a, 2, 8, rrc_flags);
-rrc!(opcb08, b, 2, 8, rrc_flags);
-rrc!(opcb09, c, 2, 8, rrc_flags);
-rrc!(opcb0a, d, 2, 8, rrc_flags);
-rrc!(opcb0b, e, 2, 8, rrc_flags);
-rrc!(opcb0c, h, 2, 8, rrc_flags);
-rrc!(opcb0d, l, 2, 8, rrc_flags);
-rrc!(opcb0e, hl, 2);
+rrc!(op0f, a, rrca_flags);
+rrc!(opcb0f, a, rrc_flags);
+rrc!(opcb08, b, rrc_flags);
+rrc!(opcb09, c, rrc_flags);
+rrc!(opcb0a, d, rrc_flags);
+rrc!(opcb0b, e, rrc_flags);
+rrc!(opcb0c, h, rrc_flags);
+rrc!(opcb0d, l, rrc_flags);
+rrc!(opcb0e, hl);
 
 fn rra_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     let c_in = if flags.contains(CpuFlags::C) { 1 } else { 0 };
@@ -155,32 +176,39 @@ fn rr_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     result
 }
 macro_rules! rr {
-    ( $name:ident, hl, $size:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            let value = mem.get_u8(cpu.get_hl());
-            mem.set_u8(cpu.get_hl(), rr_flags(&mut cpu.f, value));
-            cpu.pc += $size;
-            Cycles(16)
+    ( $name:ident, hl ) => {
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                2 => {
+                    cpu.z = mem.get_u8(cpu.get_hl());
+                    Step::Continue
+                }
+                3 => {
+                    let value = cpu.z;
+                    mem.set_u8(cpu.get_hl(), rr_flags(&mut cpu.f, value));
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
-    ( $name:ident, $dst:ident, $size:literal, $cycles:literal, $flags:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+    ( $name:ident, $dst:ident, $flags:ident ) => {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             let value = cpu.$dst;
             cpu.$dst = $flags(&mut cpu.f, value);
-            cpu.pc += $size;
-            Cycles($cycles)
+            Step::Done
         }
     };
 }
-rr!(op1f, a, 1, 4, rra_flags);
-rr!(opcb1f, a, 2, 8, rr_flags);
-rr!(opcb18, b, 2, 8, rr_flags);
-rr!(opcb19, c, 2, 8, rr_flags);
-rr!(opcb1a, d, 2, 8, rr_flags);
-rr!(opcb1b, e, 2, 8, rr_flags);
-rr!(opcb1c, h, 2, 8, rr_flags);
-rr!(opcb1d, l, 2, 8, rr_flags);
-rr!(opcb1e, hl, 2);
+rr!(op1f, a, rra_flags);
+rr!(opcb1f, a, rr_flags);
+rr!(opcb18, b, rr_flags);
+rr!(opcb19, c, rr_flags);
+rr!(opcb1a, d, rr_flags);
+rr!(opcb1b, e, rr_flags);
+rr!(opcb1c, h, rr_flags);
+rr!(opcb1d, l, rr_flags);
+rr!(opcb1e, hl);
 
 fn sla_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     let result = value << 1;
@@ -191,31 +219,38 @@ fn sla_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     result
 }
 macro_rules! sla {
-    ( $name:ident, hl, $size:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            let value = mem.get_u8(cpu.get_hl());
-            mem.set_u8(cpu.get_hl(), sla_flags(&mut cpu.f, value));
-            cpu.pc += $size;
-            Cycles(16)
+    ( $name:ident, hl ) => {
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                2 => {
+                    cpu.z = mem.get_u8(cpu.get_hl());
+                    Step::Continue
+                }
+                3 => {
+                    let value = cpu.z;
+                    mem.set_u8(cpu.get_hl(), sla_flags(&mut cpu.f, value));
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
-    ( $name:ident, $dst:ident, $size:literal ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+    ( $name:ident, $dst:ident ) => {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             let value = cpu.$dst;
             cpu.$dst = sla_flags(&mut cpu.f, value);
-            cpu.pc += $size;
-            Cycles(8)
+            Step::Done
         }
     };
 }
-sla!(opcb27, a, 2);
-sla!(opcb20, b, 2);
-sla!(opcb21, c, 2);
-sla!(opcb22, d, 2);
-sla!(opcb23, e, 2);
-sla!(opcb24, h, 2);
-sla!(opcb25, l, 2);
-sla!(opcb26, hl, 2);
+sla!(opcb27, a);
+sla!(opcb20, b);
+sla!(opcb21, c);
+sla!(opcb22, d);
+sla!(opcb23, e);
+sla!(opcb24, h);
+sla!(opcb25, l);
+sla!(opcb26, hl);
 
 fn sra_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     let result = value & 0x80 | value >> 1;
@@ -226,31 +261,38 @@ fn sra_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     result
 }
 macro_rules! sra {
-    ( $name:ident, hl, $size:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            let value = mem.get_u8(cpu.get_hl());
-            mem.set_u8(cpu.get_hl(), sra_flags(&mut cpu.f, value));
-            cpu.pc += $size;
-            Cycles(16)
+    ( $name:ident, hl ) => {
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                2 => {
+                    cpu.z = mem.get_u8(cpu.get_hl());
+                    Step::Continue
+                }
+                3 => {
+                    let value = cpu.z;
+                    mem.set_u8(cpu.get_hl(), sra_flags(&mut cpu.f, value));
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
-    ( $name:ident, $dst:ident, $size:literal ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+    ( $name:ident, $dst:ident ) => {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             let value = cpu.$dst;
             cpu.$dst = sra_flags(&mut cpu.f, value);
-            cpu.pc += $size;
-            Cycles(8)
+            Step::Done
         }
     };
 }
-sra!(opcb2f, a, 2);
-sra!(opcb28, b, 2);
-sra!(opcb29, c, 2);
-sra!(opcb2a, d, 2);
-sra!(opcb2b, e, 2);
-sra!(opcb2c, h, 2);
-sra!(opcb2d, l, 2);
-sra!(opcb2e, hl, 2);
+sra!(opcb2f, a);
+sra!(opcb28, b);
+sra!(opcb29, c);
+sra!(opcb2a, d);
+sra!(opcb2b, e);
+sra!(opcb2c, h);
+sra!(opcb2d, l);
+sra!(opcb2e, hl);
 
 fn srl_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     let result = value >> 1;
@@ -261,28 +303,35 @@ fn srl_flags(flags: &mut CpuFlags, value: u8) -> u8 {
     result
 }
 macro_rules! srl {
-    ( $name:ident, hl, $size:literal ) => {
-        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Cycles {
-            let value = mem.get_u8(cpu.get_hl());
-            mem.set_u8(cpu.get_hl(), srl_flags(&mut cpu.f, value));
-            cpu.pc += $size;
-            Cycles(16)
+    ( $name:ident, hl ) => {
+        pub fn $name(cpu: &mut Cpu, mem: &mut impl ProgramMemory) -> Step {
+            match cpu.m_cycle {
+                2 => {
+                    cpu.z = mem.get_u8(cpu.get_hl());
+                    Step::Continue
+                }
+                3 => {
+                    let value = cpu.z;
+                    mem.set_u8(cpu.get_hl(), srl_flags(&mut cpu.f, value));
+                    Step::Done
+                }
+                _ => Step::Continue,
+            }
         }
     };
-    ( $name:ident, $dst:ident, $size:literal ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
+    ( $name:ident, $dst:ident ) => {
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
             let value = cpu.$dst;
             cpu.$dst = srl_flags(&mut cpu.f, value);
-            cpu.pc += $size;
-            Cycles(8)
+            Step::Done
         }
     };
 }
-srl!(opcb3f, a, 2);
-srl!(opcb38, b, 2);
-srl!(opcb39, c, 2);
-srl!(opcb3a, d, 2);
-srl!(opcb3b, e, 2);
-srl!(opcb3c, h, 2);
-srl!(opcb3d, l, 2);
-srl!(opcb3e, hl, 2);
+srl!(opcb3f, a);
+srl!(opcb38, b);
+srl!(opcb39, c);
+srl!(opcb3a, d);
+srl!(opcb3b, e);
+srl!(opcb3c, h);
+srl!(opcb3d, l);
+srl!(opcb3e, hl);
@@ -0,0 +1,311 @@
+// Single instruction tests against the SingleStepTests/sm83 vectors.
+//
+// The full set is too large to vendor. `tests/sm83_subset` holds a few
+// vectors in the same layout, at least one opcode per handler module but
+// `undef`, which the set has none for, and always runs. For the full set, clone
+// https://github.com/SingleStepTests/sm83, either place its `v1` directory at
+// `tests/sm83/v1` or point `SM83_TESTS` at it, and run
+// `cargo test -- --ignored`.
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::cpu::instr::execute_instr;
+use crate::cpu::{Cpu, Step};
+use crate::memory::ProgramMemory;
+use crate::opcodes::{self, FlagEffect};
+use crate::registers::CpuFlags;
+
+// Longest instruction is call at 6 M-cycles.
+const MAX_M_CYCLES: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BusCycle {
+    Read(u16, u8),
+    Write(u16, u8),
+}
+
+/// Flat 64 KiB address space with no MBC or I/O behaviour that records
+/// every access so bus activity can be checked per M-cycle.
+struct FlatMemory {
+    ram: Box<[u8; 0x10000]>,
+    log: RefCell<Vec<BusCycle>>,
+}
+
+impl Default for FlatMemory {
+    fn default() -> Self {
+        Self {
+            ram: Box::new([0; 0x10000]),
+            log: RefCell::default(),
+        }
+    }
+}
+
+impl ProgramMemory for FlatMemory {
+    fn set_u8(&mut self, address: impl Into<u16>, value: u8) {
+        let address = address.into();
+        self.log.borrow_mut().push(BusCycle::Write(address, value));
+        self.ram[address as usize] = value;
+    }
+
+    fn get_u8(&self, address: impl Into<u16>) -> u8 {
+        let address = address.into();
+        let value = self.ram[address as usize];
+        self.log.borrow_mut().push(BusCycle::Read(address, value));
+        value
+    }
+}
+
+fn field(state: &Value, name: &str) -> u16 {
+    state[name]
+        .as_u64()
+        .unwrap_or_else(|| panic!("missing field {}", name)) as u16
+}
+
+fn load_state(cpu: &mut Cpu, mem: &mut FlatMemory, state: &Value) {
+    cpu.a = field(state, "a") as u8;
+    cpu.f = CpuFlags::from_bits_retain(field(state, "f") as u8);
+    cpu.b = field(state, "b") as u8;
+    cpu.c = field(state, "c") as u8;
+    cpu.d = field(state, "d") as u8;
+    cpu.e = field(state, "e") as u8;
+    cpu.h = field(state, "h") as u8;
+    cpu.l = field(state, "l") as u8;
+    cpu.sp = field(state, "sp");
+    cpu.pc = field(state, "pc");
+    cpu.ie = state["ime"].as_u64() == Some(1);
+
+    for entry in state["ram"].as_array().unwrap() {
+        let addr = entry[0].as_u64().unwrap() as usize;
+        mem.ram[addr] = entry[1].as_u64().unwrap() as u8;
+    }
+}
+
+fn expected_cycle(entry: &Value) -> Option<BusCycle> {
+    let entry = entry.as_array()?;
+    let addr = entry[0].as_u64()? as u16;
+    let value = entry[1].as_u64().unwrap_or(0) as u8;
+    let kind = entry[2].as_str()?;
+    if kind.starts_with('r') {
+        Some(BusCycle::Read(addr, value))
+    } else if kind.contains('w') {
+        Some(BusCycle::Write(addr, value))
+    } else {
+        None
+    }
+}
+
+/// The vectors model the SM83 fetch/execute overlap: the opcode has already
+/// been fetched, PC points past it and the last M-cycle fetches the next
+/// opcode. This core fetches on the first M-cycle instead, so start the test
+/// on the opcode, fetch it first and end without fetching the next one.
+fn fetch_first(test: &Value) -> Result<Value, String> {
+    let name = test["name"].as_str().unwrap_or_default();
+    let opcode = u8::from_str_radix(name.split(' ').next().unwrap_or_default(), 16)
+        .map_err(|e| format!("bad test name {:?}: {}", name, e))?;
+    let mut test = test.clone();
+    let pc = field(&test["initial"], "pc").wrapping_sub(1);
+    test["initial"]["pc"] = pc.into();
+    test["final"]["pc"] = field(&test["final"], "pc").wrapping_sub(1).into();
+    let cycles = test["cycles"].as_array_mut().unwrap();
+    cycles.pop();
+    cycles.insert(0, json!([pc, opcode, "r-m"]));
+    Ok(test)
+}
+
+fn run_test(test: &Value) -> Result<(), String> {
+    let test = fetch_first(test)?;
+    let initial = &test["initial"];
+    let expected = &test["final"];
+
+    let mut cpu = Cpu::default();
+    let mut mem = FlatMemory::default();
+    load_state(&mut cpu, &mut mem, initial);
+
+    let mut cycles = Vec::new();
+    cpu.m_cycle = 0;
+    loop {
+        let start = mem.log.borrow().len();
+        let step = execute_instr(&mut cpu, &mut mem);
+        let accesses = mem.log.borrow()[start..].to_vec();
+        if accesses.len() > 1 {
+            return Err(format!(
+                "M-cycle {} made {} bus accesses: {:?}",
+                cpu.m_cycle,
+                accesses.len(),
+                accesses
+            ));
+        }
+        cycles.push(accesses.first().copied());
+
+        if step == Step::Done {
+            break;
+        }
+        cpu.m_cycle += 1;
+        if cycles.len() > MAX_M_CYCLES {
+            return Err("instruction did not finish".to_owned());
+        }
+    }
+    cpu.m_cycle = 0;
+
+    let mut errors = Vec::new();
+
+    // The opcode table's cycle counts and flag effects, against the vector.
+    let opcode = opcodes::decode(cpu.opcode, cpu.cb_opcode);
+    if ![opcode.cycles, opcode.cycles_not_taken].contains(&(cycles.len() as u8)) {
+        errors.push(format!(
+            "opcode table: {} or {} M-cycles, took {}",
+            opcode.cycles,
+            opcode.cycles_not_taken,
+            cycles.len()
+        ));
+    }
+    let before = CpuFlags::from_bits_retain(field(initial, "f") as u8);
+    let after = CpuFlags::from_bits_retain(field(expected, "f") as u8);
+    let names = [
+        ('Z', CpuFlags::Z),
+        ('N', CpuFlags::N),
+        ('H', CpuFlags::H),
+        ('C', CpuFlags::C),
+    ];
+    for (effect, (name, flag)) in opcode.flags.0.iter().zip(names) {
+        let want = match effect {
+            FlagEffect::Unaffected => before.contains(flag),
+            FlagEffect::Reset => false,
+            FlagEffect::Set => true,
+            FlagEffect::Affected => continue,
+        };
+        if after.contains(flag) != want {
+            errors.push(format!("opcode table: {} is {:?}", name, effect));
+        }
+    }
+
+    let ime = match cpu.interrupt_change {
+        Some(ref change) => change.next_state,
+        None => cpu.ie,
+    };
+    let registers = [
+        ("a", cpu.a as u16),
+        ("f", cpu.f.bits() as u16),
+        ("b", cpu.b as u16),
+        ("c", cpu.c as u16),
+        ("d", cpu.d as u16),
+        ("e", cpu.e as u16),
+        ("h", cpu.h as u16),
+        ("l", cpu.l as u16),
+        ("sp", cpu.sp),
+        ("pc", cpu.pc),
+        ("ime", ime as u16),
+    ];
+    for (name, actual) in registers {
+        if expected[name].is_null() {
+            continue;
+        }
+        let want = field(expected, name);
+        if want != actual {
+            errors.push(format!("{}: expected {:#x}, got {:#x}", name, want, actual));
+        }
+    }
+
+    for entry in expected["ram"].as_array().unwrap() {
+        let addr = entry[0].as_u64().unwrap() as usize;
+        let want = entry[1].as_u64().unwrap() as u8;
+        let actual = mem.ram[addr];
+        if want != actual {
+            errors.push(format!(
+                "[{:#06x}]: expected {:#04x}, got {:#04x}",
+                addr, want, actual
+            ));
+        }
+    }
+
+    let expected_cycles: Vec<_> = test["cycles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(expected_cycle)
+        .collect();
+    if expected_cycles.len() != cycles.len() {
+        errors.push(format!(
+            "M-cycles: expected {}, got {}",
+            expected_cycles.len(),
+            cycles.len()
+        ));
+    } else {
+        for (idx, (actual, want)) in cycles.iter().zip(expected_cycles.iter()).enumerate() {
+            if actual != want {
+                errors.push(format!(
+                    "bus M-cycle {}: expected {:?}, got {:?}",
+                    idx, want, actual
+                ));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join(", "))
+    }
+}
+
+fn vector_dir() -> PathBuf {
+    match std::env::var_os("SM83_TESTS") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/sm83/v1"),
+    }
+}
+
+/// Run every vector file in `dir`, failing if there are none.
+fn run_vectors(dir: &Path) {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("no sm83 test vectors at {}: {}", dir.display(), e))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    assert!(
+        !files.is_empty(),
+        "no sm83 test vectors in {}",
+        dir.display()
+    );
+    files.sort();
+
+    let mut failures = Vec::new();
+    let mut count = 0;
+    for path in files {
+        let data = fs::read_to_string(&path).unwrap();
+        let tests: Vec<Value> =
+            serde_json::from_str(&data).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+
+        // Report only the first failure of each file, one file per opcode.
+        for test in tests.iter() {
+            count += 1;
+            if let Err(e) = run_test(test) {
+                failures.push(format!("{}: {}", test["name"], e));
+                break;
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} opcodes failed out of {} tests run:\n{}",
+        failures.len(),
+        count,
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn sm83_vector_subset() {
+    run_vectors(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/sm83_subset"));
+}
+
+#[test]
+#[ignore = "needs the SingleStepTests/sm83 vectors, see the top of this file"]
+fn sm83_single_step_tests() {
+    run_vectors(&vector_dir());
+}
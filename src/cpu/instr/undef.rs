@@ -1,11 +1,13 @@
-use crate::cpu::{Cpu, Cycles};
+use crate::cpu::{Cpu, State, Step};
 use crate::memory::ProgramMemory;
 
+// Illegal opcodes hang the CPU on hardware. PC is left pointing at the
+// offending opcode so the debugger can report where it happened.
 macro_rules! undef {
     ( $name:ident ) => {
-        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Cycles {
-            println!("{}", cpu);
-            panic!("Undefined instruction used!");
+        pub fn $name(cpu: &mut Cpu, _mem: &impl ProgramMemory) -> Step {
+            cpu.state = State::Locked;
+            Step::Done
         }
     };
 }
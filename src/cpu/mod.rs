@@ -1,11 +1,14 @@
+pub mod bus;
 pub mod instr;
 
 use std::fmt::Display;
 
+use crate::cpu::bus::{Access, Bus};
 use crate::cpu::instr::execute_instr;
 use crate::memory::{Memory, ProgramMemory};
 use crate::memory_map::MemoryMap;
 use crate::registers::{CpuFlags, Interrupt};
+use crate::trace::Trace;
 
 pub struct InterruptChange {
     next_state: bool,
@@ -13,12 +16,6 @@ pub struct InterruptChange {
 }
 
 impl InterruptChange {
-    pub fn di() -> Self {
-        Self {
-            next_state: false,
-            change_in: 2,
-        }
-    }
     pub fn ei() -> Self {
         Self {
             next_state: true,
@@ -32,6 +29,9 @@ pub enum State {
     Running,
     Stopped,
     Halted,
+    /// An illegal opcode was executed. The CPU hangs until reset and
+    /// interrupts are no longer serviced.
+    Locked,
 }
 
 pub struct Cpu {
@@ -47,11 +47,25 @@ pub struct Cpu {
     pub sp: u16,
     pub pc: u16,
 
-    pub wait: u8,
     pub ie: bool,
     pub interrupt_change: Option<InterruptChange>,
 
     pub state: State,
+
+    /// M-cycle of the instruction (or interrupt dispatch) in flight. Zero
+    /// means the next tick starts a new instruction.
+    pub m_cycle: u8,
+    pub opcode: u8,
+    pub cb_opcode: u8,
+    /// Internal temporary registers used by multi-cycle instructions.
+    pub z: u8,
+    pub w: u8,
+    dispatch: Option<MemoryMap>,
+    /// Memory access made during the last M-cycle, if any.
+    pub last_access: Option<Access>,
+
+    /// Instruction trace, written as each instruction starts.
+    pub trace: Option<Trace>,
 }
 
 impl Display for Cpu {
@@ -81,16 +95,31 @@ impl Default for Cpu {
             sp: 0xfffe,
             pc: 0x0000,
 
-            wait: 0,
             interrupt_change: None,
             ie: false,
 
             state: State::Running,
+
+            m_cycle: 0,
+            opcode: 0,
+            cb_opcode: 0,
+            z: 0,
+            w: 0,
+            dispatch: None,
+            last_access: None,
+            trace: None,
         }
     }
 }
 
-pub struct Cycles(u8);
+/// Outcome of running one M-cycle of an instruction.
+#[derive(Debug, PartialEq)]
+pub enum Step {
+    /// The instruction needs further M-cycles.
+    Continue,
+    /// The instruction has finished.
+    Done,
+}
 
 impl Cpu {
     pub fn get_af(&self) -> u16 {
@@ -137,13 +166,22 @@ impl Cpu {
         self.l = val as u8;
     }
 
+    pub fn get_wz(&self) -> u16 {
+        (self.w as u16) << 8 | self.z as u16
+    }
+
+    /// Interrupt vector the CPU is pushing PC to jump to, if any.
+    pub fn dispatching(&self) -> Option<MemoryMap> {
+        self.dispatch
+    }
+
     fn handle_interrupt(
         &mut self,
         mem: &mut Memory,
         reg: &Interrupt,
         bit: Interrupt,
         vector: MemoryMap,
-    ) -> bool {
+    ) -> Option<MemoryMap> {
         if reg.contains(bit) {
             self.state = State::Running;
             if self.ie {
@@ -153,82 +191,90 @@ impl Cpu {
                 mem.set_reg(MemoryMap::IF, reg);
 
                 self.ie = false;
-
-                self.sp -= 2;
-                ProgramMemory::set_u16(mem, self.sp, self.pc);
-
-                self.pc = vector.into();
-
-                self.wait = 4;
-                return true;
+                return Some(vector);
             }
         }
-        false
+        None
     }
 
     // Handle interrupts
     // http://gbdev.gg8.se/wiki/articles/Interrupts
-    fn handle_interrupts(&mut self, mem: &mut Memory) -> bool {
+    fn handle_interrupts(&mut self, mem: &mut Memory) -> Option<MemoryMap> {
         let enabled = Interrupt::from_bits(mem.get_u8(MemoryMap::IE)).unwrap();
         let requested = Interrupt::from_bits(mem.get_u8(MemoryMap::IF)).unwrap();
         let enabled_and_requested = enabled.intersection(requested);
 
-        if self.handle_interrupt(
-            mem,
-            &enabled_and_requested,
-            Interrupt::VBlank,
-            MemoryMap::InterruptVBlank,
-        ) {
-            return true;
-        }
-        if self.handle_interrupt(
-            mem,
-            &enabled_and_requested,
-            Interrupt::LcdStat,
-            MemoryMap::InterruptLcdStat,
-        ) {
-            return true;
-        }
-        if self.handle_interrupt(
-            mem,
-            &enabled_and_requested,
-            Interrupt::Timer,
-            MemoryMap::InterruptTimer,
-        ) {
-            return true;
-        }
-        if self.handle_interrupt(
-            mem,
-            &enabled_and_requested,
-            Interrupt::Serial,
-            MemoryMap::InterruptSerial,
-        ) {
-            return true;
+        [
+            (Interrupt::VBlank, MemoryMap::InterruptVBlank),
+            (Interrupt::LcdStat, MemoryMap::InterruptLcdStat),
+            (Interrupt::Timer, MemoryMap::InterruptTimer),
+            (Interrupt::Serial, MemoryMap::InterruptSerial),
+            (Interrupt::Joypad, MemoryMap::InterruptJoypad),
+        ]
+        .into_iter()
+        .find_map(|(bit, vector)| self.handle_interrupt(mem, &enabled_and_requested, bit, vector))
+    }
+
+    // Interrupt dispatch takes 5 M-cycles: two internal cycles, the two
+    // halves of PC pushed high byte first, then the jump to the vector.
+    fn dispatch_interrupt(&mut self, mem: &mut impl ProgramMemory, vector: MemoryMap) -> Step {
+        match self.m_cycle {
+            0 | 1 => Step::Continue,
+            2 => {
+                self.sp = self.sp.wrapping_sub(1);
+                mem.set_u8(self.sp, (self.pc >> 8) as u8);
+                Step::Continue
+            }
+            3 => {
+                self.sp = self.sp.wrapping_sub(1);
+                mem.set_u8(self.sp, self.pc as u8);
+                Step::Continue
+            }
+            _ => {
+                self.pc = vector.into();
+                Step::Done
+            }
         }
-        self.handle_interrupt(
-            mem,
-            &enabled_and_requested,
-            Interrupt::Joypad,
-            MemoryMap::InterruptJoypad,
-        )
     }
 
+    /// Run the CPU for one M-cycle
     pub fn tick(&mut self, mem: &mut Memory) {
-        if self.wait > 0 {
-            self.wait -= 1;
-            return;
+        self.last_access = None;
+        if self.m_cycle == 0 {
+            if matches!(self.state, State::Running | State::Halted) {
+                self.dispatch = self.handle_interrupts(mem);
+            }
+
+            if self.dispatch.is_none() && self.state != State::Running {
+                return;
+            }
         }
 
-        if self.state != State::Stopped && self.handle_interrupts(mem) {
-            return;
+        if self.m_cycle == 0 && self.dispatch.is_none() {
+            if let Some(mut trace) = self.trace.take() {
+                trace.log(self, mem);
+                self.trace = Some(trace);
+            }
         }
 
-        if self.state != State::Running {
+        let stub_ly = self.trace.as_ref().is_some_and(Trace::stubs_ly);
+        let mut bus = Bus::new(mem, self.pc).with_stub_ly(stub_ly);
+        let step = if let Some(vector) = self.dispatch {
+            self.dispatch_interrupt(&mut bus, vector)
+        } else {
+            execute_instr(self, &mut bus)
+        };
+        self.last_access = bus.access();
+
+        if step == Step::Continue {
+            self.m_cycle += 1;
             return;
         }
 
-        let Cycles(delay) = execute_instr(self, mem);
-        self.wait = delay / 4 - 1;
+        self.m_cycle = 0;
+        if self.dispatch.take().is_some() {
+            return;
+        }
 
         if let Some(ref mut change) = self.interrupt_change {
             if change.change_in > 0 {
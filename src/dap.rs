@@ -0,0 +1,737 @@
+//! Debug Adapter Protocol server, for debugging from VS Code and other DAP
+//! clients.
+//!
+//! Breakpoints in RGBDS sources are resolved through the symbol file: a line
+//! breakpoint binds to the label defined on that line, or directly above it
+//! with only blank lines and comments in between. Lines that can't be
+//! resolved are reported as unverified. With line info, see [`SourceMap`],
+//! breakpoints bind to the line itself and the game stops on source lines.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use serde_json::{json, Value};
+
+use crate::address::Address;
+use crate::debugger::{Breakpoint, Command, Expr, Register, IO_REGISTERS};
+use crate::gameboy::Gameboy;
+use crate::loader::Loader;
+use crate::memory::ProgramMemory;
+use crate::rom::Rom;
+use crate::source_map::SourceMap;
+use crate::symbols::Symbols;
+
+/// The Game Boy only has one thread of execution.
+const THREAD_ID: u64 = 1;
+
+/// Variable references of the scopes.
+const REGISTERS: u64 = 1;
+const IO: u64 = 2;
+
+/// Longest memory read answered.
+const MAX_READ: usize = 0x1000;
+
+struct Client {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    /// Bytes the socket hasn't taken yet.
+    outgoing: Vec<u8>,
+    seq: u64,
+    /// Reason to report once the debugger pauses after a resume.
+    waiting: Option<&'static str>,
+    stop_on_entry: bool,
+}
+
+impl Client {
+    fn send(&mut self, mut message: Value) -> std::io::Result<()> {
+        self.seq += 1;
+        message["seq"] = self.seq.into();
+        let body = message.to_string();
+        let data = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        self.outgoing.extend_from_slice(data.as_bytes());
+        self.flush()
+    }
+
+    /// Send as much as the non-blocking socket takes, leaving the rest for
+    /// the next poll rather than stalling the frame.
+    fn flush(&mut self) -> std::io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.outgoing.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> std::io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    /// Read whatever the client has sent, returning `None` once it has
+    /// disconnected.
+    fn receive(&mut self) -> std::io::Result<Option<Vec<Value>>> {
+        let mut chunk = [0; 4096];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Ok(None),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut messages = Vec::new();
+        while let Some(end) = self.buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            let header = String::from_utf8_lossy(&self.buffer[..end]);
+            let length = header
+                .lines()
+                .find_map(|line| line.strip_prefix("Content-Length:"))
+                .and_then(|length| length.trim().parse::<usize>().ok());
+            let Some(length) = length else {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    "missing Content-Length",
+                ));
+            };
+            let start = end + 4;
+            if self.buffer.len() < start + length {
+                break;
+            }
+            let message = serde_json::from_slice(&self.buffer[start..start + length])?;
+            self.buffer.drain(..start + length);
+            messages.push(message);
+        }
+        Ok(Some(messages))
+    }
+}
+
+/// Label defined on a line of RGBDS source, with local labels qualified by
+/// their parent, e.g. `PlayerUpdate.loop`.
+fn label(line: &str, parent: Option<&str>) -> Option<String> {
+    let line = line.split(';').next().unwrap_or_default();
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let end = line
+        .find(|c: char| c == ':' || c.is_whitespace())
+        .unwrap_or(line.len());
+    let name = &line[..end];
+    if name.is_empty() {
+        return None;
+    }
+    if let Some(local) = name.strip_prefix('.') {
+        return Some(format!("{}.{}", parent.unwrap_or_default(), local));
+    }
+    // Anything else at the start of a line without a colon is a directive
+    // or constant, like `SECTION` or `MAX_LIVES EQU 3`.
+    line[end..].starts_with(':').then(|| name.to_owned())
+}
+
+/// Whether the line is only blank or a comment.
+fn is_blank(line: &str) -> bool {
+    line.split(';').next().unwrap_or_default().trim().is_empty()
+}
+
+/// Labels defined in RGBDS source, by line number (from 1).
+fn labels(source: &str) -> HashMap<usize, String> {
+    let mut labels = HashMap::new();
+    let mut parent: Option<String> = None;
+    for (i, line) in source.lines().enumerate() {
+        if let Some(label) = label(line, parent.as_deref()) {
+            if !label.contains('.') {
+                parent = Some(label.clone());
+            }
+            labels.insert(i + 1, label);
+        }
+    }
+    labels
+}
+
+/// Label a breakpoint on the line binds to.
+fn label_for_line(source: &str, labels: &HashMap<usize, String>, line: usize) -> Option<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    for n in (1..=line.min(lines.len())).rev() {
+        if let Some(label) = labels.get(&n) {
+            return Some(label.clone());
+        }
+        // The first instruction after a label binds to it too.
+        if n != line && !is_blank(lines[n - 1]) {
+            return None;
+        }
+    }
+    None
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn parse_address(s: &str) -> Option<u16> {
+    let s = s.trim_start_matches("0x");
+    u16::from_str_radix(s, 16).ok()
+}
+
+fn variable(name: &str, value: String, memory: Option<u16>) -> Value {
+    let mut variable = json!({ "name": name, "value": value, "variablesReference": 0 });
+    if let Some(addr) = memory {
+        variable["memoryReference"] = format!("0x{:04x}", addr).into();
+    }
+    variable
+}
+
+/// DAP server listening on localhost, polled once per frame.
+pub struct DapServer {
+    listener: TcpListener,
+    client: Option<Client>,
+    symbols: Option<Symbols>,
+    source_map: Option<SourceMap>,
+    /// Source lines of the labels and breakpoints in files seen in
+    /// `setBreakpoints`, to show where the game stopped.
+    locations: HashMap<u16, (String, usize)>,
+    /// Breakpoints set for each source file, to replace on the next
+    /// `setBreakpoints`.
+    breakpoints: HashMap<String, Vec<Address>>,
+    function_breakpoints: Vec<Address>,
+    /// Lines for the client's debug console, sent on the next poll.
+    output: Vec<String>,
+}
+
+impl DapServer {
+    pub fn bind(port: u16) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        log::info!("DAP server listening on {}", listener.local_addr()?);
+        Ok(Self {
+            listener,
+            client: None,
+            symbols: None,
+            source_map: None,
+            locations: HashMap::new(),
+            breakpoints: HashMap::new(),
+            function_breakpoints: Vec::new(),
+            output: Vec::new(),
+        })
+    }
+
+    pub fn with_symbols(mut self, symbols: Option<Symbols>) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    pub fn with_source_map(mut self, source_map: Option<SourceMap>) -> Self {
+        self.source_map = source_map;
+        self
+    }
+
+    /// Show a logpoint message or debugger event in the client's debug
+    /// console.
+    pub fn output(&mut self, line: &str) {
+        if self.client.is_some() {
+            self.output.push(line.to_owned());
+        }
+    }
+
+    /// Accept a client, answer its requests and report when the debugger
+    /// stops. Returns whether a `launch` loaded a new ROM.
+    pub fn poll(&mut self, gameboy: &mut Gameboy, loader: &mut Loader) -> bool {
+        match self.try_poll(gameboy, loader) {
+            Ok(launched) => launched,
+            Err(e) => {
+                log::error!("DAP client disconnected: {}", e);
+                self.client = None;
+                false
+            }
+        }
+    }
+
+    fn try_poll(&mut self, gameboy: &mut Gameboy, loader: &mut Loader) -> std::io::Result<bool> {
+        if self.client.is_none() {
+            match self.listener.accept() {
+                Ok((stream, addr)) => {
+                    log::info!("DAP client connected from {}", addr);
+                    self.output.clear();
+                    stream.set_nonblocking(true)?;
+                    stream.set_nodelay(true)?;
+                    self.client = Some(Client {
+                        stream,
+                        buffer: Vec::new(),
+                        outgoing: Vec::new(),
+                        seq: 0,
+                        waiting: None,
+                        stop_on_entry: false,
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(false),
+                Err(e) => return Err(e),
+            }
+        }
+        let Some(client) = self.client.as_mut() else {
+            return Ok(false);
+        };
+        client.flush()?;
+        for line in self.output.drain(..) {
+            let output = format!("{}\n", line);
+            client.event("output", json!({ "category": "console", "output": output }))?;
+        }
+
+        if let Some(reason) = client.waiting {
+            if gameboy.debugger.as_ref().is_some_and(|d| d.is_paused()) {
+                client.waiting = None;
+                client.event(
+                    "stopped",
+                    json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+                )?;
+            }
+        }
+
+        let Some(requests) = client.receive()? else {
+            log::info!("DAP client disconnected");
+            self.client = None;
+            return Ok(false);
+        };
+        let mut launched = false;
+        for request in requests {
+            let command = request["command"].as_str().unwrap_or_default().to_owned();
+            let args = &request["arguments"];
+            let mut response = json!({
+                "type": "response",
+                "request_seq": request["seq"],
+                "command": command,
+            });
+            match self.handle(gameboy, loader, &command, args) {
+                Ok(body) => {
+                    response["success"] = true.into();
+                    response["body"] = body;
+                }
+                Err(message) => {
+                    response["success"] = false.into();
+                    response["message"] = message.into();
+                }
+            }
+            let Some(client) = self.client.as_mut() else {
+                break;
+            };
+            client.send(response)?;
+
+            match command.as_str() {
+                "launch" | "attach" => {
+                    launched |= command == "launch";
+                    client.event("initialized", json!({}))?;
+                }
+                "configurationDone" if client.stop_on_entry => {
+                    client.event(
+                        "stopped",
+                        json!({ "reason": "entry", "threadId": THREAD_ID, "allThreadsStopped": true }),
+                    )?;
+                }
+                "disconnect" => {
+                    log::info!("DAP client disconnected");
+                    self.client = None;
+                    break;
+                }
+                _ => (),
+            }
+        }
+        Ok(launched)
+    }
+
+    fn handle(
+        &mut self,
+        gameboy: &mut Gameboy,
+        loader: &mut Loader,
+        command: &str,
+        args: &Value,
+    ) -> Result<Value, String> {
+        match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsFunctionBreakpoints": true,
+                "supportsConditionalBreakpoints": true,
+                "supportsHitConditionalBreakpoints": true,
+                "supportsLogPoints": true,
+                "supportsEvaluateForHovers": true,
+                "supportsSetVariable": true,
+                "supportsReadMemoryRequest": true,
+            })),
+            "launch" => {
+                if let Some(path) = args["symbols"].as_str() {
+                    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+                    let symbols =
+                        Symbols::try_from(file).map_err(|e| format!("{}: {}", path, e))?;
+                    self.symbols = Some(symbols);
+                }
+                if let Some(path) = args["lines"].as_str() {
+                    let source_map =
+                        SourceMap::load(path).map_err(|e| format!("{}: {}", path, e))?;
+                    self.source_map = Some(source_map);
+                }
+                if let Some(program) = args["program"].as_str() {
+                    let new_loader = Loader::new(Rom::File, Some(program.to_owned()))
+                        .with_symbols(self.symbols.clone())
+                        .with_source_map(self.source_map.clone());
+                    *gameboy = new_loader
+                        .load_rom()
+                        .map_err(|e| format!("{}: {}", program, e))?;
+                    *loader = new_loader;
+                }
+                self.attach(gameboy, args)
+            }
+            "attach" => self.attach(gameboy, args),
+            "setBreakpoints" => self.set_breakpoints(gameboy, args),
+            "setFunctionBreakpoints" => self.set_function_breakpoints(gameboy, args),
+            "setExceptionBreakpoints" => Ok(json!({})),
+            "configurationDone" => {
+                let client = self.client.as_mut().ok_or("not connected")?;
+                if !client.stop_on_entry {
+                    client.waiting = Some("breakpoint");
+                    Self::command(gameboy, Command::Continue)?;
+                }
+                Ok(json!({}))
+            }
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "SM83" }] })),
+            "stackTrace" => self.stack_trace(gameboy),
+            "scopes" => Ok(json!({ "scopes": [
+                { "name": "Registers", "variablesReference": REGISTERS, "expensive": false },
+                { "name": "I/O", "variablesReference": IO, "expensive": false },
+            ]})),
+            "variables" => Self::variables(gameboy, args),
+            "setVariable" => Self::set_variable(gameboy, args),
+            "evaluate" => self.evaluate(gameboy, args),
+            "readMemory" => Self::read_memory(gameboy, args),
+            "continue" => self.resume(gameboy, Command::Continue, "breakpoint"),
+            "next" => self.resume(gameboy, Command::StepOver, "step"),
+            "stepIn" => self.resume(gameboy, Command::Step, "step"),
+            "stepOut" => self.resume(gameboy, Command::StepOut, "step"),
+            "pause" => {
+                self.resume(gameboy, Command::Pause, "pause")?;
+                Ok(json!({}))
+            }
+            "disconnect" => {
+                // Leave the game running for the next session.
+                Self::command(gameboy, Command::Continue)?;
+                Ok(json!({}))
+            }
+            _ => Err(format!("unsupported request {}", command)),
+        }
+    }
+
+    fn command(gameboy: &mut Gameboy, command: Command) -> Result<(), String> {
+        let debugger = gameboy.debugger.as_mut().ok_or("no debugger attached")?;
+        debugger.command(command);
+        Ok(())
+    }
+
+    /// Hold the game until the client has sent its breakpoints.
+    fn attach(&mut self, gameboy: &mut Gameboy, args: &Value) -> Result<Value, String> {
+        let client = self.client.as_mut().ok_or("not connected")?;
+        client.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+        Self::command(gameboy, Command::Pause)?;
+        Ok(json!({}))
+    }
+
+    fn resume(
+        &mut self,
+        gameboy: &mut Gameboy,
+        command: Command,
+        reason: &'static str,
+    ) -> Result<Value, String> {
+        let client = self.client.as_mut().ok_or("not connected")?;
+        client.waiting = Some(reason);
+        Self::command(gameboy, command)?;
+        Ok(json!({ "allThreadsContinued": true }))
+    }
+
+    /// Build a breakpoint from the condition, hit count and log message of a
+    /// DAP breakpoint.
+    fn breakpoint(addr: Address, args: &Value) -> Result<Breakpoint, String> {
+        let mut breakpoint = Breakpoint::new(addr);
+        if let Some(condition) = args["condition"].as_str() {
+            breakpoint = breakpoint.with_condition(condition)?;
+        }
+        if let Some(hit_count) = args["hitCondition"].as_str() {
+            let hit_count = hit_count
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid hit count {}", hit_count))?;
+            breakpoint = breakpoint.with_hit_count(hit_count);
+        }
+        if let Some(message) = args["logMessage"].as_str() {
+            breakpoint = breakpoint.with_log(message)?;
+        }
+        Ok(breakpoint)
+    }
+
+    fn set_breakpoints(&mut self, gameboy: &mut Gameboy, args: &Value) -> Result<Value, String> {
+        let path = args["source"]["path"]
+            .as_str()
+            .ok_or("missing source path")?
+            .to_owned();
+        let source = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+        let labels = labels(&source);
+
+        let debugger = gameboy.debugger.as_mut().ok_or("no debugger attached")?;
+        for addr in self.breakpoints.remove(&path).unwrap_or_default() {
+            debugger.command(Command::RemoveBreakpoint(addr));
+        }
+
+        self.locations.retain(|_, (source, _)| *source != path);
+        if let Some(ref symbols) = self.symbols {
+            for (line, label) in &labels {
+                if let Some(addr) = symbols.get_address(label) {
+                    self.locations.insert(addr.addr, (path.clone(), *line));
+                }
+            }
+        }
+
+        let mut addresses = Vec::new();
+        let mut breakpoints = Vec::new();
+        for requested in args["breakpoints"].as_array().into_iter().flatten() {
+            let line = requested["line"].as_u64().unwrap_or_default() as usize;
+            // Line info gives the address of the line, or else the label
+            // on or above it does.
+            let mapped = self
+                .source_map
+                .as_ref()
+                .and_then(|source_map| source_map.address(&path, line));
+            let resolved = match mapped {
+                Some(addr) => Ok(addr),
+                None => label_for_line(&source, &labels, line)
+                    .ok_or_else(|| "no label on or directly above this line".to_owned())
+                    .and_then(|label| {
+                        let symbols = self.symbols.as_ref().ok_or("no symbol file loaded")?;
+                        symbols
+                            .get_address(&label)
+                            .ok_or_else(|| format!("{} is not in the symbol file", label))
+                    }),
+            }
+            .and_then(|addr| Ok((addr, Self::breakpoint(addr, requested)?)));
+            // Breakpoints on lines without code move to the next line.
+            let line = mapped
+                .and_then(|addr| self.source_map.as_ref()?.location(addr))
+                .map_or(line, |(_, line)| line);
+            match resolved {
+                Ok((addr, breakpoint)) => {
+                    debugger.command(Command::InsertBreakpoint(breakpoint));
+                    addresses.push(addr);
+                    self.locations.insert(addr.addr, (path.clone(), line));
+                    breakpoints.push(json!({
+                        "verified": true,
+                        "line": line,
+                        "instructionReference": format!("0x{:04x}", addr.addr),
+                    }));
+                }
+                Err(message) => {
+                    breakpoints
+                        .push(json!({ "verified": false, "line": line, "message": message }));
+                }
+            }
+        }
+        self.breakpoints.insert(path, addresses);
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn set_function_breakpoints(
+        &mut self,
+        gameboy: &mut Gameboy,
+        args: &Value,
+    ) -> Result<Value, String> {
+        let debugger = gameboy.debugger.as_mut().ok_or("no debugger attached")?;
+        for addr in self.function_breakpoints.drain(..) {
+            debugger.command(Command::RemoveBreakpoint(addr));
+        }
+
+        let mut breakpoints = Vec::new();
+        for requested in args["breakpoints"].as_array().into_iter().flatten() {
+            let name = requested["name"].as_str().unwrap_or_default();
+            // Accept plain addresses too, e.g. `0150` or `03:4123`.
+            let addr = self
+                .symbols
+                .as_ref()
+                .and_then(|symbols| symbols.get_address(name))
+                .or_else(|| name.parse::<Address>().ok());
+            let resolved = addr
+                .ok_or_else(|| format!("unknown function {}", name))
+                .and_then(|addr| Self::breakpoint(addr, requested));
+            match resolved {
+                Ok(breakpoint) => {
+                    breakpoints.push(json!({
+                        "verified": true,
+                        "instructionReference": format!("0x{:04x}", breakpoint.addr.addr),
+                    }));
+                    self.function_breakpoints.push(breakpoint.addr);
+                    debugger.command(Command::InsertBreakpoint(breakpoint));
+                }
+                Err(message) => breakpoints.push(json!({ "verified": false, "message": message })),
+            }
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn stack_trace(&self, gameboy: &Gameboy) -> Result<Value, String> {
+        let debugger = gameboy.debugger.as_ref().ok_or("no debugger attached")?;
+        let symbol = |addr: u16| {
+            self.symbols
+                .as_ref()
+                .and_then(|symbols| symbols.get_symbol(addr))
+        };
+
+        // Each call's frame is at the caller of the call above it.
+        let mut frames = Vec::new();
+        let mut pc = gameboy.cpu.pc;
+        for call in debugger.get_callstack().iter().rev() {
+            let name = match call.symbol() {
+                Some(symbol) => symbol.to_owned(),
+                None => format!("0x{:04x}", call.addr()),
+            };
+            frames.push((name, pc));
+            pc = call.caller();
+        }
+        frames.push((symbol(pc).unwrap_or_else(|| "entry".to_owned()), pc));
+
+        let frames: Vec<Value> = frames
+            .into_iter()
+            .enumerate()
+            .map(|(id, (name, pc))| {
+                let mut frame = json!({
+                    "id": id,
+                    "name": format!("{} (0x{:04x})", name, pc),
+                    "line": 0,
+                    "column": 0,
+                    "instructionPointerReference": format!("0x{:04x}", pc),
+                });
+                let mapped = self.source_map.as_ref().and_then(|source_map| {
+                    let (file, line) = source_map.location(Address::current(pc, &gameboy.mem))?;
+                    Some((file.full_path().to_string_lossy().into_owned(), line))
+                });
+                let location = mapped.as_ref().or_else(|| self.locations.get(&pc));
+                if let Some((path, line)) = location {
+                    frame["source"] = json!({ "path": path });
+                    frame["line"] = (*line).into();
+                    frame["column"] = 1.into();
+                }
+                frame
+            })
+            .collect();
+        Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+    }
+
+    fn variables(gameboy: &mut Gameboy, args: &Value) -> Result<Value, String> {
+        let cpu = &gameboy.cpu;
+        let variables: Vec<Value> = match args["variablesReference"].as_u64() {
+            Some(REGISTERS) => {
+                let mut variables: Vec<Value> = ["a", "f", "b", "c", "d", "e", "h", "l"]
+                    .into_iter()
+                    .map(|name| {
+                        let value = name.parse::<Register>().unwrap().get(cpu);
+                        variable(name, format!("0x{:02x}", value), None)
+                    })
+                    .collect();
+                for name in ["bc", "de", "hl", "sp", "pc"] {
+                    let value = name.parse::<Register>().unwrap().get(cpu);
+                    variables.push(variable(name, format!("0x{:04x}", value), Some(value)));
+                }
+                variables.push(variable("ime", cpu.ie.to_string(), None));
+                variables.push(variable("flags", cpu.f.to_string(), None));
+                variables
+            }
+            Some(IO) => IO_REGISTERS
+                .iter()
+                // Skip aliases such as `joyp` for `p1`.
+                .filter(|(name, addr)| {
+                    IO_REGISTERS.iter().find(|(_, a)| a == addr).unwrap().0 == *name
+                })
+                .map(|(name, addr)| {
+                    let value = ProgramMemory::get_u8(&gameboy.mem, *addr);
+                    variable(name, format!("0x{:02x}", value), None)
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        Ok(json!({ "variables": variables }))
+    }
+
+    fn set_variable(gameboy: &mut Gameboy, args: &Value) -> Result<Value, String> {
+        let name = args["name"].as_str().unwrap_or_default();
+        let value: Expr = args["value"].as_str().unwrap_or_default().parse()?;
+        let value = value.eval(&gameboy.cpu, &gameboy.mem);
+        match args["variablesReference"].as_u64() {
+            Some(REGISTERS) => {
+                let register = name
+                    .parse::<Register>()
+                    .map_err(|_| format!("{} can't be set", name))?;
+                register.set(&mut gameboy.cpu, value);
+                let value = register.get(&gameboy.cpu);
+                Ok(json!({ "value": format!("0x{:02x}", value) }))
+            }
+            Some(IO) => {
+                let (_, addr) = IO_REGISTERS
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .ok_or_else(|| format!("unknown register {}", name))?;
+                ProgramMemory::set_u8(&mut gameboy.mem, *addr, value as u8);
+                let value = ProgramMemory::get_u8(&gameboy.mem, *addr);
+                Ok(json!({ "value": format!("0x{:02x}", value) }))
+            }
+            _ => Err(format!("{} can't be set", name)),
+        }
+    }
+
+    /// Evaluate a label, which reads the byte it points to, or an expression.
+    fn evaluate(&self, gameboy: &Gameboy, args: &Value) -> Result<Value, String> {
+        let expression = args["expression"].as_str().unwrap_or_default().trim();
+        let label = self
+            .symbols
+            .as_ref()
+            .and_then(|symbols| symbols.get_address(expression));
+        if let Some(Address { addr, .. }) = label {
+            let value = ProgramMemory::get_u8(&gameboy.mem, addr);
+            return Ok(json!({
+                "result": format!("[0x{:04x}] = 0x{:02x}", addr, value),
+                "variablesReference": 0,
+                "memoryReference": format!("0x{:04x}", addr),
+            }));
+        }
+        let expr: Expr = expression.parse()?;
+        let value = expr.eval(&gameboy.cpu, &gameboy.mem);
+        Ok(json!({ "result": format!("0x{:04x} ({})", value, value), "variablesReference": 0 }))
+    }
+
+    fn read_memory(gameboy: &Gameboy, args: &Value) -> Result<Value, String> {
+        let reference = args["memoryReference"].as_str().unwrap_or_default();
+        let base =
+            parse_address(reference).ok_or_else(|| format!("invalid address {}", reference))?;
+        let start = base as i64 + args["offset"].as_i64().unwrap_or_default();
+        let count = (args["count"].as_u64().unwrap_or_default() as usize).min(MAX_READ);
+        if !(0..=0xffff).contains(&start) {
+            return Ok(json!({ "address": reference, "unreadableBytes": count }));
+        }
+        let start = start as u16;
+        let count = count.min(0x10000 - start as usize);
+        let data: Vec<u8> = (0..count)
+            .map(|i| ProgramMemory::get_u8(&gameboy.mem, start.wrapping_add(i as u16)))
+            .collect();
+        Ok(json!({ "address": format!("0x{:04x}", start), "data": base64(&data) }))
+    }
+}
@@ -0,0 +1,515 @@
+//! Code and data analysis by recursive traversal: control flow is followed
+//! from the entry point, the RST and interrupt vectors and addresses seen
+//! executing, and bytes it never reaches are taken to be data.
+//!
+//! Jumps into the switchable bank from bank 0 only go somewhere known when
+//! the bank was just selected with `ld a, n` and `ld (2000), a`, or the
+//! cartridge only has one switchable bank. Jump tables are found from
+//! `ld hl, table` before `jp hl`, and read once everything else has been
+//! followed, up to the first entry that isn't a ROM address or runs into
+//! code or an earlier entry's target.
+//!
+//! Along the way it records what each instruction refers to, for
+//! cross-references and the call graph.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use crate::address::Address;
+use crate::mbc::Mbc;
+use crate::memory::Memory;
+use crate::opcodes::{self, Flow, Operand};
+
+/// Where code starts in bank 0.
+const VECTORS: &[u16] = &[
+    0x00, 0x08, 0x10, 0x18, 0x20, 0x28, 0x30, 0x38, 0x40, 0x48, 0x50, 0x58, 0x60,
+];
+const ENTRY_POINT: u16 = 0x100;
+/// Most entries read from a jump table.
+const MAX_TABLE_ENTRIES: usize = 128;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Kind {
+    /// Not reached from any code.
+    #[default]
+    Data,
+    /// First byte of an instruction.
+    Code,
+    /// Later bytes of an instruction.
+    Operand,
+    /// Entry of a jump table.
+    Table,
+}
+
+/// How an instruction refers to an address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Access {
+    Jump,
+    Call,
+    Read,
+    Write,
+    /// Loaded into a register pair, e.g. `ld hl, table`.
+    Pointer,
+    /// Jump table entry.
+    Table,
+}
+
+impl Display for Access {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Jump => "jump",
+            Self::Call => "call",
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Pointer => "pointer",
+            Self::Table => "table",
+        };
+        f.pad(s)
+    }
+}
+
+/// Instruction or jump table entry referring to an address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Reference {
+    pub from: Address,
+    pub access: Access,
+}
+
+pub struct Analysis {
+    /// Kind of each byte of each ROM bank.
+    banks: Vec<Vec<Kind>>,
+    functions: BTreeSet<Address>,
+    jump_tables: BTreeMap<Address, Vec<Address>>,
+    /// References to each address. Those in the switchable bank from code
+    /// that doesn't know which bank is mapped have no bank, as does RAM.
+    references: BTreeMap<Address, BTreeSet<Reference>>,
+    /// Calls between functions, as the caller and the function called.
+    calls: BTreeSet<(Address, Address)>,
+}
+
+/// Code to follow, with the bank known to be mapped at 0x4000 there.
+type Work = (Address, Option<u16>);
+
+fn origin(bank: u16) -> u16 {
+    if bank == 0 {
+        0x0000
+    } else {
+        0x4000
+    }
+}
+
+/// Name for a function without a label, e.g. `fn_0150` or `fn_01_4000`.
+pub fn function_name(addr: Address) -> String {
+    match addr.bank {
+        Some(0) | None => format!("fn_{:04x}", addr.addr),
+        Some(bank) => format!("fn_{:02x}_{:04x}", bank, addr.addr),
+    }
+}
+
+/// ROM address of a jump target, if the bank it is in is known.
+fn resolve(addr: u16, rom_bank: Option<u16>) -> Option<Address> {
+    match addr {
+        0x0000..=0x3fff => Some(Address::banked(0, addr)),
+        0x4000..=0x7fff => rom_bank.map(|bank| Address::banked(bank, addr)),
+        _ => None,
+    }
+}
+
+impl Analysis {
+    /// Analyse the cartridge ROM, also following code from `executed`.
+    pub fn new(mem: &Memory, executed: impl IntoIterator<Item = Address>) -> Self {
+        let mut roms = Vec::new();
+        while let Some(rom) = mem.mbc.rom_bank_data(roms.len() as u16) {
+            roms.push(rom);
+        }
+        let mut analysis = Self {
+            banks: roms.iter().map(|rom| vec![Kind::Data; rom.len()]).collect(),
+            functions: BTreeSet::new(),
+            jump_tables: BTreeMap::new(),
+            references: BTreeMap::new(),
+            calls: BTreeSet::new(),
+        };
+
+        // With a single switchable bank, it's always the one mapped in.
+        let only_bank = (roms.len() == 2).then_some(1);
+        let mut work: Vec<Work> = VECTORS
+            .iter()
+            .chain([&ENTRY_POINT])
+            .map(|addr| (Address::banked(0, *addr), only_bank))
+            .collect();
+        analysis
+            .functions
+            .extend(work.iter().map(|(addr, _)| *addr));
+        for addr in executed {
+            if let (Some(bank), 0x0000..=0x7fff) = (addr.bank, addr.addr) {
+                let rom_bank = if bank == 0 { only_bank } else { Some(bank) };
+                work.push((addr, rom_bank));
+            }
+        }
+
+        let mut tables = Vec::new();
+        while !work.is_empty() || !tables.is_empty() {
+            while let Some(item) = work.pop() {
+                analysis.follow(&roms, item, &mut work, &mut tables);
+            }
+            // Tables last, so that code after them is already known.
+            while let Some((table, rom_bank)) = tables.pop() {
+                analysis.jump_table(&roms, table, rom_bank, &mut work);
+            }
+        }
+
+        let calls = analysis.references.iter().flat_map(|(target, references)| {
+            references
+                .iter()
+                .filter(|reference| reference.access == Access::Call)
+                .filter_map(|reference| analysis.function_at(reference.from))
+                .map(|caller| (caller, *target))
+        });
+        analysis.calls = calls.collect();
+        analysis
+    }
+
+    fn kind_mut(&mut self, addr: Address) -> Option<&mut Kind> {
+        let bank = addr.bank?;
+        let offset = addr.addr.checked_sub(origin(bank))?;
+        self.banks.get_mut(bank as usize)?.get_mut(offset as usize)
+    }
+
+    /// What the byte at a ROM address is, or `None` outside ROM.
+    pub fn kind(&self, addr: Address) -> Option<Kind> {
+        let bank = addr.bank?;
+        let offset = addr.addr.checked_sub(origin(bank))?;
+        self.banks.get(bank as usize)?.get(offset as usize).copied()
+    }
+
+    /// Entry point, vectors and call targets.
+    pub fn functions(&self) -> &BTreeSet<Address> {
+        &self.functions
+    }
+
+    /// Function an address is in, taken to be the closest one before it in
+    /// the same bank.
+    pub fn function_at(&self, addr: Address) -> Option<Address> {
+        self.functions
+            .range(..=addr)
+            .next_back()
+            .filter(|function| function.bank == addr.bank)
+            .copied()
+    }
+
+    /// Instructions referring to an address, also counting those that refer
+    /// to it in whichever bank is mapped.
+    pub fn references_to(&self, addr: Address) -> impl Iterator<Item = &Reference> {
+        let unbanked = Address::new(addr.addr);
+        let unbanked = (unbanked != addr).then(|| self.references.get(&unbanked));
+        self.references
+            .get(&addr)
+            .into_iter()
+            .chain(unbanked.flatten())
+            .flatten()
+    }
+
+    /// Calls found between functions, as the caller and the function
+    /// called.
+    pub fn calls(&self) -> &BTreeSet<(Address, Address)> {
+        &self.calls
+    }
+
+    fn refer(&mut self, from: Address, to: Address, access: Access) {
+        let reference = Reference { from, access };
+        self.references.entry(to).or_default().insert(reference);
+    }
+
+    /// Mark the instructions from `addr` on as code until control flow
+    /// leaves, queuing jump and call targets.
+    fn follow(
+        &mut self,
+        roms: &[&[u8]],
+        (addr, mut rom_bank): Work,
+        work: &mut Vec<Work>,
+        tables: &mut Vec<Work>,
+    ) {
+        let Some(bank) = addr.bank else {
+            return;
+        };
+        let Some(rom) = roms.get(bank as usize) else {
+            return;
+        };
+        if bank != 0 {
+            rom_bank = Some(bank);
+        }
+        let origin = origin(bank);
+        let mut pc = addr.addr;
+        // Recent `ld a, n` and `ld hl, nn`, for bank switches and tables.
+        let mut last_a = None;
+        let mut last_hl = None;
+        loop {
+            let offset = (pc - origin) as usize;
+            let Some(byte) = rom.get(offset).copied() else {
+                return;
+            };
+            let opcode = opcodes::decode(byte, rom.get(offset + 1).copied().unwrap_or_default());
+            let len = opcode.length as usize;
+            if opcode.flow == Flow::Illegal || offset + len > rom.len() {
+                return;
+            }
+            // Stop at code already followed, or bytes it overlaps.
+            let here = |i: usize| Address::banked(bank, pc + i as u16);
+            if (0..len).any(|i| self.kind(here(i)) != Some(Kind::Data)) {
+                return;
+            }
+            for i in 0..len {
+                if let Some(kind) = self.kind_mut(here(i)) {
+                    *kind = if i == 0 { Kind::Code } else { Kind::Operand };
+                }
+            }
+
+            let imm = match len {
+                2 if byte != 0xcb => u16::from(rom[offset + 1]),
+                3 => u16::from_le_bytes([rom[offset + 1], rom[offset + 2]]),
+                _ => 0,
+            };
+            match byte {
+                // ld a, n
+                0x3e => last_a = Some(imm as u8),
+                // ld (nn), a to the ROM bank register
+                0xea if (0x2000..0x4000).contains(&imm) && bank == 0 => {
+                    rom_bank = last_a.map(|a| u16::from(a.max(1)));
+                }
+                // ld hl, nn
+                0x21 => last_hl = Some(imm),
+                // jp hl
+                0xe9 => {
+                    if let Some(table) = last_hl.and_then(|table| resolve(table, rom_bank)) {
+                        tables.push((table, rom_bank));
+                    }
+                }
+                _ => (),
+            }
+
+            let from = here(0);
+            let address = |addr| resolve(addr, rom_bank).unwrap_or(Address::new(addr));
+            for (idx, operand) in opcode.operands.iter().enumerate() {
+                let data = match operand {
+                    Operand::IndImm16 => imm,
+                    Operand::IndHighImm8 => 0xff00 | imm,
+                    // As in the disassembly, small values are constants.
+                    Operand::Imm16 if opcode.target(pc, imm).is_none() && imm >= 0x100 => {
+                        self.refer(from, address(imm), Access::Pointer);
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let access = if idx == 0 {
+                    Access::Write
+                } else {
+                    Access::Read
+                };
+                self.refer(from, address(data), access);
+            }
+
+            if let Some(target) = opcode.target(pc, imm) {
+                let access = if opcode.flow == Flow::Call {
+                    Access::Call
+                } else {
+                    Access::Jump
+                };
+                self.refer(from, address(target), access);
+                if let Some(target) = resolve(target, rom_bank) {
+                    if opcode.flow == Flow::Call {
+                        self.functions.insert(target);
+                    }
+                    work.push((target, rom_bank));
+                }
+            }
+
+            let unconditional = opcode.condition().is_none();
+            if matches!(opcode.flow, Flow::Jump | Flow::Return) && unconditional {
+                return;
+            }
+            pc += len as u16;
+        }
+    }
+
+    fn jump_table(
+        &mut self,
+        roms: &[&[u8]],
+        table: Address,
+        rom_bank: Option<u16>,
+        work: &mut Vec<Work>,
+    ) {
+        if self.jump_tables.contains_key(&table) {
+            return;
+        }
+        let Some(bank) = table.bank else {
+            return;
+        };
+        let Some(rom) = roms.get(bank as usize) else {
+            return;
+        };
+        let mut entries = Vec::new();
+        for i in 0..MAX_TABLE_ENTRIES as u16 {
+            let entry = Address::banked(bank, table.addr + 2 * i);
+            let second = Address::banked(bank, entry.addr + 1);
+            let is_data = |addr| self.kind(addr) == Some(Kind::Data);
+            // Code often follows straight after the table.
+            let is_target = |addr| entries.contains(&addr);
+            if !is_data(entry) || !is_data(second) || is_target(entry) || is_target(second) {
+                break;
+            }
+            let offset = (entry.addr - origin(bank)) as usize;
+            let target = u16::from_le_bytes([rom[offset], rom[offset + 1]]);
+            let Some(target) = resolve(target, rom_bank) else {
+                break;
+            };
+            for addr in [entry, second] {
+                if let Some(kind) = self.kind_mut(addr) {
+                    *kind = Kind::Table;
+                }
+            }
+            self.refer(entry, target, Access::Table);
+            entries.push(target);
+        }
+        work.extend(entries.iter().map(|target| (*target, rom_bank)));
+        if !entries.is_empty() {
+            self.jump_tables.insert(table, entries);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_map::MemoryMap;
+
+    /// MBC1 ROM of `banks` banks filled with an illegal opcode, so only the
+    /// code given at each offset is followed.
+    fn rom(banks: usize, code: &[(usize, &[u8])]) -> Memory {
+        let mut data = vec![0xd3; banks * 0x4000];
+        data[MemoryMap::HeaderCartridgeType as usize] = 0x01;
+        data[MemoryMap::HeaderRomSize as usize] = (banks.trailing_zeros() - 1) as u8;
+        data[MemoryMap::HeaderRamSize as usize] = 0x00;
+        for (offset, bytes) in code {
+            data[*offset..offset + bytes.len()].copy_from_slice(bytes);
+        }
+        Memory::from(data)
+    }
+
+    fn rom0(addr: u16) -> Address {
+        Address::banked(0, addr)
+    }
+
+    #[test]
+    fn call_into_bank_0() {
+        let mem = rom(
+            2,
+            &[
+                // call $4000; jp $0103
+                (0x100, &[0xcd, 0x00, 0x40, 0xc3, 0x03, 0x01]),
+                // call $0200; ret
+                (0x4000, &[0xcd, 0x00, 0x02, 0xc9]),
+                // ret
+                (0x200, &[0xc9]),
+            ],
+        );
+        let analysis = Analysis::new(&mem, []);
+        let bank1 = Address::banked(1, 0x4000);
+        assert_eq!(analysis.kind(bank1), Some(Kind::Code));
+        assert_eq!(
+            analysis.kind(Address::banked(1, 0x4001)),
+            Some(Kind::Operand)
+        );
+        assert_eq!(analysis.kind(Address::banked(1, 0x4003)), Some(Kind::Code));
+        assert_eq!(analysis.kind(rom0(0x200)), Some(Kind::Code));
+        assert_eq!(analysis.kind(Address::new(0x200)), None);
+
+        assert!(analysis.functions().contains(&bank1));
+        assert!(analysis.functions().contains(&rom0(0x200)));
+        assert_eq!(
+            analysis.function_at(Address::banked(1, 0x4003)),
+            Some(bank1)
+        );
+        assert_eq!(analysis.function_at(rom0(0x103)), Some(rom0(0x100)));
+        assert_eq!(analysis.function_at(rom0(0x200)), Some(rom0(0x200)));
+        assert!(analysis.calls().contains(&(rom0(0x100), bank1)));
+        assert!(analysis.calls().contains(&(bank1, rom0(0x200))));
+    }
+
+    #[test]
+    fn rst() {
+        let mem = rom(
+            2,
+            &[
+                // rst $18; jp $0101
+                (0x100, &[0xdf, 0xc3, 0x01, 0x01]),
+                // nop; ret
+                (0x18, &[0x00, 0xc9]),
+            ],
+        );
+        let analysis = Analysis::new(&mem, []);
+        assert_eq!(analysis.kind(rom0(0x18)), Some(Kind::Code));
+        assert_eq!(analysis.kind(rom0(0x19)), Some(Kind::Code));
+        // Execution carries on after the call.
+        assert_eq!(analysis.kind(rom0(0x101)), Some(Kind::Code));
+        assert_eq!(analysis.function_at(rom0(0x19)), Some(rom0(0x18)));
+        let reference = Reference {
+            from: rom0(0x100),
+            access: Access::Call,
+        };
+        assert!(analysis.references_to(rom0(0x18)).any(|r| *r == reference));
+        assert!(analysis.calls().contains(&(rom0(0x100), rom0(0x18))));
+    }
+
+    #[test]
+    fn jump_table() {
+        let mem = rom(
+            2,
+            &[
+                // ld hl, $0110; jp hl
+                (0x100, &[0x21, 0x10, 0x01, 0xe9]),
+                // dw $0120, $0130, then a RAM address ending the table
+                (0x110, &[0x20, 0x01, 0x30, 0x01, 0x00, 0xc0]),
+                (0x120, &[0xc9]),
+                (0x130, &[0xc9]),
+            ],
+        );
+        let analysis = Analysis::new(&mem, []);
+        for addr in 0x110..0x114 {
+            assert_eq!(analysis.kind(rom0(addr)), Some(Kind::Table), "{:04x}", addr);
+        }
+        assert_eq!(analysis.kind(rom0(0x114)), Some(Kind::Data));
+        assert_eq!(analysis.kind(rom0(0x120)), Some(Kind::Code));
+        assert_eq!(analysis.kind(rom0(0x130)), Some(Kind::Code));
+        // Table entries aren't calls, so they stay in the function that
+        // jumped through the table.
+        assert_eq!(analysis.function_at(rom0(0x130)), Some(rom0(0x100)));
+        let reference = Reference {
+            from: rom0(0x112),
+            access: Access::Table,
+        };
+        assert!(analysis.references_to(rom0(0x130)).any(|r| *r == reference));
+    }
+
+    #[test]
+    fn data_after_jump() {
+        let mem = rom(
+            2,
+            &[
+                // jp $0150; then bytes that would decode as ld a, 1
+                (0x100, &[0xc3, 0x50, 0x01, 0x3e, 0x01]),
+                // jr nz, $0150; jr $0150
+                (0x150, &[0x20, 0xfe, 0x18, 0xfc]),
+            ],
+        );
+        let analysis = Analysis::new(&mem, []);
+        assert_eq!(analysis.kind(rom0(0x100)), Some(Kind::Code));
+        assert_eq!(analysis.kind(rom0(0x102)), Some(Kind::Operand));
+        assert_eq!(analysis.kind(rom0(0x103)), Some(Kind::Data));
+        assert_eq!(analysis.kind(rom0(0x104)), Some(Kind::Data));
+        // Conditional jumps fall through.
+        assert_eq!(analysis.kind(rom0(0x152)), Some(Kind::Code));
+        assert_eq!(analysis.kind(rom0(0x154)), Some(Kind::Data));
+        assert_eq!(analysis.function_at(rom0(0x152)), Some(rom0(0x100)));
+        assert_eq!(analysis.function_at(Address::banked(1, 0x4000)), None);
+    }
+}
@@ -0,0 +1,338 @@
+//! Assembler for single SM83 instructions, for patching code while
+//! debugging. It takes RGBDS syntax and the syntax the disassembly is shown
+//! in, e.g. `ldh a, [$ff44]` or `ld a,(0xff00 + 0x44)`, and finds the
+//! encoding in the opcode tables.
+//!
+//! Numbers are `$` or `0x` hex, `%` binary or decimal, or labels. `db`
+//! assembles bytes as they are.
+
+use crate::opcodes::{Condition, Flow, Opcode, Operand, CB_OPCODES, OPCODES};
+use crate::symbols::Symbols;
+
+/// Operators `a` may be left out of, as in `cp $05`.
+const IMPLIED_A: &[&str] = &["add", "adc", "sub", "sbc", "and", "xor", "or", "cp"];
+
+/// Assemble one instruction at `addr`.
+pub fn assemble(text: &str, addr: u16, symbols: Option<&Symbols>) -> Result<Vec<u8>, String> {
+    let text = text.split(';').next().unwrap_or_default().trim();
+    let (mnemonic, operands) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let mnemonic = mnemonic.to_ascii_lowercase();
+    let operands: Vec<_> = operands
+        .split(',')
+        .map(str::trim)
+        .filter(|operand| !operand.is_empty())
+        .collect();
+    let assembler = Assembler { addr, symbols };
+
+    if mnemonic == "db" {
+        return operands
+            .iter()
+            .map(|operand| {
+                let value = assembler.value(operand)?;
+                u8::try_from(value)
+                    .or_else(|_| i8::try_from(value).map(|value| value as u8))
+                    .map_err(|_| format!("{} doesn't fit in a byte", operand))
+            })
+            .collect();
+    }
+
+    // `ldh` is how RGBDS writes `ld` to and from the high page.
+    let ldh = mnemonic == "ldh";
+    let candidates = OPCODES
+        .iter()
+        .enumerate()
+        .map(|(byte, opcode)| (None, byte, opcode));
+    let prefixed = CB_OPCODES
+        .iter()
+        .enumerate()
+        .map(|(byte, opcode)| (Some(0xcb), byte, opcode));
+    let mut known = false;
+    for (prefix, byte, opcode) in candidates.chain(prefixed) {
+        let name = if ldh { "ld" } else { &mnemonic };
+        let encodable = !matches!(opcode.flow, Flow::Prefix | Flow::Illegal);
+        if opcode.mnemonic != name || !encodable {
+            continue;
+        }
+        known = true;
+        let mut operands = operands.clone();
+        let implied = operands.len() + 1 == opcode.operands.len()
+            && opcode.operands.first() == Some(&Operand::A)
+            && IMPLIED_A.contains(&name);
+        if implied {
+            operands.insert(0, "a");
+        }
+        if operands.len() != opcode.operands.len() {
+            continue;
+        }
+        let high = opcode
+            .operands
+            .iter()
+            .any(|operand| matches!(operand, Operand::IndHighImm8 | Operand::IndHighC));
+        if ldh && !high {
+            continue;
+        }
+        let mut bytes: Vec<u8> = prefix.into_iter().chain([byte as u8]).collect();
+        let encoded = opcode
+            .operands
+            .iter()
+            .zip(&operands)
+            .try_for_each(|(operand, text)| {
+                let imm = assembler.operand(operand, opcode, text, ldh)?;
+                bytes.extend(imm);
+                Some(())
+            });
+        if encoded.is_some() {
+            // `stop` takes a second byte, which is 0.
+            bytes.resize(opcode.length.into(), 0);
+            return Ok(bytes);
+        }
+    }
+    if !known {
+        return Err(format!("unknown instruction {:?}", mnemonic));
+    }
+    if mnemonic == "jr" {
+        let target = operands.last().map(|target| assembler.value(target));
+        if let Some(Ok(target)) = target {
+            let offset = target - (i32::from(addr) + 2);
+            if !(-128..=127).contains(&offset) {
+                return Err(format!("jr target is {} bytes away, out of range", offset));
+            }
+        }
+    }
+    Err(format!(
+        "invalid operands for {}: {:?}",
+        mnemonic,
+        operands.join(", ")
+    ))
+}
+
+struct Assembler<'a> {
+    addr: u16,
+    symbols: Option<&'a Symbols>,
+}
+
+/// The inside of `[x]` or `(x)`, without spaces.
+fn indirect(text: &str) -> Option<String> {
+    let inner = text
+        .strip_prefix('[')
+        .and_then(|text| text.strip_suffix(']'))
+        .or_else(|| {
+            text.strip_prefix('(')
+                .and_then(|text| text.strip_suffix(')'))
+        })?;
+    Some(inner.chars().filter(|c| !c.is_whitespace()).collect())
+}
+
+/// The offset in `$ff00+n` or `0xff00+n`, or the register in `$ff00+c`.
+fn high_page(inner: &str) -> Option<&str> {
+    inner
+        .strip_prefix("$ff00+")
+        .or_else(|| inner.strip_prefix("0xff00+"))
+}
+
+impl Assembler<'_> {
+    /// A number or label.
+    fn value(&self, text: &str) -> Result<i32, String> {
+        let text = text.trim();
+        if let Some(text) = text.strip_prefix('-') {
+            return self.value(text).map(|value| -value);
+        }
+        let number = if let Some(hex) = text.strip_prefix('$').or_else(|| text.strip_prefix("0x")) {
+            i32::from_str_radix(hex, 16).ok()
+        } else if let Some(binary) = text.strip_prefix('%') {
+            i32::from_str_radix(binary, 2).ok()
+        } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+            text.parse().ok()
+        } else {
+            let label = self.symbols.and_then(|symbols| symbols.get_address(text));
+            label.map(|addr| i32::from(addr.addr))
+        };
+        number.ok_or_else(|| format!("invalid number or unknown label {:?}", text))
+    }
+
+    /// Encode an operand, or `None` if `text` isn't that operand.
+    fn operand(
+        &self,
+        operand: &Operand,
+        opcode: &Opcode,
+        text: &str,
+        ldh: bool,
+    ) -> Option<Vec<u8>> {
+        let lower = text.to_ascii_lowercase();
+        let register = |name: &str| (lower == name).then(Vec::new);
+        let pointer = |names: &[&str]| {
+            let inner = indirect(&lower)?;
+            names.contains(&inner.as_str()).then(Vec::new)
+        };
+        let value = |text: &str| self.value(text).ok();
+        let byte = |value: i32| (-128..=255).contains(&value).then(|| vec![value as u8]);
+        let word = |value: i32| {
+            let value = u16::try_from(value).ok()?;
+            Some(value.to_le_bytes().to_vec())
+        };
+        let signed = |value: i32| i8::try_from(value).ok().map(|value| vec![value as u8]);
+        match operand {
+            Operand::A => register("a"),
+            Operand::B => register("b"),
+            Operand::C => register("c"),
+            Operand::D => register("d"),
+            Operand::E => register("e"),
+            Operand::H => register("h"),
+            Operand::L => register("l"),
+            Operand::Af => register("af"),
+            Operand::Bc => register("bc"),
+            Operand::De => register("de"),
+            // `jp (hl)` is an old way of writing `jp hl`.
+            Operand::Hl if opcode.mnemonic == "jp" => register("hl").or_else(|| pointer(&["hl"])),
+            Operand::Hl => register("hl"),
+            Operand::Sp => register("sp"),
+            Operand::IndBc => pointer(&["bc"]),
+            Operand::IndDe => pointer(&["de"]),
+            Operand::IndHl => pointer(&["hl"]),
+            Operand::IndHlInc => pointer(&["hl+", "hli"]),
+            Operand::IndHlDec => pointer(&["hl-", "hld"]),
+            Operand::IndHighC => {
+                let inner = indirect(&lower)?;
+                (inner == "c" || high_page(&inner) == Some("c")).then(Vec::new)
+            }
+            Operand::Imm8 => byte(value(text)?),
+            Operand::Imm16 => word(value(text)?),
+            Operand::IndImm16 => word(value(&indirect(text)?)?),
+            Operand::IndHighImm8 => {
+                let inner = indirect(text)?;
+                let value = match high_page(&inner.to_ascii_lowercase()) {
+                    Some(_) => value(&inner[inner.find('+')? + 1..])?,
+                    // Only `ldh` takes the whole address.
+                    None if ldh => value(&inner)?,
+                    None => return None,
+                };
+                match value {
+                    0x00..=0xff => Some(vec![value as u8]),
+                    0xff00..=0xffff => Some(vec![value as u8]),
+                    _ => None,
+                }
+            }
+            Operand::Offset8 => signed(value(text)?),
+            Operand::SpOffset8 => {
+                let offset: String = lower.chars().filter(|c| !c.is_whitespace()).collect();
+                let offset = offset.strip_prefix("sp")?;
+                match offset.strip_prefix('+') {
+                    Some(offset) => signed(value(offset)?),
+                    None if offset.starts_with('-') => signed(value(offset)?),
+                    None => offset.is_empty().then(|| vec![0]),
+                }
+            }
+            Operand::Rel8 => {
+                let next = i32::from(self.addr) + i32::from(opcode.length);
+                signed(value(text)? - next)
+            }
+            Operand::Bit(bit) => (value(text)? == i32::from(*bit)).then(Vec::new),
+            Operand::Cond(Condition::Nz) => register("nz"),
+            Operand::Cond(Condition::Z) => register("z"),
+            Operand::Cond(Condition::Nc) => register("nc"),
+            Operand::Cond(Condition::C) => register("c"),
+            Operand::Vector(vector) => (value(text)? == i32::from(*vector)).then(Vec::new),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::*;
+    use crate::dasm::decoder::Decoder;
+    use crate::dasm::Syntax;
+
+    fn asm(text: &str) -> Result<Vec<u8>, String> {
+        assemble(text, 0x0100, None)
+    }
+
+    #[test]
+    fn high_page() {
+        assert_eq!(asm("ldh a, [$44]"), Ok(vec![0xf0, 0x44]));
+        assert_eq!(asm("ldh a, [$ff44]"), Ok(vec![0xf0, 0x44]));
+        assert_eq!(asm("ld a,(0xff00 + 0x44)"), Ok(vec![0xf0, 0x44]));
+        assert_eq!(asm("ldh [$ff80], a"), Ok(vec![0xe0, 0x80]));
+        // Without `ldh`, RGBDS takes the whole address.
+        assert_eq!(asm("ld a, [$ff44]"), Ok(vec![0xfa, 0x44, 0xff]));
+        assert_eq!(asm("ld a, [c]"), Ok(vec![0xf2]));
+        assert_eq!(asm("ldh [c], a"), Ok(vec![0xe2]));
+        assert!(asm("ldh a, [$c000]").is_err());
+    }
+
+    #[test]
+    fn operands() {
+        assert_eq!(asm("ld hl, sp-2"), Ok(vec![0xf8, 0xfe]));
+        assert_eq!(asm("ld hl, sp + $10"), Ok(vec![0xf8, 0x10]));
+        assert_eq!(asm("add sp, -1"), Ok(vec![0xe8, 0xff]));
+        assert_eq!(asm("cp $05"), Ok(vec![0xfe, 0x05]));
+        assert_eq!(asm("CP A, %101 ; compare"), Ok(vec![0xfe, 0x05]));
+        assert_eq!(asm("bit 3, [hl]"), Ok(vec![0xcb, 0x5e]));
+        assert_eq!(asm("res 0,a"), Ok(vec![0xcb, 0x87]));
+        assert_eq!(asm("rst $38"), Ok(vec![0xff]));
+        assert_eq!(asm("stop"), Ok(vec![0x10, 0x00]));
+        assert_eq!(asm("ld [hl+], a"), Ok(vec![0x22]));
+        assert_eq!(asm("ld a, [hld]"), Ok(vec![0x3a]));
+        assert_eq!(asm("jp (hl)"), Ok(vec![0xe9]));
+        assert_eq!(asm("db $01, 2, %11, -1"), Ok(vec![0x01, 0x02, 0x03, 0xff]));
+        assert!(asm("db $100").is_err());
+        assert!(asm("bit 8, a").is_err());
+        assert_eq!(
+            asm("frob a"),
+            Err("unknown instruction \"frob\"".to_owned())
+        );
+    }
+
+    #[test]
+    fn relative_jumps() {
+        let symbols: Symbols = "00:0150 Loop\n".parse().unwrap();
+        let symbols = Some(&symbols);
+        assert_eq!(
+            assemble("jr nz, Loop", 0x0160, symbols),
+            Ok(vec![0x20, 0xee])
+        );
+        assert_eq!(assemble("jr Loop", 0x0150, symbols), Ok(vec![0x18, 0xfe]));
+        assert_eq!(
+            assemble("call Loop", 0x0160, symbols),
+            Ok(vec![0xcd, 0x50, 0x01])
+        );
+        assert_eq!(asm("jr $0181"), Ok(vec![0x18, 0x7f]));
+        assert_eq!(
+            asm("jr $0182").map_err(|e| e.contains("out of range")),
+            Err(true)
+        );
+        assert!(asm("jr c, $0080").is_err());
+    }
+
+    /// Every instruction the disassembly writes assembles back to its bytes.
+    #[test]
+    fn round_trip() {
+        let prefixed = (0..=0xff).map(|byte| vec![0xcb, byte]);
+        let unprefixed = (0..=0xff)
+            .filter(|byte| !matches!(OPCODES[*byte as usize].flow, Flow::Prefix | Flow::Illegal))
+            .flat_map(|byte| {
+                // `stop` is only assembled with a 0 after it.
+                let imm: &[[u8; 2]] = match byte {
+                    0x10 => &[[0x00, 0x00]],
+                    _ => &[[0x12, 0x34], [0x92, 0xff]],
+                };
+                imm.iter().map(move |imm| vec![byte, imm[0], imm[1]])
+            });
+        for bytes in unprefixed.chain(prefixed) {
+            let (addr, instr) = Decoder::from_bytes(BytesMut::from(&bytes[..]), None)
+                .with_origin(0x0200)
+                .with_syntax(Syntax::Rgbds)
+                .next()
+                .unwrap();
+            assert!(!instr.repr().starts_with("db"), "{:02x?}", bytes);
+            let len = instr.length() as usize;
+            assert_eq!(
+                assemble(instr.repr(), addr, None).as_deref(),
+                Ok(&bytes[..len]),
+                "{}",
+                instr.repr()
+            );
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! RGBDS source for the whole ROM, which assembles and links back to the
+//! same bytes, as the start of a disassembly project:
+//!
+//! ```text
+//! rgbasm -o game.o game.asm
+//! rgblink -o game.gb game.o
+//! ```
+//!
+//! Each bank is a `SECTION` at its address. Code the analysis found is
+//! written as instructions and everything else as `db`, with labels from the
+//! symbols or generated for functions. Labels outside ROM become constants.
+//! Instructions are assembled as written, which RGBDS does since it stopped
+//! adding a `nop` after `halt` and turning `ld` into `ldh`.
+
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use bytes::BytesMut;
+
+use crate::dasm::decoder::Decoder;
+use crate::dasm::{entry_points, origin, Analysis, Syntax};
+use crate::mbc::Mbc;
+use crate::memory::Memory;
+use crate::symbols::Symbols;
+
+/// Write the cartridge ROM as RGBDS source.
+pub fn export_rgbds(
+    mem: &Memory,
+    analysis: &Analysis,
+    symbols: Option<&Symbols>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut defined = HashSet::new();
+
+    // Constants can't be local, so RAM locals are left as numbers.
+    let mut constants: Vec<_> = symbols
+        .into_iter()
+        .flat_map(Symbols::labels)
+        .filter(|(addr, name)| addr.addr >= 0x8000 && !name.contains('.'))
+        .collect();
+    constants.sort_by_key(|(addr, name)| (addr.addr, *name));
+    for (addr, name) in constants {
+        if defined.insert(name.to_owned()) {
+            writeln!(out, "DEF {} EQU ${:04x}", name, addr.addr)?;
+        }
+    }
+
+    let mut bank = 0;
+    while let Some(rom) = mem.mbc.rom_bank_data(bank) {
+        writeln!(out)?;
+        if bank == 0 {
+            writeln!(out, "SECTION \"ROM Bank $00\", ROM0[$0000]")?;
+        } else {
+            writeln!(
+                out,
+                "SECTION \"ROM Bank ${:02x}\", ROMX[$4000], BANK[${:02x}]",
+                bank, bank
+            )?;
+        }
+
+        let decoder = Decoder::from_bytes(BytesMut::from(rom), symbols)
+            .with_origin(origin(bank))
+            .with_rom_bank(Some(bank))
+            .with_entry_points(entry_points(bank, symbols, Some(analysis)))
+            .with_analysis(Some(analysis))
+            .with_syntax(Syntax::Rgbds);
+        let mut scope = String::new();
+        for (_, instr) in decoder {
+            // Labels without a bank are in every switchable bank, but can
+            // only be defined in one.
+            if let Some(symbol) = instr.symbol().filter(|symbol| !defined.contains(*symbol)) {
+                let local = symbol
+                    .strip_prefix(scope.as_str())
+                    .filter(|local| !scope.is_empty() && local.starts_with('.'));
+                match local {
+                    Some(local) => writeln!(out, "{}:", local)?,
+                    None => writeln!(out, "\n{}:", symbol)?,
+                }
+                defined.insert(symbol.to_owned());
+            }
+            if let Some(symbol) = instr.symbol() {
+                scope = symbol.split('.').next().unwrap_or(symbol).to_owned();
+            }
+            writeln!(out, "    {}", instr.repr())?;
+        }
+        bank += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_map::MemoryMap;
+
+    #[test]
+    fn export() {
+        // Illegal opcodes everywhere else, so they are written as data.
+        let mut data = vec![0xd3; 0x8000];
+        data[MemoryMap::HeaderCartridgeType as usize] = 0x01;
+        data[MemoryMap::HeaderRomSize as usize] = 0x00;
+        data[MemoryMap::HeaderRamSize as usize] = 0x00;
+        let code: &[(usize, &[u8])] = &[
+            // jp Main
+            (0x100, &[0xc3, 0x50, 0x01]),
+            // Main: select bank 1, call into it, then jump through the table
+            (
+                0x150,
+                &[
+                    0x3e, 0x01, 0xea, 0x00, 0x20, 0xcd, 0x00, 0x40, 0x21, 0x60, 0x01, 0xe9,
+                ],
+            ),
+            // Jump table
+            (0x160, &[0x70, 0x01, 0x72, 0x01]),
+            // jr @; .loop: dec a; jr nz, .loop; ret
+            (0x170, &[0x18, 0xfe, 0x3d, 0x20, 0xfd, 0xc9]),
+            // ret, then data
+            (0x4000, &[0xc9, 0x12, 0x34, 0x56]),
+        ];
+        for (offset, bytes) in code {
+            data[*offset..offset + bytes.len()].copy_from_slice(bytes);
+        }
+        let mem = Memory::from(data);
+        let symbols: Symbols = "00:0150 Main\n00:0172 .loop\nc0a0 wCounter\n"
+            .parse()
+            .unwrap();
+        let analysis = Analysis::new(&mem, []);
+        let mut out = Vec::new();
+        export_rgbds(&mem, &analysis, Some(&symbols), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        let after = |line: &str, len: usize| {
+            let start = lines.iter().position(|l| *l == line).unwrap();
+            &lines[start..start + len]
+        };
+
+        assert_eq!(lines[0], "DEF wCounter EQU $c0a0");
+        assert_eq!(
+            after("SECTION \"ROM Bank $00\", ROM0[$0000]", 4),
+            [
+                "SECTION \"ROM Bank $00\", ROM0[$0000]",
+                "",
+                "fn_0000:",
+                "    db $d3,$d3,$d3,$d3,$d3,$d3,$d3,$d3",
+            ]
+        );
+        assert_eq!(
+            after("fn_0100:", 3),
+            ["fn_0100:", "    jp Main", "    db $d3"]
+        );
+        assert_eq!(
+            after("Main:", 15),
+            [
+                "Main:",
+                "    ld a,$01",
+                "    ld [$2000],a",
+                // Bank 0 code doesn't know which bank it calls into.
+                "    call $4000",
+                "    ld hl,$0160",
+                "    jp hl",
+                "    db $d3,$d3,$d3,$d3",
+                "    dw $0170",
+                "    dw .loop",
+                "    db $d3,$d3,$d3,$d3,$d3,$d3,$d3,$d3",
+                "    db $d3,$d3,$d3,$d3",
+                "    jr $0170",
+                ".loop:",
+                "    dec a",
+                "    jr nz,.loop",
+            ]
+        );
+        assert_eq!(
+            after("SECTION \"ROM Bank $01\", ROMX[$4000], BANK[$01]", 6),
+            [
+                "SECTION \"ROM Bank $01\", ROMX[$4000], BANK[$01]",
+                "",
+                "fn_01_4000:",
+                "    ret",
+                "    db $12,$34,$56,$d3,$d3,$d3,$d3,$d3",
+                "    db $d3,$d3,$d3,$d3,$d3,$d3,$d3,$d3",
+            ]
+        );
+        // Every byte is written once.
+        let bytes: usize = lines
+            .iter()
+            .filter_map(|line| line.trim().strip_prefix("db "))
+            .map(|line| line.split(',').count())
+            .sum();
+        assert_eq!(bytes + 3 + 12 + 4 + 6 + 1, 0x8000);
+    }
+}
@@ -1,15 +1,82 @@
 use std::fmt::Display;
 
+use crate::dasm::Syntax;
+use crate::opcodes::Opcode;
+
 #[derive(Clone)]
 pub struct Instruction {
     pub addr: u16,
     repr: String,
     symbol: Option<String>,
+    /// The opcode, or `None` for bytes shown as data.
+    opcode: Option<&'static Opcode>,
+    length: u16,
 }
 
 impl Instruction {
-    pub fn new(addr: u16, repr: String, symbol: Option<String>) -> Self {
-        Self { addr, repr, symbol }
+    pub fn new(addr: u16, repr: String, symbol: Option<String>, opcode: &'static Opcode) -> Self {
+        Self {
+            addr,
+            repr,
+            symbol,
+            opcode: Some(opcode),
+            length: opcode.length.into(),
+        }
+    }
+
+    /// Bytes that aren't an instruction, shown as `db`.
+    pub fn data(addr: u16, bytes: &[u8], symbol: Option<String>, syntax: Syntax) -> Self {
+        let bytes: Vec<_> = bytes.iter().map(|b| syntax.hex8(*b)).collect();
+        Self {
+            addr,
+            repr: format!("db {}", bytes.join(",")),
+            symbol,
+            opcode: None,
+            length: bytes.len() as u16,
+        }
+    }
+
+    /// Jump table entry, shown as `dw`.
+    pub fn word(addr: u16, target: String, symbol: Option<String>) -> Self {
+        Self {
+            addr,
+            repr: format!("dw {}", target),
+            symbol,
+            opcode: None,
+            length: 2,
+        }
+    }
+
+    /// Label at the instruction.
+    pub fn symbol(&self) -> Option<&str> {
+        self.symbol.as_deref()
+    }
+
+    /// The instruction without its address or label, e.g. `ld a,0x01`.
+    pub fn repr(&self) -> &str {
+        &self.repr
+    }
+
+    /// Length in bytes.
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    /// Length, timing and flag effects, e.g. `2 bytes, 3/2 M-cycles, ----`.
+    pub fn details(&self) -> String {
+        let bytes = if self.length == 1 { "byte" } else { "bytes" };
+        let Some(opcode) = self.opcode else {
+            return format!("{} {} of data", self.length, bytes);
+        };
+        let cycles = if opcode.cycles == opcode.cycles_not_taken {
+            format!("{}", opcode.cycles)
+        } else {
+            format!("{}/{}", opcode.cycles, opcode.cycles_not_taken)
+        };
+        format!(
+            "{} {}, {} M-cycles, {}",
+            self.length, bytes, cycles, opcode.flags
+        )
     }
 }
 
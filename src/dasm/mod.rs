@@ -1,4 +1,7 @@
+mod analysis;
+mod assembler;
 mod decoder;
+mod export;
 mod instruction;
 
 use std::collections::HashMap;
@@ -6,22 +9,116 @@ use std::ops::Range;
 
 use bytes::BytesMut;
 
+use crate::address::Address;
 use crate::dasm::{decoder::Decoder, instruction::Instruction};
+
+use crate::mbc::Mbc;
+use crate::memory::{Memory, ProgramMemory};
 use crate::symbols::Symbols;
+pub use analysis::{function_name, Access, Analysis, Kind};
+pub use assembler::assemble;
+pub use export::export_rgbds;
 
-pub struct Disassembly {
+/// Longest SM83 instruction, in bytes.
+const MAX_LENGTH: u16 = 3;
+
+/// Where code starts in bank 0: the RST and interrupt vectors, the entry
+/// point, the cartridge header and the code after it.
+const ENTRY_POINTS: &[u16] = &[
+    0x00, 0x08, 0x10, 0x18, 0x20, 0x28, 0x30, 0x38, 0x40, 0x48, 0x50, 0x58, 0x60, 0x100, 0x104,
+    0x150,
+];
+
+/// Assembly syntax instructions are written in.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Syntax {
+    /// `ld a,(0xff00 + 0x44)`, as shown in the debugger.
+    #[default]
+    Default,
+    /// `ldh a,[$ff44]`, as RGBDS assembles it.
+    Rgbds,
+}
+
+impl Syntax {
+    fn hex8(self, value: u8) -> String {
+        match self {
+            Self::Default => format!("0x{:02x}", value),
+            Self::Rgbds => format!("${:02x}", value),
+        }
+    }
+
+    fn hex16(self, value: u16) -> String {
+        match self {
+            Self::Default => format!("0x{:04x}", value),
+            Self::Rgbds => format!("${:04x}", value),
+        }
+    }
+
+    /// Memory operand at `operand`.
+    fn indirect(self, operand: &str) -> String {
+        match self {
+            Self::Default => format!("({})", operand),
+            Self::Rgbds => format!("[{}]", operand),
+        }
+    }
+}
+
+fn origin(bank: u16) -> u16 {
+    if bank == 0 {
+        0x0000
+    } else {
+        0x4000
+    }
+}
+
+/// Addresses in a bank that start an instruction or data: the vectors, the
+/// entry point, labels and functions found by the analysis, so every one of
+/// them gets a line of its own.
+fn entry_points(bank: u16, symbols: Option<&Symbols>, analysis: Option<&Analysis>) -> Vec<u16> {
+    let origin = origin(bank);
+    let labels = symbols.into_iter().flat_map(|symbols| {
+        symbols.label_addresses().filter(|addr| {
+            addr.addr & 0xc000 == origin && addr.bank.is_none_or(|other| other == bank)
+        })
+    });
+    let functions = analysis
+        .into_iter()
+        .flat_map(|analysis| analysis.functions())
+        .filter(|addr| addr.bank == Some(bank));
+    let mut entry_points: Vec<u16> = labels
+        .chain(functions.copied())
+        .map(|addr| addr.addr)
+        .collect();
+    if bank == 0 {
+        entry_points.extend(ENTRY_POINTS);
+    }
+    entry_points
+}
+
+/// Disassembly of one ROM bank.
+struct Bank {
     instructions: Vec<Instruction>,
     address_to_instruction: HashMap<u16, usize>,
 }
 
-impl Disassembly {
-    pub fn from_bytes(bytes: BytesMut, symbols: Option<&Symbols>) -> Self {
-        let decoder = Decoder::from_bytes(bytes, symbols);
+impl Bank {
+    /// Decode a bank linearly, re-synchronising at the vectors, the entry
+    /// point, labels and functions so data before them doesn't throw off
+    /// the code.
+    fn decode(
+        rom: &[u8],
+        bank: u16,
+        symbols: Option<&Symbols>,
+        analysis: Option<&Analysis>,
+    ) -> Self {
+        let decoder = Decoder::from_bytes(BytesMut::from(rom), symbols)
+            .with_origin(origin(bank))
+            .with_rom_bank(Some(bank))
+            .with_entry_points(entry_points(bank, symbols, analysis))
+            .with_analysis(analysis);
         let mut instructions = Vec::new();
         let mut address_to_instruction = HashMap::new();
-        let decoder = decoder.enumerate();
-
-        for (idx, (addr, instr)) in decoder {
+        for (idx, (addr, instr)) in decoder.enumerate() {
             instructions.push(instr);
             address_to_instruction.insert(addr, idx);
         }
@@ -31,26 +128,88 @@ impl Disassembly {
             address_to_instruction,
         }
     }
+}
 
-    pub fn get_instructions_near(&self, pc: u16, range: Range<isize>) -> Option<Vec<Instruction>> {
-        let idx = if let Some(idx) = self.address_to_instruction.get(&pc) {
-            *idx
-        } else {
-            return None;
-        };
+/// Disassembly of each ROM bank on its own, addressed as `bank:addr`. Banks
+/// are decoded the first time they're looked at.
+#[derive(Default)]
+pub struct Disassembly {
+    banks: HashMap<u16, Bank>,
+    analysis: Option<Analysis>,
+}
 
-        let start = if (idx as isize) + range.start <= 0 {
-            0
-        } else {
-            idx.wrapping_add_signed(range.start)
-        };
+impl Disassembly {
+    /// Use code and data analysis to tell code from data.
+    pub fn with_analysis(mut self, analysis: Analysis) -> Self {
+        self.analysis = Some(analysis);
+        self
+    }
 
-        let end = if (idx as isize) + range.end > (self.instructions.len() - 1) as isize {
-            self.instructions.len() - 1
-        } else {
-            idx.wrapping_add_signed(range.end)
+    pub fn analysis(&self) -> Option<&Analysis> {
+        self.analysis.as_ref()
+    }
+
+    /// Decode `count` instructions starting at `addr` in the banks currently
+    /// mapped, even if `addr` isn't an instruction boundary of the whole
+    /// memory disassembly.
+    pub fn decode(
+        mem: &impl ProgramMemory,
+        addr: u16,
+        count: usize,
+        symbols: Option<&Symbols>,
+    ) -> Vec<Instruction> {
+        let len = (count as u16).saturating_mul(MAX_LENGTH);
+        let bytes: BytesMut = (0..len).map(|i| mem.get_u8(addr.wrapping_add(i))).collect();
+        Decoder::from_bytes(bytes, symbols)
+            .with_origin(addr)
+            .with_rom_bank(mem.bank(0x4000))
+            .take(count)
+            .map(|(_, instr)| instr)
+            .collect()
+    }
+
+    /// Instructions around `addr`, from the disassembly of its ROM bank, or
+    /// decoded from `addr` onwards in memory as mapped for RAM and the boot
+    /// ROM. ROM addresses without a bank are in the bank mapped in.
+    pub fn get_instructions_near(
+        &mut self,
+        mem: &Memory,
+        addr: Address,
+        range: Range<isize>,
+        symbols: Option<&Symbols>,
+    ) -> Vec<Instruction> {
+        let bank = match addr.addr {
+            0x0000..=0x00ff if mem.boot_rom_mapped() => None,
+            0x0000..=0x3fff => Some(0),
+            0x4000..=0x7fff => addr.bank.or_else(|| mem.bank(addr.addr)),
+            _ => None,
+        };
+        let Some(bank) = bank else {
+            let count = range.len();
+            return Self::decode(mem, addr.addr, count, symbols);
         };
+        // No such bank in this cartridge.
+        let Some(rom) = mem.mbc.rom_bank_data(bank) else {
+            return Vec::new();
+        };
+        let bank = self
+            .banks
+            .entry(bank)
+            .or_insert_with(|| Bank::decode(rom, bank, symbols, self.analysis.as_ref()));
 
-        Some(Vec::from(&self.instructions[start..end]))
+        // Inside an instruction, e.g. a jump into the middle of one, show
+        // the instruction the address is part of.
+        let idx = (0..MAX_LENGTH).find_map(|back| {
+            let addr = addr.addr.checked_sub(back)?;
+            bank.address_to_instruction.get(&addr).copied()
+        });
+        let Some(idx) = idx else {
+            return Vec::new();
+        };
+        let start = idx.saturating_add_signed(range.start);
+        let end = idx
+            .saturating_add_signed(range.end)
+            .min(bank.instructions.len());
+        bank.instructions[start..end].to_vec()
     }
 }
@@ -0,0 +1,86 @@
+use std::fmt::Display;
+
+use crate::address::Address;
+use crate::cpu::Cpu;
+use crate::debugger::expr::Expr;
+use crate::debugger::logpoint::Logpoint;
+use crate::memory::ProgramMemory;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breakpoint {
+    pub addr: Address,
+    /// Source text and parsed form of the condition.
+    condition: Option<(String, Expr)>,
+    /// Only break once the breakpoint has been hit this many times.
+    pub hit_count: Option<u32>,
+    /// Log this message instead of pausing.
+    pub log: Option<Logpoint>,
+    pub enabled: bool,
+    /// Times PC reached the breakpoint with its condition true.
+    pub hits: u32,
+}
+
+impl Breakpoint {
+    pub fn new(addr: Address) -> Self {
+        Self {
+            addr,
+            condition: None,
+            hit_count: None,
+            log: None,
+            enabled: true,
+            hits: 0,
+        }
+    }
+
+    pub fn with_condition(mut self, condition: &str) -> Result<Self, String> {
+        let expr = condition.parse()?;
+        self.condition = Some((condition.trim().to_owned(), expr));
+        Ok(self)
+    }
+
+    pub fn with_hit_count(mut self, hit_count: u32) -> Self {
+        self.hit_count = Some(hit_count);
+        self
+    }
+
+    pub fn with_log(mut self, message: &str) -> Result<Self, String> {
+        self.log = Some(message.parse()?);
+        Ok(self)
+    }
+
+    pub fn condition(&self) -> Option<&str> {
+        self.condition.as_ref().map(|(source, _)| source.as_str())
+    }
+
+    /// Record that PC reached the breakpoint, returning whether to break
+    /// (or log, for a logpoint).
+    pub fn hit(&mut self, cpu: &Cpu, mem: &impl ProgramMemory) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if let Some((_, ref expr)) = self.condition {
+            if !expr.is_true(cpu, mem) {
+                return false;
+            }
+        }
+        self.hits += 1;
+        self.hits >= self.hit_count.unwrap_or(1)
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.addr)?;
+        if let Some(condition) = self.condition() {
+            write!(f, " if {}", condition)?;
+        }
+        if let Some(ref log) = self.log {
+            write!(f, " log {}", log)?;
+        }
+        if let Some(hit_count) = self.hit_count {
+            write!(f, " ({}/{} hits)", self.hits, hit_count)
+        } else {
+            write!(f, " ({} hits)", self.hits)
+        }
+    }
+}
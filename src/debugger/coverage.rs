@@ -0,0 +1,253 @@
+//! What the code did to memory: whether each ROM byte was executed, read or
+//! written, and how many times each VRAM, WRAM and HRAM byte was read and
+//! written. Writes to ROM are writes to the cartridge's registers.
+//! Nothing is recorded until it's enabled, as it's done on every access.
+//!
+//! The report lists, for each bank, how much of the code the analysis found
+//! was executed, then the code that never was:
+//!
+//! ```text
+//! bank $00: 1520 of 2048 code bytes executed (74.2%)
+//! total: 1520 of 2048 code bytes executed (74.2%)
+//!
+//! not executed:
+//! 00:0213-021f in Main main.asm:42
+//! ```
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use bitflags::bitflags;
+
+use crate::address::Address;
+use crate::cpu::bus::{Access, AccessKind};
+use crate::dasm::{Analysis, Kind};
+use crate::debugger::function_name;
+use crate::mbc::Mbc;
+use crate::memory::Memory;
+use crate::source_map::SourceMap;
+use crate::symbols::Symbols;
+
+bitflags! {
+    /// How a ROM byte was used.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct RomUse: u8 {
+        const EXECUTED = 1;
+        const READ = 1 << 1;
+        const WRITTEN = 1 << 2;
+    }
+}
+
+/// Reads and writes of a RAM byte.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RamUse {
+    pub reads: u32,
+    pub writes: u32,
+}
+
+/// RAM address counted for `addr`, with echo RAM folded onto WRAM.
+fn ram_address(addr: u16) -> Option<u16> {
+    match addr {
+        0x8000..=0x9fff | 0xc000..=0xdfff | 0xff80..=0xfffe => Some(addr),
+        0xe000..=0xfdff => Some(addr - 0x2000),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+pub struct Coverage {
+    enabled: bool,
+    rom: HashMap<Address, RomUse>,
+    ram: HashMap<u16, RamUse>,
+    /// Most reads and most writes of any RAM byte, to scale heatmaps by.
+    most: RamUse,
+}
+
+impl Coverage {
+    /// Start or stop recording, keeping what was recorded so far.
+    pub fn enable(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Forget what was recorded, carrying on recording if enabled.
+    pub fn clear(&mut self) {
+        *self = Self {
+            enabled: self.enabled,
+            ..Self::default()
+        };
+    }
+
+    /// Mark the bytes of the instruction at `addr` as executed.
+    pub fn execute(&mut self, addr: Address, length: u8) {
+        if !self.enabled {
+            return;
+        }
+        for offset in 0..u16::from(length) {
+            let addr = Address {
+                bank: addr.bank,
+                addr: addr.addr.wrapping_add(offset),
+            };
+            *self.rom.entry(addr).or_default() |= RomUse::EXECUTED;
+        }
+    }
+
+    /// Count a read or write made by an instruction.
+    pub fn access(&mut self, access: &Access) {
+        if !self.enabled {
+            return;
+        }
+        let write = matches!(access.kind, AccessKind::Write { .. });
+        if access.addr < 0x8000 {
+            let addr = Address {
+                bank: access.bank,
+                addr: access.addr,
+            };
+            let used = if write { RomUse::WRITTEN } else { RomUse::READ };
+            *self.rom.entry(addr).or_default() |= used;
+        } else if let Some(addr) = ram_address(access.addr) {
+            let counts = self.ram.entry(addr).or_default();
+            if write {
+                counts.writes = counts.writes.saturating_add(1);
+                self.most.writes = self.most.writes.max(counts.writes);
+            } else {
+                counts.reads = counts.reads.saturating_add(1);
+                self.most.reads = self.most.reads.max(counts.reads);
+            }
+        }
+    }
+
+    pub fn rom(&self, addr: Address) -> RomUse {
+        self.rom.get(&addr).copied().unwrap_or_default()
+    }
+
+    /// Reads and writes of a VRAM, WRAM or HRAM byte.
+    pub fn ram(&self, addr: u16) -> RamUse {
+        ram_address(addr)
+            .and_then(|addr| self.ram.get(&addr))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Most reads and most writes of any one RAM byte.
+    pub fn most(&self) -> RamUse {
+        self.most
+    }
+
+    /// Write how much of the code in `analysis` was executed, and the code
+    /// that wasn't.
+    pub fn report(
+        &self,
+        mem: &Memory,
+        analysis: &Analysis,
+        symbols: Option<&Symbols>,
+        source_map: Option<&SourceMap>,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let percent = |executed: usize, code: usize| 100. * executed as f64 / code.max(1) as f64;
+        let mut total = (0, 0);
+        // Runs of code not executed, as the first and last byte.
+        let mut untested: Vec<(Address, Address)> = Vec::new();
+        let mut bank = 0;
+        while let Some(rom) = mem.mbc.rom_bank_data(bank) {
+            let origin = if bank == 0 { 0x0000 } else { 0x4000 };
+            let (mut code, mut executed) = (0, 0);
+            let mut last = None;
+            for offset in 0..rom.len() as u16 {
+                let addr = Address::banked(bank, origin + offset);
+                if !matches!(analysis.kind(addr), Some(Kind::Code | Kind::Operand)) {
+                    continue;
+                }
+                code += 1;
+                if self.rom(addr).contains(RomUse::EXECUTED) {
+                    executed += 1;
+                    continue;
+                }
+                match untested.last_mut() {
+                    Some((_, end)) if last == Some(addr.addr.wrapping_sub(1)) => *end = addr,
+                    _ => untested.push((addr, addr)),
+                }
+                last = Some(addr.addr);
+            }
+            writeln!(
+                out,
+                "bank ${:02x}: {} of {} code bytes executed ({:.1}%)",
+                bank,
+                executed,
+                code,
+                percent(executed, code)
+            )?;
+            total = (total.0 + executed, total.1 + code);
+            bank += 1;
+        }
+        writeln!(
+            out,
+            "total: {} of {} code bytes executed ({:.1}%)",
+            total.0,
+            total.1,
+            percent(total.0, total.1)
+        )?;
+
+        writeln!(out, "\nnot executed:")?;
+        for (start, end) in untested {
+            write!(out, "{}-{:04x}", start, end.addr)?;
+            if let Some(function) = analysis.function_at(start) {
+                write!(out, " in {}", function_name(Some(function), symbols))?;
+            }
+            let location = source_map.and_then(|source_map| source_map.location(start));
+            if let Some((file, line)) = location {
+                write!(out, " {}:{}", file.path, line)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_map::MemoryMap;
+
+    #[test]
+    fn report() {
+        // Illegal opcodes everywhere else, so the vectors aren't code.
+        let mut rom = vec![0xd3; 0x8000];
+        rom[MemoryMap::HeaderCartridgeType as usize] = 0x01;
+        rom[MemoryMap::HeaderRomSize as usize] = 0x00;
+        rom[MemoryMap::HeaderRamSize as usize] = 0x00;
+        // nop; nop; jp $0110
+        rom[0x100..0x105].copy_from_slice(&[0x00, 0x00, 0xc3, 0x10, 0x01]);
+        // jr z, $0114; nop; nop; ret
+        rom[0x110..0x115].copy_from_slice(&[0x28, 0x02, 0x00, 0x00, 0xc9]);
+        let mem = Memory::from(rom);
+        let analysis = Analysis::new(&mem, []);
+        let symbols: Symbols = "00:0100 Start\n".parse().unwrap();
+        let source_map: SourceMap = "00:0112 main.asm:7\n".parse().unwrap();
+
+        let mut coverage = Coverage::default();
+        coverage.execute(Address::banked(0, 0x100), 1);
+        assert_eq!(coverage.rom(Address::banked(0, 0x100)), RomUse::empty());
+        coverage.enable(true);
+        for (addr, length) in [(0x100, 1), (0x102, 3), (0x110, 2), (0x114, 1)] {
+            coverage.execute(Address::banked(0, addr), length);
+        }
+        let mut out = Vec::new();
+        coverage
+            .report(&mem, &analysis, Some(&symbols), Some(&source_map), &mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "bank $00: 7 of 10 code bytes executed (70.0%)\n\
+             bank $01: 0 of 0 code bytes executed (0.0%)\n\
+             total: 7 of 10 code bytes executed (70.0%)\n\
+             \n\
+             not executed:\n\
+             00:0101-0101 in Start\n\
+             00:0112-0113 in Start main.asm:7\n"
+        );
+    }
+}
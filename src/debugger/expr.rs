@@ -0,0 +1,491 @@
+//! Expressions for breakpoint conditions, e.g. `a == 0x3f && [hl] != 0`.
+//!
+//! Operands are CPU registers (`a`, `hl`, `sp`, `pc`, `ime`, ...), I/O
+//! registers by name (`ly`, `lcdc`, `if`, ...), numbers (`144`, `0x3f` or
+//! `$3f`) and memory reads (`[hl]`, `[c0a0]`). Numbers without a prefix are
+//! decimal, except inside `[...]` where they are addresses and hex, as
+//! everywhere else addresses are written. There a register name wins over
+//! hex, so `[de]` reads through DE and `[0xde]` reads 0x00de. Outside
+//! brackets, numbers with a leading zero, like `0150`, and bare words of hex
+//! digits, like `c0a0`, are rejected as ambiguous. Values are 16 bit and
+//! comparisons evaluate to 1 or 0.
+
+use std::fmt::Display;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+use crate::address::parse_hex;
+use crate::cpu::Cpu;
+use crate::memory::ProgramMemory;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Register {
+    A,
+    F,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    Af,
+    Bc,
+    De,
+    Hl,
+    Sp,
+    Pc,
+    Ime,
+}
+
+impl Register {
+    pub fn get(self, cpu: &Cpu) -> u16 {
+        match self {
+            Register::A => cpu.a.into(),
+            Register::F => cpu.f.bits().into(),
+            Register::B => cpu.b.into(),
+            Register::C => cpu.c.into(),
+            Register::D => cpu.d.into(),
+            Register::E => cpu.e.into(),
+            Register::H => cpu.h.into(),
+            Register::L => cpu.l.into(),
+            Register::Af => cpu.get_af(),
+            Register::Bc => cpu.get_bc(),
+            Register::De => cpu.get_de(),
+            Register::Hl => cpu.get_hl(),
+            Register::Sp => cpu.sp,
+            Register::Pc => cpu.pc,
+            Register::Ime => cpu.ie.into(),
+        }
+    }
+
+    pub fn set(self, cpu: &mut Cpu, value: u16) {
+        match self {
+            Register::A => cpu.a = value as u8,
+            // The low nibble of F always reads as zero.
+            Register::F => cpu.set_af(u16::from(cpu.a) << 8 | value & 0xf0),
+            Register::B => cpu.b = value as u8,
+            Register::C => cpu.c = value as u8,
+            Register::D => cpu.d = value as u8,
+            Register::E => cpu.e = value as u8,
+            Register::H => cpu.h = value as u8,
+            Register::L => cpu.l = value as u8,
+            Register::Af => cpu.set_af(value & 0xfff0),
+            Register::Bc => cpu.set_bc(value),
+            Register::De => cpu.set_de(value),
+            Register::Hl => cpu.set_hl(value),
+            Register::Sp => cpu.sp = value,
+            Register::Pc => cpu.pc = value,
+            Register::Ime => cpu.ie = value != 0,
+        }
+    }
+}
+
+impl FromStr for Register {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "a" => Register::A,
+            "f" => Register::F,
+            "b" => Register::B,
+            "c" => Register::C,
+            "d" => Register::D,
+            "e" => Register::E,
+            "h" => Register::H,
+            "l" => Register::L,
+            "af" => Register::Af,
+            "bc" => Register::Bc,
+            "de" => Register::De,
+            "hl" => Register::Hl,
+            "sp" => Register::Sp,
+            "pc" => Register::Pc,
+            "ime" => Register::Ime,
+            _ => return Err(()),
+        })
+    }
+}
+
+pub const IO_REGISTERS: &[(&str, u16)] = &[
+    ("p1", 0xff00),
+    ("joyp", 0xff00),
+    ("sb", 0xff01),
+    ("sc", 0xff02),
+    ("div", 0xff04),
+    ("tima", 0xff05),
+    ("tma", 0xff06),
+    ("tac", 0xff07),
+    ("if", 0xff0f),
+    ("lcdc", 0xff40),
+    ("stat", 0xff41),
+    ("scy", 0xff42),
+    ("scx", 0xff43),
+    ("ly", 0xff44),
+    ("lyc", 0xff45),
+    ("dma", 0xff46),
+    ("bgp", 0xff47),
+    ("obp0", 0xff48),
+    ("obp1", 0xff49),
+    ("wy", 0xff4a),
+    ("wx", 0xff4b),
+    ("ie", 0xffff),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    BitAnd,
+    BitOr,
+    BitXor,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(u16),
+    Register(Register),
+    /// Byte in memory at the address.
+    Memory(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn eval(&self, cpu: &Cpu, mem: &impl ProgramMemory) -> u16 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::Register(reg) => reg.get(cpu),
+            Expr::Memory(addr) => mem.get_u8(addr.eval(cpu, mem)).into(),
+            Expr::Not(expr) => (expr.eval(cpu, mem) == 0).into(),
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(cpu, mem);
+                // Short circuit so `[hl]` isn't read when it doesn't matter.
+                match op {
+                    BinaryOp::Or if lhs != 0 => return 1,
+                    BinaryOp::And if lhs == 0 => return 0,
+                    _ => (),
+                }
+                let rhs = rhs.eval(cpu, mem);
+                match op {
+                    BinaryOp::Or | BinaryOp::And => (rhs != 0).into(),
+                    BinaryOp::Eq => (lhs == rhs).into(),
+                    BinaryOp::Ne => (lhs != rhs).into(),
+                    BinaryOp::Lt => (lhs < rhs).into(),
+                    BinaryOp::Le => (lhs <= rhs).into(),
+                    BinaryOp::Gt => (lhs > rhs).into(),
+                    BinaryOp::Ge => (lhs >= rhs).into(),
+                    BinaryOp::Add => lhs.wrapping_add(rhs),
+                    BinaryOp::Sub => lhs.wrapping_sub(rhs),
+                    BinaryOp::BitAnd => lhs & rhs,
+                    BinaryOp::BitOr => lhs | rhs,
+                    BinaryOp::BitXor => lhs ^ rhs,
+                }
+            }
+        }
+    }
+
+    pub fn is_true(&self, cpu: &Cpu, mem: &impl ProgramMemory) -> bool {
+        self.eval(cpu, mem) != 0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(u16),
+    Ident(String),
+    Op(&'static str),
+}
+
+fn parse_number(s: &str) -> Result<u16, String> {
+    let result = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix('$')) {
+        u16::from_str_radix(hex, 16)
+    } else if s.len() > 1 && s.starts_with('0') {
+        return Err(format!(
+            "ambiguous number {:?}, write 0x{} for hex or drop the leading zeros",
+            s, s
+        ));
+    } else {
+        s.parse()
+    };
+    result.map_err(|e| format!("invalid number {:?}: {}", s, e))
+}
+
+// Longest operators first so `<=` isn't lexed as `<`.
+const OPERATORS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "+", "-", "&", "|", "^", "!", "(", ")", "[", "]",
+];
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars: Peekable<Chars> = s.chars().peekable();
+    // Brackets open around the current token.
+    let mut depth = 0;
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_alphanumeric() || c == '$' || c == '_' {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '$' || c == '_') {
+                    break;
+                }
+                word.push(c.to_ascii_lowercase());
+                chars.next();
+            }
+            let named = word.parse::<Register>().is_ok()
+                || IO_REGISTERS.iter().any(|(name, _)| *name == word);
+            if depth > 0 && !named {
+                tokens.push(Token::Number(parse_hex(&word)?));
+            } else if word.starts_with(|c: char| c.is_ascii_digit() || c == '$') {
+                tokens.push(Token::Number(parse_number(&word)?));
+            } else {
+                tokens.push(Token::Ident(word));
+            }
+        } else {
+            let rest: String = chars.clone().take(2).collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or_else(|| format!("unexpected {:?}", c))?;
+            for _ in 0..op.len() {
+                chars.next();
+            }
+            match *op {
+                "[" => depth += 1,
+                "]" => depth -= 1,
+                _ => (),
+            }
+            tokens.push(Token::Op(op));
+        }
+    }
+    Ok(tokens)
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Op(op) => write!(f, "{}", op),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        if self.peek_op() == Some(op) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected {:?}", op))
+        }
+    }
+
+    /// Parse a left associative chain of `ops` with operands parsed by
+    /// `next`.
+    fn binary(
+        &mut self,
+        ops: &[(&str, BinaryOp)],
+        next: fn(&mut Self) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        let mut lhs = next(self)?;
+        while let Some(&(_, op)) = ops.iter().find(|(s, _)| Some(*s) == self.peek_op()) {
+            self.pos += 1;
+            let rhs = next(self)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        self.binary(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        self.binary(&[("&&", BinaryOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        self.binary(
+            &[
+                ("==", BinaryOp::Eq),
+                ("!=", BinaryOp::Ne),
+                ("<=", BinaryOp::Le),
+                (">=", BinaryOp::Ge),
+                ("<", BinaryOp::Lt),
+                (">", BinaryOp::Gt),
+            ],
+            Self::sum,
+        )
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        self.binary(
+            &[
+                ("+", BinaryOp::Add),
+                ("-", BinaryOp::Sub),
+                ("&", BinaryOp::BitAnd),
+                ("|", BinaryOp::BitOr),
+                ("^", BinaryOp::BitXor),
+            ],
+            Self::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek_op() {
+            Some("!") => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some("(") => {
+                self.pos += 1;
+                let expr = self.or()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some("[") => {
+                self.pos += 1;
+                let expr = self.or()?;
+                self.expect("]")?;
+                Ok(Expr::Memory(Box::new(expr)))
+            }
+            _ => self.atom(),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Ident(name)) => {
+                if let Ok(reg) = name.parse() {
+                    Ok(Expr::Register(reg))
+                } else if let Some((_, addr)) = IO_REGISTERS.iter().find(|(io, _)| *io == name) {
+                    Ok(Expr::Memory(Box::new(Expr::Number(*addr))))
+                } else if u16::from_str_radix(&name, 16).is_ok() {
+                    Err(format!(
+                        "unknown register {:?}, write hex as 0x{}",
+                        name, name
+                    ))
+                } else {
+                    Err(format!("unknown register {:?}", name))
+                }
+            }
+            Some(Token::Op(op)) => Err(format!("unexpected \"{}\"", op)),
+            None => Err("unexpected end of expression".to_owned()),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(format!("unexpected \"{}\"", parser.tokens[parser.pos]));
+        }
+        Ok(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(s: &str) -> Result<Expr, String> {
+        s.parse()
+    }
+
+    #[test]
+    fn decimal() {
+        assert_eq!(number("0"), Ok(Expr::Number(0)));
+        assert_eq!(number("144"), Ok(Expr::Number(144)));
+        assert!(number("65536").is_err());
+        assert!(number("1f").is_err());
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(number("0x0150"), Ok(Expr::Number(0x0150)));
+        assert_eq!(number("$ff"), Ok(Expr::Number(0xff)));
+        assert_eq!(number("0XC0A0"), Ok(Expr::Number(0xc0a0)));
+        assert!(number("0x").is_err());
+        assert!(number("$10000").is_err());
+    }
+
+    #[test]
+    fn ambiguous() {
+        // Hex as an address elsewhere, decimal as a number.
+        assert!(number("0150").is_err());
+        assert!(number("00").is_err());
+        // Hex digits that aren't a register.
+        assert!(number("c0a0").is_err());
+        assert!(number("ff").is_err());
+    }
+
+    #[test]
+    fn hex_addresses_in_brackets() {
+        let memory = |addr| Ok(Expr::Memory(Box::new(Expr::Number(addr))));
+        assert_eq!(number("[c0a3]"), memory(0xc0a3));
+        assert_eq!(number("[0150]"), memory(0x0150));
+        assert_eq!(number("[0xff44]"), memory(0xff44));
+        assert_eq!(number("[$ff44]"), memory(0xff44));
+        assert_eq!(
+            number("[[c0a0]]"),
+            Ok(Expr::Memory(Box::new(memory(0xc0a0).unwrap())))
+        );
+        assert!(number("[c0ag]").is_err());
+
+        // Registers still win, and the brackets only cover what's inside.
+        let de = Box::new(Expr::Register(Register::De));
+        assert_eq!(number("[de]"), Ok(Expr::Memory(de)));
+        let hl = Box::new(Expr::Register(Register::Hl));
+        let offset = Expr::Binary(BinaryOp::Add, hl, Box::new(Expr::Number(0x10)));
+        let expected = Expr::Binary(
+            BinaryOp::Eq,
+            Box::new(Expr::Memory(Box::new(offset))),
+            Box::new(Expr::Number(10)),
+        );
+        assert_eq!(number("[hl + 10] == 10"), Ok(expected));
+    }
+
+    #[test]
+    fn registers_over_hex() {
+        assert_eq!(number("de"), Ok(Expr::Register(Register::De)));
+        assert_eq!(number("a"), Ok(Expr::Register(Register::A)));
+        let ly = Expr::Memory(Box::new(Expr::Number(0xff44)));
+        assert_eq!(number("ly"), Ok(ly));
+    }
+
+    #[test]
+    fn comparison() {
+        let pc = Box::new(Expr::Register(Register::Pc));
+        let expected = Expr::Binary(BinaryOp::Eq, pc, Box::new(Expr::Number(0x0150)));
+        assert_eq!(number("pc == 0x0150"), Ok(expected));
+        assert!(number("pc == 0150").is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::debugger::expr::IO_REGISTERS;
+use crate::registers::Interrupt;
+
+const INTERRUPTS: &[(&str, Interrupt)] = &[
+    ("vblank", Interrupt::VBlank),
+    ("stat", Interrupt::LcdStat),
+    ("timer", Interrupt::Timer),
+    ("serial", Interrupt::Serial),
+    ("joypad", Interrupt::Joypad),
+];
+
+/// Name of the interrupt handled at `vector`, e.g. `vblank` at 0x0040.
+pub fn interrupt_name(vector: u16) -> Option<&'static str> {
+    let offset = vector.checked_sub(0x40).filter(|offset| offset % 8 == 0)?;
+    let (name, _) = INTERRUPTS.get(usize::from(offset / 8))?;
+    Some(name)
+}
+
+/// Hardware event to pause on.
+///
+/// Written as `int <vblank|stat|timer|serial|joypad>`, `halt` (HALT or
+/// STOP), `lcd <on|off>`, `bank` (ROM bank change), `dma` (OAM DMA start),
+/// `io <register>` (write to an I/O register, by name or address) or
+/// `line <ly> [<dot>]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HardwareEvent {
+    Interrupt(Interrupt),
+    Halt,
+    Lcd(bool),
+    RomBank,
+    OamDma,
+    IoWrite(u16),
+    Scanline { ly: u8, dot: u32 },
+}
+
+fn parse_number<T: FromStr>(s: Option<&str>, what: &str) -> Result<T, String> {
+    let s = s.ok_or_else(|| format!("expected {}", what))?;
+    s.parse().map_err(|_| format!("invalid {} {:?}", what, s))
+}
+
+impl FromStr for HardwareEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        let mut words = lower.split_whitespace();
+
+        let event = match words.next() {
+            Some("int") => {
+                let name = words.next().unwrap_or_default();
+                let (_, interrupt) = INTERRUPTS
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .ok_or("expected vblank, stat, timer, serial or joypad")?;
+                HardwareEvent::Interrupt(*interrupt)
+            }
+            Some("halt") => HardwareEvent::Halt,
+            Some("lcd") => match words.next() {
+                Some("on") => HardwareEvent::Lcd(true),
+                Some("off") => HardwareEvent::Lcd(false),
+                _ => return Err("expected on or off".to_owned()),
+            },
+            Some("bank") => HardwareEvent::RomBank,
+            Some("dma") => HardwareEvent::OamDma,
+            Some("io") => {
+                let name = words.next().ok_or("expected an I/O register")?;
+                let addr = match IO_REGISTERS.iter().find(|(n, _)| *n == name) {
+                    Some((_, addr)) => *addr,
+                    None => u16::from_str_radix(name.trim_start_matches("0x"), 16)
+                        .ok()
+                        .filter(|addr| matches!(addr, 0xff00..=0xff7f | 0xffff))
+                        .ok_or_else(|| format!("invalid I/O register {:?}", name))?,
+                };
+                HardwareEvent::IoWrite(addr)
+            }
+            Some("line") => {
+                let ly = parse_number(words.next(), "line")?;
+                let dot = match words.next() {
+                    Some(dot) => parse_number(Some(dot), "dot")?,
+                    None => 0,
+                };
+                HardwareEvent::Scanline { ly, dot }
+            }
+            _ => return Err("expected int, halt, lcd, bank, dma, io or line".to_owned()),
+        };
+
+        if let Some(word) = words.next() {
+            return Err(format!("unexpected {:?}", word));
+        }
+        Ok(event)
+    }
+}
+
+impl Display for HardwareEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HardwareEvent::Interrupt(interrupt) => {
+                let name = INTERRUPTS
+                    .iter()
+                    .find(|(_, i)| i == interrupt)
+                    .map_or("?", |(name, _)| name);
+                write!(f, "int {}", name)
+            }
+            HardwareEvent::Halt => write!(f, "halt"),
+            HardwareEvent::Lcd(on) => write!(f, "lcd {}", if *on { "on" } else { "off" }),
+            HardwareEvent::RomBank => write!(f, "bank"),
+            HardwareEvent::OamDma => write!(f, "dma"),
+            HardwareEvent::IoWrite(addr) => match IO_REGISTERS.iter().find(|(_, a)| a == addr) {
+                Some((name, _)) => write!(f, "io {}", name),
+                None => write!(f, "io 0x{:04x}", addr),
+            },
+            HardwareEvent::Scanline { ly, dot } => write!(f, "line {} {}", ly, dot),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::cpu::Cpu;
+use crate::debugger::expr::Expr;
+use crate::memory::ProgramMemory;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Expr(Expr),
+}
+
+/// Message logged instead of pausing when a breakpoint is hit.
+///
+/// Expressions in braces are replaced with their value in hex, e.g.
+/// `spawn enemy type={[c0a3]} at x={b}`. Use `{{` and `}}` for literal
+/// braces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Logpoint {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl Logpoint {
+    pub fn format(&self, cpu: &Cpu, mem: &impl ProgramMemory) -> String {
+        let mut message = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => message.push_str(text),
+                Part::Expr(expr) => match expr.eval(cpu, mem) {
+                    value @ 0..=0xff => message.push_str(&format!("0x{:02x}", value)),
+                    value => message.push_str(&format!("0x{:04x}", value)),
+                },
+            }
+        }
+        message
+    }
+}
+
+impl FromStr for Logpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut expr = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => expr.push(c),
+                            None => return Err("unmatched \"{\"".to_owned()),
+                        }
+                    }
+                    let expr = expr.parse().map_err(|e| format!("{{{}}}: {}", expr, e))?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Expr(expr));
+                }
+                '}' => return Err("unmatched \"}\"".to_owned()),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Self {
+            source: s.to_owned(),
+            parts,
+        })
+    }
+}
+
+impl Display for Logpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.source)
+    }
+}
@@ -1,20 +1,67 @@
-use std::collections::{HashSet, VecDeque};
+mod breakpoint;
+mod coverage;
+mod expr;
+mod hardware_event;
+mod logpoint;
+mod profiler;
+mod watchpoint;
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::fmt::Display;
 
-use crate::cpu::Cpu;
+use crate::address::Address;
+use crate::cpu::bus::{Access, AccessKind};
+use crate::cpu::{Cpu, State as CpuState};
 use crate::memory::ProgramMemory;
-use crate::registers::CpuFlags;
+use crate::memory_map::MemoryMap;
+use crate::opcodes::{self, Flow};
+use crate::ppu::Ppu;
+use crate::registers::Interrupt;
 use crate::symbols::Symbols;
 
+pub use breakpoint::Breakpoint;
+pub use coverage::{Coverage, RomUse};
+pub use expr::{Expr, Register, IO_REGISTERS};
+pub use hardware_event::{interrupt_name, HardwareEvent};
+pub use profiler::{function_name, FunctionProfile, Profile, Profiler};
+pub use watchpoint::{WatchCondition, WatchKind, Watchpoint};
+
+/// Writing a page number here starts an OAM DMA transfer.
+const OAM_DMA: u16 = 0xff46;
+
 #[derive(PartialEq)]
 pub enum Command {
     Continue,
     Pause,
+    /// Run one instruction, entering calls.
     Step,
+    /// Run one instruction, running a CALL or RST until it returns.
+    StepOver,
+    /// Run until the next call.
     StepIn,
+    /// Run until the current function returns.
     StepOut,
-    InsertBreakpoint(u16),
-    RemoveBreakpoint(u16),
+    RunTo(Address),
+    /// Run until the PPU enters VBlank.
+    RunToFrame,
+    /// Run until the CPU jumps to an interrupt vector.
+    RunToInterrupt,
+    InsertBreakpoint(Breakpoint),
+    RemoveBreakpoint(Address),
+    EnableBreakpoint(Address, bool),
+    InsertWatchpoint(Watchpoint),
+    RemoveWatchpoint(Watchpoint),
+    InsertHardwareEvent(HardwareEvent),
+    RemoveHardwareEvent(HardwareEvent),
+    /// Start a new profiling session, or stop profiling.
+    Profile(bool),
+    /// Start or stop recording coverage.
+    Coverage(bool),
+    /// Forget the coverage recorded so far.
+    ClearCoverage,
 }
 
 #[derive(Default, PartialEq)]
@@ -23,20 +70,77 @@ enum State {
     Continue,
     StepOut(usize),
     StepIn(usize),
+    /// Run until PC reaches the address with the callstack at most this high.
+    StepOver(usize, u16),
+    RunTo(Address),
+    /// Set once LY reaches the first VBlank line.
+    RunToFrame(bool),
+    /// Set once an interrupt dispatch has started.
+    RunToInterrupt(bool),
     Step,
     Pause,
 }
 
+pub enum Event {
+    IllegalInstruction {
+        addr: u16,
+        opcode: u8,
+    },
+    Watchpoint {
+        watchpoint: Watchpoint,
+        access: Access,
+    },
+    Hardware {
+        event: HardwareEvent,
+        pc: u16,
+    },
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Event::IllegalInstruction { addr, opcode } => write!(
+                f,
+                "Illegal instruction 0x{:02x} at 0x{:04x}, CPU locked up",
+                opcode, addr
+            ),
+            Event::Watchpoint { watchpoint, access } => {
+                write!(f, "Watchpoint {}: 0x{:04x} ", watchpoint, access.pc)?;
+                match access.kind {
+                    AccessKind::Read => {
+                        write!(f, "read 0x{:02x} from 0x{:04x}", access.value, access.addr)
+                    }
+                    AccessKind::Write { old } => write!(
+                        f,
+                        "wrote 0x{:02x} to 0x{:04x} (was 0x{:02x})",
+                        access.value, access.addr, old
+                    ),
+                }
+            }
+            Event::Hardware { event, pc } => write!(f, "Event {} at 0x{:04x}", event, pc),
+        }
+    }
+}
+
 pub struct Call {
-    addr: u16,
+    addr: Address,
     caller: u16,
     symbol: Option<String>,
 }
 
 impl Call {
-    pub fn new(addr: u16, caller: u16, symbols: Option<&Symbols>) -> Self {
+    /// Handler of an interrupt dispatched before the instruction at
+    /// `caller`, named after the interrupt without a symbol.
+    pub fn interrupt(vector: Address, caller: u16, symbols: Option<&Symbols>) -> Self {
+        Self {
+            symbol: Some(function_name(Some(vector), symbols)),
+            ..Self::new(vector, caller, symbols)
+        }
+    }
+
+    pub fn new(addr: Address, caller: u16, symbols: Option<&Symbols>) -> Self {
         let symbol = if let Some(symbols) = symbols {
-            symbols.get_symbol(addr)
+            symbols.get_symbol_at(addr).map(str::to_owned)
         } else {
             None
         };
@@ -47,6 +151,26 @@ impl Call {
             symbol,
         }
     }
+
+    /// Address of the called function.
+    pub fn addr(&self) -> u16 {
+        self.addr.addr
+    }
+
+    /// The called function, in the bank it was called in.
+    pub fn address(&self) -> Address {
+        self.addr
+    }
+
+    /// Address of the call instruction, or the one an interrupt came
+    /// before.
+    pub fn caller(&self) -> u16 {
+        self.caller
+    }
+
+    pub fn symbol(&self) -> Option<&str> {
+        self.symbol.as_deref()
+    }
 }
 
 impl Display for Call {
@@ -55,10 +179,10 @@ impl Display for Call {
             write!(
                 f,
                 "0x{:04x} ({}) from 0x{:04x}",
-                self.addr, symbol, self.caller
+                self.addr.addr, symbol, self.caller
             )
         } else {
-            write!(f, "0x{:04x} from 0x{:04x}", self.addr, self.caller)
+            write!(f, "0x{:04x} from 0x{:04x}", self.addr.addr, self.caller)
         }
     }
 }
@@ -66,10 +190,34 @@ impl Display for Call {
 #[derive(Default)]
 pub struct Debugger {
     state: State,
-    breakpoints: HashSet<u16>,
+    breakpoints: BTreeMap<Address, Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    hardware_events: Vec<HardwareEvent>,
+    /// Watchpoint or hardware event hit mid-instruction, reported once the
+    /// instruction ends.
+    pending_hit: Option<Event>,
+    /// ROM bank and PPU position (LY, dot) seen after the last M-cycle.
+    rom_bank: Option<u16>,
+    position: (u8, u32),
+    halted: bool,
     commands: VecDeque<Command>,
+    events: VecDeque<Event>,
+    /// Messages from logpoints.
+    logs: VecDeque<String>,
     callstack: Vec<Call>,
+    /// ROM addresses of instructions seen executing, which code analysis
+    /// starts from too.
+    executed: HashSet<Address>,
+    /// Call instructions seen executing, with the address they called.
+    calls: HashSet<(Address, Address)>,
+    /// What the code executed, read and wrote.
+    coverage: Coverage,
+    profiler: Profiler,
     last_pc: Option<u16>,
+    /// Length of the instruction at `last_pc`, decoded once on reaching it.
+    instr_length: u8,
+    last_ly: u8,
+    locked: bool,
     symbols: Option<Symbols>,
 }
 
@@ -83,11 +231,26 @@ impl Debugger {
 
     pub fn reset(&mut self) {
         self.commands = Default::default();
+        self.events = Default::default();
+        self.logs = Default::default();
         self.callstack = Default::default();
         self.last_pc = Default::default();
+        self.locked = false;
+        self.pending_hit = None;
+        self.rom_bank = None;
+        self.halted = false;
     }
 
-    fn call(&mut self, cpu: &Cpu, addr: u16) {
+    /// Functions on the callstack, outermost first.
+    fn stack(&self) -> Vec<Address> {
+        self.callstack.iter().map(Call::address).collect()
+    }
+
+    fn call(&mut self, cpu: &Cpu, caller: Address, addr: Address) {
+        self.calls.insert((caller, addr));
+        if self.profiler.is_enabled() {
+            self.profiler.call(&self.stack(), addr);
+        }
         self.callstack
             .push(Call::new(addr, cpu.pc, self.symbols.as_ref()));
         if let State::StepIn(height) = self.state {
@@ -98,10 +261,28 @@ impl Debugger {
     }
 
     pub fn is_running(&self) -> bool {
-        self.state == State::Continue
+        self.state != State::Pause
+    }
+
+    /// Whether the debugger is paused with no commands left to run.
+    pub fn is_paused(&self) -> bool {
+        self.state == State::Pause && self.commands.is_empty()
+    }
+
+    /// Push a frame for the handler of an interrupt being dispatched, which
+    /// its `reti` pops.
+    fn interrupt(&mut self, cpu: &Cpu, vector: Address) {
+        if self.profiler.is_enabled() {
+            self.profiler.call(&self.stack(), vector);
+        }
+        self.callstack
+            .push(Call::interrupt(vector, cpu.pc, self.symbols.as_ref()));
     }
 
     fn ret(&mut self) {
+        if self.profiler.is_enabled() {
+            self.profiler.flush(&self.stack());
+        }
         self.callstack.pop();
         if let State::StepOut(height) = self.state {
             if height == self.callstack.len() {
@@ -114,16 +295,133 @@ impl Debugger {
         self.commands.push_back(command);
     }
 
-    pub fn get_breakpoints(&self) -> Vec<u16> {
-        let mut v: Vec<_> = self.breakpoints.iter().cloned().collect();
-        v.sort();
-        v
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    pub fn poll_log(&mut self) -> Option<String> {
+        self.logs.pop_front()
+    }
+
+    pub fn get_breakpoints(&self) -> impl Iterator<Item = &Breakpoint> {
+        self.breakpoints.values()
+    }
+
+    pub fn get_watchpoints(&self) -> &Vec<Watchpoint> {
+        &self.watchpoints
+    }
+
+    pub fn get_hardware_events(&self) -> &Vec<HardwareEvent> {
+        &self.hardware_events
+    }
+
+    pub fn get_executed(&self) -> &HashSet<Address> {
+        &self.executed
+    }
+
+    pub fn get_calls(&self) -> &HashSet<(Address, Address)> {
+        &self.calls
+    }
+
+    pub fn coverage(&self) -> &Coverage {
+        &self.coverage
+    }
+
+    pub fn profiler(&self) -> &Profiler {
+        &self.profiler
     }
 
     pub fn get_callstack(&self) -> &Vec<Call> {
         &self.callstack
     }
 
+    /// Check the memory access the CPU made this M-cycle against the
+    /// watchpoints.
+    pub fn check_access(&mut self, cpu: &Cpu, mem: &impl ProgramMemory) {
+        let Some(access) = cpu.last_access else {
+            return;
+        };
+
+        // Fetching the instruction and its operands doesn't count as a read.
+        if access.kind == AccessKind::Read
+            && access.addr.wrapping_sub(access.pc) < self.instr_length.into()
+        {
+            return;
+        }
+        if self.coverage.is_enabled() {
+            let boot_rom = mem.get_u8(MemoryMap::BootRomDisable) == 0 && access.addr < 0x100;
+            if !boot_rom {
+                self.coverage.access(&access);
+            }
+        }
+
+        if self.pending_hit.is_some() {
+            return;
+        }
+
+        if let Some(watchpoint) = self.watchpoints.iter().find(|wp| wp.matches(&access)) {
+            self.pending_hit = Some(Event::Watchpoint {
+                watchpoint: watchpoint.clone(),
+                access,
+            });
+        }
+    }
+
+    /// Check what happened in the hardware this M-cycle against the
+    /// hardware events.
+    pub fn check_events(&mut self, cpu: &Cpu, mem: &impl ProgramMemory, ppu: &Ppu) {
+        let rom_bank = mem.bank(0x4000);
+        let bank_changed = self.rom_bank.is_some_and(|bank| Some(bank) != rom_bank);
+        self.rom_bank = rom_bank;
+        let position = (mem.get_u8(MemoryMap::LY), ppu.line_dot());
+        let last_position = std::mem::replace(&mut self.position, position);
+        let halted = matches!(cpu.state, CpuState::Halted | CpuState::Stopped);
+        let entered_halt = halted && !std::mem::replace(&mut self.halted, halted);
+
+        // The first M-cycle of an interrupt dispatch leaves m_cycle at 1.
+        let dispatched = cpu
+            .dispatching()
+            .filter(|_| cpu.m_cycle == 1)
+            .map(u16::from);
+        if let Some(vector) = dispatched {
+            self.interrupt(cpu, Address::current(vector, mem));
+        }
+
+        if self.hardware_events.is_empty() || self.pending_hit.is_some() {
+            return;
+        }
+
+        let interrupt =
+            dispatched.map(|vector| Interrupt::from_bits_retain(1 << ((vector - 0x40) / 8)));
+        let write = cpu.last_access.and_then(|access| match access.kind {
+            AccessKind::Write { old } => Some((access.addr, old, access.value)),
+            AccessKind::Read => None,
+        });
+
+        let hit = self.hardware_events.iter().find(|event| match **event {
+            HardwareEvent::Interrupt(i) => interrupt == Some(i),
+            HardwareEvent::Halt => entered_halt,
+            HardwareEvent::Lcd(on) => write.is_some_and(|(addr, old, value)| {
+                addr == u16::from(MemoryMap::LCDC)
+                    && (old ^ value) & 0x80 != 0
+                    && (value & 0x80 != 0) == on
+            }),
+            HardwareEvent::RomBank => bank_changed,
+            HardwareEvent::OamDma => write.is_some_and(|(addr, _, _)| addr == OAM_DMA),
+            HardwareEvent::IoWrite(reg) => write.is_some_and(|(addr, _, _)| addr == reg),
+            HardwareEvent::Scanline { ly, dot } => {
+                let reached = |(line, line_dot): (u8, u32)| line == ly && line_dot >= dot;
+                reached(position) && !reached(last_position)
+            }
+        });
+        if let Some(event) = hit {
+            self.pending_hit = Some(Event::Hardware {
+                event: *event,
+                pc: cpu.pc,
+            });
+        }
+    }
+
     pub fn tick(&mut self, cpu: &Cpu, mem: &impl ProgramMemory) -> bool {
         while let Some(command) = self.commands.pop_front() {
             match command {
@@ -136,6 +434,29 @@ impl Debugger {
                 Command::Pause => {
                     self.state = State::Pause;
                 }
+                Command::StepOver => {
+                    let opcode =
+                        opcodes::decode(mem.get_u8(cpu.pc), mem.get_u8(cpu.pc.wrapping_add(1)));
+                    self.state = if opcode.flow == Flow::Call {
+                        let next = cpu.pc.wrapping_add(opcode.length.into());
+                        // A call taken was pushed on reaching it, so the
+                        // caller is one frame down.
+                        let taken = opcode.condition().is_none_or(|cond| cond.holds(&cpu.f));
+                        let height = self.callstack.len().saturating_sub(taken.into());
+                        State::StepOver(height, next)
+                    } else {
+                        State::Step
+                    };
+                }
+                Command::RunTo(addr) => {
+                    self.state = State::RunTo(addr);
+                }
+                Command::RunToFrame => {
+                    self.state = State::RunToFrame(false);
+                }
+                Command::RunToInterrupt => {
+                    self.state = State::RunToInterrupt(false);
+                }
                 Command::StepIn => {
                     self.state = State::StepIn(self.callstack.len() + 1);
                 }
@@ -144,15 +465,78 @@ impl Debugger {
                         self.state = State::StepOut(self.callstack.len() - 1);
                     }
                 }
-                Command::InsertBreakpoint(addr) => {
-                    self.breakpoints.insert(addr);
+                Command::InsertBreakpoint(breakpoint) => {
+                    self.breakpoints.insert(breakpoint.addr, breakpoint);
                 }
                 Command::RemoveBreakpoint(addr) => {
                     self.breakpoints.remove(&addr);
                 }
+                Command::EnableBreakpoint(addr, enabled) => {
+                    if let Some(breakpoint) = self.breakpoints.get_mut(&addr) {
+                        breakpoint.enabled = enabled;
+                    }
+                }
+                Command::InsertWatchpoint(watchpoint) => {
+                    if !self.watchpoints.contains(&watchpoint) {
+                        self.watchpoints.push(watchpoint);
+                    }
+                }
+                Command::RemoveWatchpoint(watchpoint) => {
+                    self.watchpoints.retain(|wp| *wp != watchpoint);
+                }
+                Command::InsertHardwareEvent(event) => {
+                    if !self.hardware_events.contains(&event) {
+                        self.hardware_events.push(event);
+                    }
+                }
+                Command::RemoveHardwareEvent(event) => {
+                    self.hardware_events.retain(|e| *e != event);
+                }
+                Command::Profile(enabled) => {
+                    self.profiler.flush(&self.stack());
+                    self.profiler.enable(enabled);
+                }
+                Command::Coverage(enabled) => self.coverage.enable(enabled),
+                Command::ClearCoverage => self.coverage.clear(),
             }
         }
 
+        if cpu.m_cycle == 0 {
+            if let Some(event) = self.pending_hit.take() {
+                self.events.push_back(event);
+                self.state = State::Pause;
+            }
+        }
+
+        // Pause at the end of the instruction running when the frame or
+        // interrupt starts.
+        let ly = mem.get_u8(MemoryMap::LY);
+        match self.state {
+            State::RunToFrame(true) | State::RunToInterrupt(true) if cpu.m_cycle == 0 => {
+                self.state = State::Pause;
+            }
+            State::RunToFrame(ref mut vblank) => {
+                *vblank |= ly == 144 && self.last_ly != 144;
+            }
+            State::RunToInterrupt(ref mut dispatched) => {
+                *dispatched |= cpu.dispatching().is_some();
+            }
+            _ => (),
+        }
+        if ly == 144 && self.last_ly != 144 && self.profiler.is_enabled() {
+            self.profiler.vblank(&self.stack());
+        }
+        self.last_ly = ly;
+
+        if cpu.state == CpuState::Locked && !self.locked {
+            self.locked = true;
+            self.events.push_back(Event::IllegalInstruction {
+                addr: cpu.pc,
+                opcode: mem.get_u8(cpu.pc),
+            });
+            self.state = State::Pause;
+        }
+
         let is_new_pc = if let Some(last_pc) = self.last_pc {
             if last_pc != cpu.pc {
                 self.last_pc = Some(cpu.pc);
@@ -166,10 +550,48 @@ impl Debugger {
         };
 
         if is_new_pc {
+            let opcode = opcodes::decode(mem.get_u8(cpu.pc), mem.get_u8(cpu.pc.wrapping_add(1)));
+            self.instr_length = opcode.length;
+            let boot_rom = mem.get_u8(MemoryMap::BootRomDisable) == 0 && cpu.pc < 0x100;
+            if cpu.pc < 0x8000 && !boot_rom {
+                let addr = Address::current(cpu.pc, mem);
+                self.executed.insert(addr);
+                self.coverage.execute(addr, opcode.length);
+            }
+
             match self.state {
-                State::Continue | State::StepIn(_) | State::StepOut(_) => {
-                    if self.breakpoints.contains(&cpu.pc) {
-                        self.state = State::Pause;
+                State::Continue
+                | State::StepIn(_)
+                | State::StepOut(_)
+                | State::StepOver(..)
+                | State::RunTo(_)
+                | State::RunToFrame(_)
+                | State::RunToInterrupt(_) => {
+                    // A breakpoint without a bank fires in any bank.
+                    let any_bank = Address::new(cpu.pc);
+                    let this_bank = Address::current(cpu.pc, mem);
+                    let banked = Some(this_bank).filter(|addr| *addr != any_bank);
+                    for addr in [Some(any_bank), banked].into_iter().flatten() {
+                        if let Some(breakpoint) = self.breakpoints.get_mut(&addr) {
+                            if breakpoint.hit(cpu, mem) {
+                                match breakpoint.log {
+                                    Some(ref log) => self.logs.push_back(log.format(cpu, mem)),
+                                    None => self.state = State::Pause,
+                                }
+                            }
+                        }
+                    }
+
+                    match self.state {
+                        State::StepOver(height, addr)
+                            if cpu.pc == addr && self.callstack.len() <= height =>
+                        {
+                            self.state = State::Pause;
+                        }
+                        State::RunTo(addr) if addr == any_bank || addr == this_bank => {
+                            self.state = State::Pause;
+                        }
+                        _ => (),
                     }
                 }
                 State::Step => {
@@ -181,62 +603,28 @@ impl Debugger {
             }
 
             // Process callstack
-            match mem.get_u8(cpu.pc) {
-                0xcd => self.call(cpu, mem.get_u16(cpu.pc + 1)),
-                0xc4 => {
-                    if !cpu.f.contains(CpuFlags::Z) {
-                        self.call(cpu, mem.get_u16(cpu.pc + 1));
-                    }
-                }
-                0xcc => {
-                    if cpu.f.contains(CpuFlags::Z) {
-                        self.call(cpu, mem.get_u16(cpu.pc + 1));
+            let opcode = opcodes::decode(mem.get_u8(cpu.pc), 0);
+            let taken = opcode.condition().is_none_or(|cond| cond.holds(&cpu.f));
+            if taken {
+                match opcode.flow {
+                    Flow::Call => {
+                        let imm = mem.get_u16(cpu.pc.wrapping_add(1));
+                        if let Some(addr) = opcode.target(cpu.pc, imm) {
+                            let caller = Address::current(cpu.pc, mem);
+                            self.call(cpu, caller, Address::current(addr, mem));
+                        }
                     }
+                    Flow::Return => self.ret(),
+                    _ => (),
                 }
-                0xd4 => {
-                    if !cpu.f.contains(CpuFlags::C) {
-                        self.call(cpu, mem.get_u16(cpu.pc + 1));
-                    }
-                }
-                0xdc => {
-                    if cpu.f.contains(CpuFlags::C) {
-                        self.call(cpu, mem.get_u16(cpu.pc + 1));
-                    }
-                }
-                0xc7 => self.call(cpu, 0x00),
-                0xcf => self.call(cpu, 0x08),
-                0xd7 => self.call(cpu, 0x10),
-                0xdf => self.call(cpu, 0x18),
-                0xe7 => self.call(cpu, 0x20),
-                0xef => self.call(cpu, 0x28),
-                0xf7 => self.call(cpu, 0x30),
-                0xff => self.call(cpu, 0x38),
-                0xc9 | 0xd9 => self.ret(),
-                0xc0 => {
-                    if !cpu.f.contains(CpuFlags::Z) {
-                        self.ret();
-                    }
-                }
-                0xc8 => {
-                    if cpu.f.contains(CpuFlags::Z) {
-                        self.ret();
-                    }
-                }
-                0xd0 => {
-                    if !cpu.f.contains(CpuFlags::C) {
-                        self.ret();
-                    }
-                }
-                0xd8 => {
-                    if cpu.f.contains(CpuFlags::C) {
-                        self.ret();
-                    }
-                }
-                _ => (),
             }
         }
 
         // Return if we are running or not
-        self.state != State::Pause
+        let running = self.state != State::Pause;
+        if running {
+            self.profiler.cycle();
+        }
+        running
     }
 }
@@ -0,0 +1,184 @@
+//! M-cycles spent in each function, following the callstack. Cycles are
+//! counted until the stack changes and then added to every function on it:
+//! inclusive for each function on the stack, exclusive for the innermost.
+//! Code outside any call is the top level. Interrupt handlers are frames
+//! of their own, on top of the code they interrupted.
+//!
+//! The last frame, from one VBlank to the next, is kept as well as the whole
+//! session, which can be exported as collapsed stacks for flame graph tools:
+//!
+//! ```text
+//! top;Main;UpdateSprites 1520
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::address::Address;
+use crate::dasm;
+use crate::debugger::interrupt_name;
+use crate::symbols::Symbols;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FunctionProfile {
+    /// M-cycles in the function and the functions it called.
+    pub inclusive: u64,
+    /// M-cycles in the function itself.
+    pub exclusive: u64,
+    pub calls: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    /// Each function by its address, `None` for the top level.
+    functions: HashMap<Option<Address>, FunctionProfile>,
+    /// M-cycles spent with each callstack, outermost call first.
+    stacks: HashMap<Vec<Address>, u64>,
+    cycles: u64,
+    frames: u64,
+}
+
+impl Profile {
+    fn add(&mut self, stack: &[Address], cycles: u64) {
+        self.cycles += cycles;
+        *self.stacks.entry(stack.to_vec()).or_default() += cycles;
+        let top = stack.last().copied();
+        self.functions.entry(top).or_default().exclusive += cycles;
+        // Recursive functions are only counted once.
+        let mut seen: Vec<Option<Address>> = Vec::with_capacity(stack.len() + 1);
+        for function in [None].into_iter().chain(stack.iter().copied().map(Some)) {
+            if !seen.contains(&function) {
+                self.functions.entry(function).or_default().inclusive += cycles;
+                seen.push(function);
+            }
+        }
+    }
+
+    /// Functions with their cycles, the top level as `None`.
+    pub fn functions(&self) -> impl Iterator<Item = (Option<Address>, &FunctionProfile)> {
+        self.functions
+            .iter()
+            .map(|(function, profile)| (*function, profile))
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// VBlanks seen.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Collapsed stacks, one line per callstack with the M-cycles spent in
+    /// it, as taken by `flamegraph.pl` and speedscope.
+    pub fn collapsed(&self, symbols: Option<&Symbols>) -> String {
+        let mut stacks: Vec<_> = self
+            .stacks
+            .iter()
+            .map(|(stack, cycles)| {
+                let names = stack.iter().map(|addr| function_name(Some(*addr), symbols));
+                let names: Vec<_> = [function_name(None, symbols)]
+                    .into_iter()
+                    .chain(names)
+                    .collect();
+                (names.join(";"), *cycles)
+            })
+            .collect();
+        stacks.sort();
+        let mut out = String::new();
+        for (stack, cycles) in stacks {
+            let _ = writeln!(out, "{} {}", stack, cycles);
+        }
+        out
+    }
+}
+
+/// Name of a function from the symbols, after the interrupt for a handler
+/// such as `int_vblank`, or as in the disassembly.
+pub fn function_name(function: Option<Address>, symbols: Option<&Symbols>) -> String {
+    let Some(addr) = function else {
+        return "top".to_owned();
+    };
+    if let Some(symbol) = symbols.and_then(|symbols| symbols.get_symbol_at(addr)) {
+        return symbol.to_owned();
+    }
+    match interrupt_name(addr.addr) {
+        Some(name) if matches!(addr.bank, None | Some(0)) => format!("int_{}", name),
+        _ => dasm::function_name(addr),
+    }
+}
+
+#[derive(Default)]
+pub struct Profiler {
+    enabled: bool,
+    /// M-cycles since the callstack last changed.
+    pending: u64,
+    frame: Profile,
+    last_frame: Profile,
+    session: Profile,
+}
+
+impl Profiler {
+    /// Start a new session, or stop profiling.
+    pub fn enable(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            *self = Self::default();
+        }
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn cycle(&mut self) {
+        if self.enabled {
+            self.pending += 1;
+        }
+    }
+
+    /// Add the cycles so far to `stack`, before it changes.
+    pub fn flush(&mut self, stack: &[Address]) {
+        if self.pending > 0 {
+            self.frame.add(stack, self.pending);
+            self.session.add(stack, self.pending);
+            self.pending = 0;
+        }
+    }
+
+    pub fn call(&mut self, stack: &[Address], function: Address) {
+        if self.enabled {
+            self.flush(stack);
+            self.frame
+                .functions
+                .entry(Some(function))
+                .or_default()
+                .calls += 1;
+            self.session
+                .functions
+                .entry(Some(function))
+                .or_default()
+                .calls += 1;
+        }
+    }
+
+    /// Start the next frame at VBlank.
+    pub fn vblank(&mut self, stack: &[Address]) {
+        if self.enabled {
+            self.flush(stack);
+            self.frame.frames = 1;
+            self.session.frames += 1;
+            self.last_frame = std::mem::take(&mut self.frame);
+        }
+    }
+
+    /// The last whole frame.
+    pub fn last_frame(&self) -> &Profile {
+        &self.last_frame
+    }
+
+    pub fn session(&self) -> &Profile {
+        &self.session
+    }
+}
@@ -0,0 +1,229 @@
+// Debugger tests on small programs, run from 0x0100 without the boot ROM.
+
+use crate::address::Address;
+use crate::cpu::bus::AccessKind;
+use crate::cpu::State as CpuState;
+use crate::debugger::{Command, Debugger, Event, HardwareEvent, RomUse, Watchpoint};
+use crate::gameboy::Gameboy;
+use crate::memory::{Memory, ProgramMemory};
+use crate::memory_map::MemoryMap;
+
+/// Most M-cycles to run waiting for the debugger to pause.
+const MAX_M_CYCLES: usize = 100_000;
+
+/// Gameboy with `code` placed at each address, at 0x0100 with the boot ROM
+/// unmapped and the debugger paused.
+fn gameboy(code: &[(u16, &[u8])]) -> Gameboy {
+    gameboy_with_banks(2, code)
+}
+
+/// Same as [`gameboy`], on an MBC1 cartridge with `banks` ROM banks.
+fn gameboy_with_banks(banks: usize, code: &[(u16, &[u8])]) -> Gameboy {
+    let mut rom = vec![0; banks * 0x4000];
+    rom[MemoryMap::HeaderCartridgeType as usize] = 0x01;
+    rom[MemoryMap::HeaderRomSize as usize] = banks.trailing_zeros() as u8 - 1;
+    for (addr, bytes) in code {
+        let start = usize::from(*addr);
+        rom[start..start + bytes.len()].copy_from_slice(bytes);
+    }
+    let mut gameboy = Gameboy::new(Memory::from(rom));
+    ProgramMemory::set_u8(&mut gameboy.mem, MemoryMap::BootRomDisable, 1);
+    gameboy.cpu.pc = 0x0100;
+    let mut debugger = Debugger::new(None);
+    debugger.command(Command::Pause);
+    gameboy.attach_debugger(Some(debugger));
+    gameboy.tick();
+    gameboy
+}
+
+fn debugger(gameboy: &mut Gameboy) -> &mut Debugger {
+    gameboy.debugger.as_mut().unwrap()
+}
+
+/// Run `command` until the debugger pauses again.
+fn run(gameboy: &mut Gameboy, command: Command) {
+    debugger(gameboy).command(command);
+    gameboy.tick();
+    for _ in 0..MAX_M_CYCLES {
+        if debugger(gameboy).is_paused() {
+            return;
+        }
+        gameboy.tick();
+    }
+    panic!("didn't pause, pc 0x{:04x}", gameboy.cpu.pc);
+}
+
+fn stack(gameboy: &mut Gameboy) -> Vec<u16> {
+    let callstack = debugger(gameboy).get_callstack();
+    callstack.iter().map(|call| call.addr()).collect()
+}
+
+#[test]
+fn step_over_recursive_call() {
+    let mut gameboy = gameboy(&[
+        // ld a, 3; call recurse; jr @
+        (0x0100, &[0x3e, 0x03, 0xcd, 0x00, 0x02, 0x18, 0xfe]),
+        // recurse: dec a; jr z, .done; call recurse; nop; .done: ret
+        (0x0200, &[0x3d, 0x28, 0x04, 0xcd, 0x00, 0x02, 0x00, 0xc9]),
+    ]);
+    run(&mut gameboy, Command::RunTo(Address::new(0x0203)));
+    assert_eq!(stack(&mut gameboy), [0x0200, 0x0200]);
+
+    // The call made from the called function returns to 0x0206 first, one
+    // frame too deep.
+    run(&mut gameboy, Command::StepOver);
+    assert_eq!(gameboy.cpu.pc, 0x0206);
+    assert_eq!(stack(&mut gameboy), [0x0200]);
+}
+
+#[test]
+fn interrupt_handler_frame() {
+    let mut gameboy = gameboy(&[
+        // vblank: nop; reti
+        (0x0040, &[0x00, 0xd9]),
+        // ld a, 1; ldh [IE], a; ei; call request; jr @
+        (
+            0x0100,
+            &[0x3e, 0x01, 0xe0, 0xff, 0xfb, 0xcd, 0x00, 0x02, 0x18, 0xfe],
+        ),
+        // request: ld a, 1; ldh [IF], a; nop; nop; ret
+        (0x0200, &[0x3e, 0x01, 0xe0, 0x0f, 0x00, 0x00, 0xc9]),
+    ]);
+    debugger(&mut gameboy).command(Command::Profile(true));
+    run(&mut gameboy, Command::RunTo(Address::new(0x0040)));
+    assert_eq!(stack(&mut gameboy), [0x0200, 0x0040]);
+    let handler = &debugger(&mut gameboy).get_callstack()[1];
+    assert_eq!(handler.symbol(), Some("int_vblank"));
+    assert_eq!(handler.caller(), 0x0204);
+
+    // The handler's reti pops its own frame, not the one interrupted.
+    run(&mut gameboy, Command::RunTo(Address::new(0x0205)));
+    assert_eq!(stack(&mut gameboy), [0x0200]);
+
+    run(&mut gameboy, Command::RunTo(Address::new(0x0108)));
+    assert!(stack(&mut gameboy).is_empty());
+    let collapsed = debugger(&mut gameboy).profiler().session().collapsed(None);
+    let stacks: Vec<_> = collapsed
+        .lines()
+        .filter_map(|line| line.rsplit_once(' '))
+        .map(|(stack, _)| stack)
+        .collect();
+    assert_eq!(stacks, ["top", "top;fn_0200", "top;fn_0200;int_vblank"]);
+}
+
+#[test]
+fn watchpoint_range_bank_and_change() {
+    let mut gameboy = gameboy(&[(
+        0x0100,
+        &[
+            0x3e, 0x05, // ld a, 5
+            0xea, 0xa1, 0xc0, // ld [$c0a1], a
+            0xea, 0xa1, 0xc0, // ld [$c0a1], a: same value
+            0xea, 0xb0, 0xc0, // ld [$c0b0], a: out of range
+            0x3c, // inc a
+            0xea, 0xa1, 0xc0, // ld [$c0a1], a
+            0x18, 0xfe, // jr @
+        ],
+    )]);
+    run(&mut gameboy, Command::RunTo(Address::new(0x0105)));
+    for watchpoint in ["w 01:c0a1", "w c0a0-c0a1 changed"] {
+        let watchpoint: Watchpoint = watchpoint.parse().unwrap();
+        debugger(&mut gameboy).command(Command::InsertWatchpoint(watchpoint));
+    }
+
+    run(&mut gameboy, Command::Continue);
+    let Some(Event::Watchpoint { watchpoint, access }) = debugger(&mut gameboy).poll_event() else {
+        panic!("expected a watchpoint hit");
+    };
+    assert_eq!(watchpoint.to_string(), "w 0xc0a0-0xc0a1 changed");
+    assert_eq!(access.pc, 0x010c);
+    assert_eq!(access.addr, 0xc0a1);
+    assert_eq!(access.value, 6);
+    assert_eq!(access.kind, AccessKind::Write { old: 5 });
+}
+
+#[test]
+fn halt_event() {
+    // nop; halt; jr @
+    let mut gameboy = gameboy(&[(0x0100, &[0x00, 0x76, 0x18, 0xfe])]);
+    debugger(&mut gameboy).command(Command::InsertHardwareEvent(HardwareEvent::Halt));
+
+    run(&mut gameboy, Command::Continue);
+    assert!(matches!(
+        debugger(&mut gameboy).poll_event(),
+        Some(Event::Hardware {
+            event: HardwareEvent::Halt,
+            ..
+        })
+    ));
+    assert_eq!(gameboy.cpu.state, CpuState::Halted);
+}
+
+#[test]
+fn rom_bank_event() {
+    let mut gameboy = gameboy_with_banks(
+        4,
+        &[(
+            0x0100,
+            &[
+                0x3e, 0x01, // ld a, 1
+                0xea, 0x00, 0x20, // ld [$2000], a: already bank 1
+                0x3e, 0x02, // ld a, 2
+                0xea, 0x00, 0x20, // ld [$2000], a
+                0x18, 0xfe, // jr @
+            ],
+        )],
+    );
+    debugger(&mut gameboy).command(Command::InsertHardwareEvent(HardwareEvent::RomBank));
+
+    run(&mut gameboy, Command::Continue);
+    assert!(matches!(
+        debugger(&mut gameboy).poll_event(),
+        Some(Event::Hardware {
+            event: HardwareEvent::RomBank,
+            ..
+        })
+    ));
+    assert_eq!(gameboy.cpu.a, 2);
+    assert_eq!(gameboy.mem.bank(0x4000), Some(2));
+}
+
+#[test]
+fn coverage_only_while_enabled() {
+    let mut gameboy = gameboy(&[(
+        0x0100,
+        &[
+            0xfa, 0x00, 0xc0, // ld a, [$c000]
+            0xea, 0x01, 0xc0, // ld [$c001], a
+            0x18, 0xf8, // jr $0100
+        ],
+    )]);
+    let rom = |gameboy: &mut Gameboy, addr| {
+        let addr = Address::banked(0, addr);
+        debugger(gameboy).coverage().rom(addr)
+    };
+    run(&mut gameboy, Command::RunTo(Address::new(0x0106)));
+    assert_eq!(rom(&mut gameboy, 0x0100), RomUse::empty());
+    assert_eq!(debugger(&mut gameboy).coverage().ram(0xc000).reads, 0);
+
+    debugger(&mut gameboy).command(Command::Coverage(true));
+    run(&mut gameboy, Command::RunTo(Address::new(0x0106)));
+    // Fetching the operands isn't a read.
+    assert_eq!(rom(&mut gameboy, 0x0101), RomUse::EXECUTED);
+    assert_eq!(rom(&mut gameboy, 0x0104), RomUse::EXECUTED);
+    assert_eq!(debugger(&mut gameboy).coverage().ram(0xc000).reads, 1);
+    assert_eq!(debugger(&mut gameboy).coverage().ram(0xc001).writes, 1);
+
+    debugger(&mut gameboy).command(Command::Coverage(false));
+    run(&mut gameboy, Command::RunTo(Address::new(0x0106)));
+    assert_eq!(debugger(&mut gameboy).coverage().ram(0xc000).reads, 1);
+
+    // Clearing keeps recording if it was enabled.
+    debugger(&mut gameboy).command(Command::Coverage(true));
+    debugger(&mut gameboy).command(Command::ClearCoverage);
+    run(&mut gameboy, Command::RunTo(Address::new(0x0103)));
+    assert!(debugger(&mut gameboy).coverage().is_enabled());
+    assert_eq!(rom(&mut gameboy, 0x0101), RomUse::EXECUTED);
+    assert_eq!(debugger(&mut gameboy).coverage().ram(0xc000).reads, 1);
+    assert_eq!(debugger(&mut gameboy).coverage().ram(0xc001).writes, 0);
+}
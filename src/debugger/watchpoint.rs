@@ -0,0 +1,136 @@
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::address::{parse_hex, Address};
+use crate::cpu::bus::{Access, AccessKind};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Access,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WatchCondition {
+    Any,
+    /// Only writes of this value.
+    Equals(u8),
+    /// Only writes that change the stored value.
+    Changed,
+}
+
+/// Pauses when the CPU accesses an address in `range`, and in `bank` if
+/// one is given.
+///
+/// Written as `<r|w|rw> [<bank>:]<addr>[-<end>] [== <value>|changed]`, e.g.
+/// `w c0a0 changed` or `rw 01:a000-a0ff`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Watchpoint {
+    pub kind: WatchKind,
+    pub bank: Option<u16>,
+    pub range: RangeInclusive<u16>,
+    pub condition: WatchCondition,
+}
+
+impl Watchpoint {
+    pub fn matches(&self, access: &Access) -> bool {
+        if !self.range.contains(&access.addr)
+            || self.bank.is_some_and(|bank| access.bank != Some(bank))
+        {
+            return false;
+        }
+        match (self.kind, access.kind) {
+            (WatchKind::Read | WatchKind::Access, AccessKind::Read) => {
+                self.condition == WatchCondition::Any
+            }
+            (WatchKind::Write | WatchKind::Access, AccessKind::Write { old }) => {
+                match self.condition {
+                    WatchCondition::Any => true,
+                    WatchCondition::Equals(value) => access.value == value,
+                    WatchCondition::Changed => access.value != old,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Watchpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+
+        let kind = match words.next() {
+            Some("r") => WatchKind::Read,
+            Some("w") => WatchKind::Write,
+            Some("rw") => WatchKind::Access,
+            _ => return Err("expected r, w or rw".to_owned()),
+        };
+
+        let (start, end) = match words.next() {
+            Some(range) => match range.split_once('-') {
+                Some((start, end)) => (start.parse::<Address>()?, parse_hex(end)?),
+                None => {
+                    let start = range.parse::<Address>()?;
+                    (start, start.addr)
+                }
+            },
+            None => return Err("expected an address".to_owned()),
+        };
+        let range = start.addr..=end;
+        if range.is_empty() {
+            return Err("empty address range".to_owned());
+        }
+
+        let condition = match words.next() {
+            None => WatchCondition::Any,
+            Some("changed") => WatchCondition::Changed,
+            Some("==") => match words.next() {
+                Some(value) => WatchCondition::Equals(parse_hex(value)?),
+                None => return Err("expected a value after ==".to_owned()),
+            },
+            Some(word) => return Err(format!("unexpected {:?}", word)),
+        };
+        if kind == WatchKind::Read && condition != WatchCondition::Any {
+            return Err("value conditions only apply to writes".to_owned());
+        }
+
+        if let Some(word) = words.next() {
+            return Err(format!("unexpected {:?}", word));
+        }
+
+        Ok(Self {
+            kind,
+            bank: start.bank,
+            range,
+            condition,
+        })
+    }
+}
+
+impl Display for Watchpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            WatchKind::Read => "r",
+            WatchKind::Write => "w",
+            WatchKind::Access => "rw",
+        };
+        let start = Address {
+            bank: self.bank,
+            addr: *self.range.start(),
+        };
+        write!(f, "{} {}", kind, start)?;
+        if self.range.start() != self.range.end() {
+            let prefix = if self.bank.is_some() { "" } else { "0x" };
+            write!(f, "-{}{:04x}", prefix, self.range.end())?;
+        }
+        match self.condition {
+            WatchCondition::Any => Ok(()),
+            WatchCondition::Equals(value) => write!(f, " == 0x{:02x}", value),
+            WatchCondition::Changed => write!(f, " changed"),
+        }
+    }
+}
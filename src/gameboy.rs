@@ -45,6 +45,10 @@ impl Gameboy {
         self.ppu.tick(&mut self.mem);
         let out = self.serial.tick(&mut self.mem);
         self.cpu.tick(&mut self.mem);
+        if let Some(ref mut debugger) = self.debugger {
+            debugger.check_access(&self.cpu, &self.mem);
+            debugger.check_events(&self.cpu, &self.mem, &self.ppu);
+        }
         out
     }
 
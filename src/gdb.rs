@@ -0,0 +1,454 @@
+//! GDB remote serial protocol server.
+//!
+//! The SM83 has no GDB architecture, so the registers are described by a
+//! target description as six 16 bit registers: `af`, `bc`, `de`, `hl`, `sp`
+//! and `pc`. Addresses above 0xffff carry a bank in their upper 16 bits for
+//! breakpoints and memory reads, e.g. `0x34123` is `03:4123`.
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use crate::address::{parse_hex, Address};
+use crate::debugger::{Breakpoint, Command, Debugger, WatchCondition, WatchKind, Watchpoint};
+use crate::gameboy::Gameboy;
+use crate::memory::ProgramMemory;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.gnu.gdb.sm83.core">
+    <reg name="af" bitsize="16" type="int"/>
+    <reg name="bc" bitsize="16" type="int"/>
+    <reg name="de" bitsize="16" type="int"/>
+    <reg name="hl" bitsize="16" type="data_ptr"/>
+    <reg name="sp" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+  </feature>
+</target>
+"#;
+
+/// Longest memory read answered, to fit the packet size.
+const MAX_READ: u32 = 0x400;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+enum Packet {
+    Data(String),
+    /// Ctrl-C from the client.
+    Interrupt,
+}
+
+struct Client {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    /// Bytes the socket hasn't taken yet.
+    outgoing: Vec<u8>,
+    /// Signal to report once the debugger pauses after `c`, `s` or Ctrl-C.
+    waiting: Option<u8>,
+}
+
+impl Client {
+    fn send_raw(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.outgoing.extend_from_slice(data);
+        self.flush()
+    }
+
+    /// Send as much as the non-blocking socket takes, leaving the rest for
+    /// the next poll rather than stalling the frame.
+    fn flush(&mut self) -> std::io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.outgoing.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, packet: &str) -> std::io::Result<()> {
+        let checksum = packet.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        self.send_raw(format!("${}#{:02x}", packet, checksum).as_bytes())
+    }
+
+    /// Read whatever the client has sent, returning `None` once it has
+    /// disconnected.
+    fn receive(&mut self) -> std::io::Result<Option<Vec<Packet>>> {
+        let mut chunk = [0; 4096];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Ok(None),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut packets = Vec::new();
+        while let Some(&first) = self.buffer.first() {
+            match first {
+                b'$' => {
+                    let Some(hash) = self.buffer.iter().position(|b| *b == b'#') else {
+                        break;
+                    };
+                    if self.buffer.len() < hash + 3 {
+                        break;
+                    }
+                    let data = String::from_utf8_lossy(&self.buffer[1..hash]).into_owned();
+                    let checksum = std::str::from_utf8(&self.buffer[hash + 1..hash + 3])
+                        .ok()
+                        .and_then(|s| u8::from_str_radix(s, 16).ok());
+                    self.buffer.drain(..hash + 3);
+                    let sum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+                    if checksum == Some(sum) {
+                        self.send_raw(b"+")?;
+                        packets.push(Packet::Data(data));
+                    } else {
+                        self.send_raw(b"-")?;
+                    }
+                }
+                0x03 => {
+                    self.buffer.remove(0);
+                    packets.push(Packet::Interrupt);
+                }
+                // Acks, and anything else outside a packet.
+                _ => {
+                    self.buffer.remove(0);
+                }
+            }
+        }
+        Ok(Some(packets))
+    }
+}
+
+/// GDB server listening on localhost, polled once per frame.
+pub struct GdbServer {
+    listener: TcpListener,
+    client: Option<Client>,
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect()
+}
+
+fn address(addr: u32) -> Address {
+    match addr >> 16 {
+        0 => Address::new(addr as u16),
+        bank => Address::banked(bank as u16, addr as u16),
+    }
+}
+
+fn get_register(gameboy: &Gameboy, n: usize) -> Option<u16> {
+    let cpu = &gameboy.cpu;
+    Some(match n {
+        0 => cpu.get_af(),
+        1 => cpu.get_bc(),
+        2 => cpu.get_de(),
+        3 => cpu.get_hl(),
+        4 => cpu.sp,
+        5 => cpu.pc,
+        _ => return None,
+    })
+}
+
+fn set_register(gameboy: &mut Gameboy, n: usize, value: u16) -> bool {
+    let cpu = &mut gameboy.cpu;
+    match n {
+        // The low nibble of F always reads as zero.
+        0 => cpu.set_af(value & 0xfff0),
+        1 => cpu.set_bc(value),
+        2 => cpu.set_de(value),
+        3 => cpu.set_hl(value),
+        4 => cpu.sp = value,
+        5 => cpu.pc = value,
+        _ => return false,
+    }
+    true
+}
+
+/// Registers are sent little endian.
+fn encode_register(value: u16) -> String {
+    format!("{:02x}{:02x}", value as u8, value >> 8)
+}
+
+fn decode_register(s: &str) -> Option<u16> {
+    let bytes = decode_hex(s)?;
+    match bytes[..] {
+        [low, high] => Some(u16::from_le_bytes([low, high])),
+        _ => None,
+    }
+}
+
+impl GdbServer {
+    pub fn bind(port: u16) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        log::info!("GDB server listening on {}", listener.local_addr()?);
+        Ok(Self {
+            listener,
+            client: None,
+        })
+    }
+
+    /// Accept a client, answer its packets and report when the debugger
+    /// stops.
+    pub fn poll(&mut self, gameboy: &mut Gameboy) {
+        if let Err(e) = self.try_poll(gameboy) {
+            log::error!("GDB client disconnected: {}", e);
+            self.client = None;
+        }
+    }
+
+    fn try_poll(&mut self, gameboy: &mut Gameboy) -> std::io::Result<()> {
+        if self.client.is_none() {
+            match self.listener.accept() {
+                Ok((stream, addr)) => {
+                    log::info!("GDB client connected from {}", addr);
+                    stream.set_nonblocking(true)?;
+                    stream.set_nodelay(true)?;
+                    self.client = Some(Client {
+                        stream,
+                        buffer: Vec::new(),
+                        outgoing: Vec::new(),
+                        waiting: None,
+                    });
+                    // GDB expects the target to be stopped when it attaches.
+                    if let Some(debugger) = gameboy.debugger.as_mut() {
+                        debugger.command(Command::Pause);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+        let Some(client) = self.client.as_mut() else {
+            return Ok(());
+        };
+        client.flush()?;
+        let Some(debugger) = gameboy.debugger.as_ref() else {
+            return Ok(());
+        };
+
+        if let Some(signal) = client.waiting {
+            if debugger.is_paused() {
+                client.waiting = None;
+                client.send(&format!("S{:02x}", signal))?;
+            }
+        }
+
+        let Some(packets) = client.receive()? else {
+            log::info!("GDB client disconnected");
+            self.client = None;
+            return Ok(());
+        };
+        for packet in packets {
+            let Some(client) = self.client.as_mut() else {
+                break;
+            };
+            match packet {
+                Packet::Interrupt => {
+                    client.waiting = Some(SIGINT);
+                    if let Some(debugger) = gameboy.debugger.as_mut() {
+                        debugger.command(Command::Pause);
+                    }
+                }
+                Packet::Data(data) => {
+                    if let Some(reply) = Self::handle(client, gameboy, &data) {
+                        client.send(&reply)?;
+                    }
+                    if data == "k" {
+                        self.client = None;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle a packet, returning the reply unless it is sent later.
+    fn handle(client: &mut Client, gameboy: &mut Gameboy, data: &str) -> Option<String> {
+        let (kind, args) = data.split_at(data.chars().next().map_or(0, char::len_utf8));
+        let reply = match (kind, args) {
+            ("?", _) => format!("S{:02x}", SIGTRAP),
+            ("g", _) => (0..6)
+                .filter_map(|n| get_register(gameboy, n))
+                .map(encode_register)
+                .collect(),
+            ("G", values) => {
+                let values: Option<Vec<u16>> = (0..values.len())
+                    .step_by(4)
+                    .map(|i| values.get(i..i + 4).and_then(decode_register))
+                    .collect();
+                match values {
+                    Some(values) if values.len() == 6 => {
+                        for (n, value) in values.into_iter().enumerate() {
+                            set_register(gameboy, n, value);
+                        }
+                        "OK".to_owned()
+                    }
+                    _ => "E01".to_owned(),
+                }
+            }
+            ("p", n) => match parse_hex(n).ok().and_then(|n| get_register(gameboy, n)) {
+                Some(value) => encode_register(value),
+                None => "E01".to_owned(),
+            },
+            ("P", assignment) => {
+                let set = assignment.split_once('=').and_then(|(n, value)| {
+                    let n = parse_hex::<usize>(n).ok()?;
+                    let value = decode_register(value)?;
+                    Some(set_register(gameboy, n, value))
+                });
+                if set == Some(true) {
+                    "OK".to_owned()
+                } else {
+                    "E01".to_owned()
+                }
+            }
+            ("m", args) => {
+                let range = args.split_once(',').and_then(|(addr, len)| {
+                    Some((parse_hex::<u32>(addr).ok()?, parse_hex::<u32>(len).ok()?))
+                });
+                let bytes = range.and_then(|(addr, len)| {
+                    let start = address(addr);
+                    (0..len.min(MAX_READ) as u16)
+                        .map(|i| {
+                            let addr = Address {
+                                bank: start.bank,
+                                addr: start.addr.wrapping_add(i),
+                            };
+                            gameboy.mem.debug_get_u8(addr)
+                        })
+                        .map(|byte| byte.map(|byte| format!("{:02x}", byte)))
+                        .collect::<Option<String>>()
+                });
+                bytes.unwrap_or_else(|| "E01".to_owned())
+            }
+            // Writes go through the memory map, as the CPU's would, so they
+            // can't name a bank.
+            ("M", args) => {
+                let write = args.split_once(':').and_then(|(range, bytes)| {
+                    let (addr, _) = range.split_once(',')?;
+                    let addr = parse_hex::<u16>(addr).ok()?;
+                    Some((addr, decode_hex(bytes)?))
+                });
+                match write {
+                    Some((addr, bytes)) => {
+                        for (i, byte) in bytes.into_iter().enumerate() {
+                            ProgramMemory::set_u8(
+                                &mut gameboy.mem,
+                                addr.wrapping_add(i as u16),
+                                byte,
+                            );
+                        }
+                        "OK".to_owned()
+                    }
+                    None => "E01".to_owned(),
+                }
+            }
+            ("c", _) => {
+                gameboy.debugger.as_mut()?.command(Command::Continue);
+                client.waiting = Some(SIGTRAP);
+                return None;
+            }
+            ("s", _) => {
+                gameboy.debugger.as_mut()?.command(Command::Step);
+                client.waiting = Some(SIGTRAP);
+                return None;
+            }
+            ("Z", args) | ("z", args) => {
+                let debugger = gameboy.debugger.as_mut()?;
+                match Self::breakpoint(debugger, kind == "Z", args) {
+                    Some(()) => "OK".to_owned(),
+                    None => String::new(),
+                }
+            }
+            ("D", _) => {
+                gameboy.debugger.as_mut()?.command(Command::Continue);
+                "OK".to_owned()
+            }
+            ("H", _) => "OK".to_owned(),
+            ("q", _) => Self::query(data),
+            _ => String::new(),
+        };
+        Some(reply)
+    }
+
+    /// Insert or remove a breakpoint or watchpoint for `Z` and `z`, or `None`
+    /// if the type isn't supported.
+    fn breakpoint(debugger: &mut Debugger, insert: bool, args: &str) -> Option<()> {
+        let mut args = args.split(',');
+        let kind = args.next()?;
+        let addr = address(parse_hex(args.next()?).ok()?);
+        let len = parse_hex::<u16>(args.next()?).ok()?.max(1);
+
+        let watch_kind = match kind {
+            "0" | "1" => {
+                debugger.command(if insert {
+                    Command::InsertBreakpoint(Breakpoint::new(addr))
+                } else {
+                    Command::RemoveBreakpoint(addr)
+                });
+                return Some(());
+            }
+            "2" => WatchKind::Write,
+            "3" => WatchKind::Read,
+            "4" => WatchKind::Access,
+            _ => return None,
+        };
+        let watchpoint = Watchpoint {
+            kind: watch_kind,
+            bank: addr.bank,
+            range: addr.addr..=addr.addr.saturating_add(len - 1),
+            condition: WatchCondition::Any,
+        };
+        debugger.command(if insert {
+            Command::InsertWatchpoint(watchpoint)
+        } else {
+            Command::RemoveWatchpoint(watchpoint)
+        });
+        Some(())
+    }
+
+    fn query(data: &str) -> String {
+        if data.starts_with("qSupported") {
+            // Stops are a plain SIGTRAP, without the reason `swbreak` and
+            // `hwbreak` would add.
+            "PacketSize=1000;qXfer:features:read+".to_owned()
+        } else if let Some(args) = data.strip_prefix("qXfer:features:read:target.xml:") {
+            let range = args.split_once(',').and_then(|(offset, len)| {
+                Some((
+                    parse_hex::<usize>(offset).ok()?,
+                    parse_hex::<usize>(len).ok()?,
+                ))
+            });
+            match range {
+                Some((offset, len)) => {
+                    let start = offset.min(TARGET_XML.len());
+                    let end = (offset + len).min(TARGET_XML.len());
+                    let more = if end < TARGET_XML.len() { 'm' } else { 'l' };
+                    format!("{}{}", more, &TARGET_XML[start..end])
+                }
+                None => "E01".to_owned(),
+            }
+        } else {
+            match data {
+                "qAttached" => "1".to_owned(),
+                "qC" => "QC1".to_owned(),
+                "qfThreadInfo" => "m1".to_owned(),
+                "qsThreadInfo" => "l".to_owned(),
+                _ => String::new(),
+            }
+        }
+    }
+}
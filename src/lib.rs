@@ -1,21 +1,30 @@
+mod address;
 pub mod app;
 mod buttons;
+pub mod console;
 mod cpu;
-mod dasm;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dap;
+pub mod dasm;
 mod debugger;
 mod gameboy;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod gdb;
 mod governor;
 pub mod loader;
 mod mbc;
 mod memory;
 mod memory_map;
+mod opcodes;
 mod ppu;
 mod registers;
 pub mod rom;
 mod serial;
+pub mod source_map;
 pub mod symbols;
 pub mod time;
 mod timer;
+pub mod trace;
 mod ui;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -5,6 +5,7 @@ use crate::debugger::Debugger;
 use crate::gameboy::Gameboy;
 use crate::memory::Memory;
 use crate::rom::{Data, Rom};
+use crate::source_map::SourceMap;
 use crate::symbols::Symbols;
 
 #[derive(Default)]
@@ -12,6 +13,7 @@ pub struct Loader {
     pub rom: Rom,
     rom_path: Option<String>,
     symbols: Option<Symbols>,
+    source_map: Option<SourceMap>,
 }
 
 impl Loader {
@@ -20,6 +22,7 @@ impl Loader {
             rom,
             rom_path,
             symbols: None,
+            source_map: None,
         }
     }
 
@@ -28,6 +31,19 @@ impl Loader {
         self
     }
 
+    pub fn symbols(&self) -> Option<&Symbols> {
+        self.symbols.as_ref()
+    }
+
+    pub fn with_source_map(mut self, source_map: Option<SourceMap>) -> Self {
+        self.source_map = source_map;
+        self
+    }
+
+    pub fn source_map(&self) -> Option<&SourceMap> {
+        self.source_map.as_ref()
+    }
+
     pub fn load_rom(&self) -> Result<Gameboy, std::io::Error> {
         let data = match self.rom {
             Rom::File => {
@@ -58,6 +74,7 @@ impl Loader {
 
     pub fn reset_gameboy(&self, gameboy: &mut Gameboy) -> Result<(), std::io::Error> {
         let mut debugger = gameboy.detach_debugger();
+        let trace = gameboy.cpu.trace.take();
         if let Some(ref mut debugger) = debugger {
             debugger.reset();
         }
@@ -84,6 +101,7 @@ impl Loader {
         let mem = Memory::from(data);
         let mut new_gameboy = Gameboy::new(mem);
         new_gameboy.attach_debugger(debugger);
+        new_gameboy.cpu.trace = trace;
         std::mem::swap(&mut new_gameboy, gameboy);
         Ok(())
     }
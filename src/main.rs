@@ -3,15 +3,23 @@ fn main() -> Result<(), std::io::Error> {
     use clap::Parser;
     use egb::app::App;
     use egb::args::Args;
+    use egb::console;
+    use egb::dap::DapServer;
+    use egb::dasm::{export_rgbds, Analysis};
+    use egb::gdb::GdbServer;
     use egb::loader::Loader;
     use egb::rom::Rom;
+    use egb::source_map::SourceMap;
     use egb::symbols::Symbols;
+    use egb::trace::Trace;
     use std::fs::File;
+    use std::io::{BufWriter, Write};
 
     fn load_symbols(path: Option<String>) -> Result<Option<Symbols>, std::io::Error> {
         if let Some(ref path) = path {
             let file = File::open(path)?;
-            let symbols = Symbols::try_from(file)?;
+            let symbols = Symbols::try_from(file)
+                .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
             Ok(Some(symbols))
         } else {
             Ok(None)
@@ -28,10 +36,43 @@ fn main() -> Result<(), std::io::Error> {
 
     let symbols = load_symbols(args.symbols)?;
 
-    let loader = Loader::new(rom, args.rom_file).with_symbols(symbols.clone());
-    let gameboy = loader.load_rom()?;
+    let source_map = args
+        .lines
+        .map(|path| {
+            SourceMap::load(&path)
+                .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", path, e)))
+        })
+        .transpose()?;
+
+    let loader = Loader::new(rom, args.rom_file)
+        .with_symbols(symbols.clone())
+        .with_source_map(source_map);
+    let mut gameboy = loader.load_rom()?;
+    if let Some(ref path) = args.trace {
+        gameboy.cpu.trace = Some(Trace::create(path)?.with_stub_ly(args.trace_doctor));
+    }
+
+    if let Some(ref path) = args.export_asm {
+        let analysis = Analysis::new(&gameboy.mem, []);
+        let mut out = BufWriter::new(File::create(path)?);
+        export_rgbds(&gameboy.mem, &analysis, symbols.as_ref(), &mut out)?;
+        return out.flush();
+    }
 
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+    if args.headless {
+        return console::repl(gameboy, loader, symbols);
+    }
+    let gdb = args.gdb.map(GdbServer::bind).transpose()?;
+    let dap = args
+        .dap
+        .map(|port| {
+            DapServer::bind(port).map(|dap| {
+                dap.with_symbols(symbols.clone())
+                    .with_source_map(loader.source_map().cloned())
+            })
+        })
+        .transpose()?;
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([1920.0, 1080.0]),
         ..Default::default()
@@ -40,7 +81,9 @@ fn main() -> Result<(), std::io::Error> {
         "Gameboy Emulator",
         options,
         Box::new(|_| {
-            let app = App::new(gameboy, loader, symbols);
+            let app = App::new(gameboy, loader, symbols)
+                .with_gdb(gdb)
+                .with_dap(dap);
             Box::new(app)
         }),
     )
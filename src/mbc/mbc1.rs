@@ -82,6 +82,22 @@ impl Mbc1 {
             secondary_banking: 0,
         }
     }
+
+    fn rom_bank(&self) -> usize {
+        if self.advanced_banking_mode {
+            ((self.secondary_banking & 0x3) << 4) | (self.primary_banking & 0xf)
+        } else {
+            self.primary_banking
+        }
+    }
+
+    fn ram_bank(&self) -> usize {
+        if self.advanced_banking_mode {
+            self.secondary_banking
+        } else {
+            0
+        }
+    }
 }
 
 impl Mbc for Mbc1 {
@@ -104,11 +120,8 @@ impl Mbc for Mbc1 {
             0x8000..=0x9fff => self.vram[address as usize - 0x8000] = value,
             0xa000..=0xbfff => {
                 if self.ram_enable {
-                    if self.advanced_banking_mode {
-                        self.external_ram[self.secondary_banking][address as usize - 0xa000] = value
-                    } else {
-                        self.external_ram[0][address as usize - 0xa000] = value
-                    }
+                    let bank = self.ram_bank();
+                    self.external_ram[bank][address as usize - 0xa000] = value
                 }
             }
             0xc000..=0xcfff => self.wram1[address as usize - 0xc000] = value,
@@ -123,22 +136,11 @@ impl Mbc for Mbc1 {
         let address = address.into();
         match address {
             0x0000..=0x3fff => self.fixed_rom[address as usize],
-            0x4000..=0x7fff => {
-                if self.advanced_banking_mode {
-                    let bank = ((self.secondary_banking & 0x3) << 4) | (self.primary_banking & 0xf);
-                    self.switchable_rom[bank - 1][address as usize - 0x4000]
-                } else {
-                    self.switchable_rom[self.primary_banking - 1][address as usize - 0x4000]
-                }
-            }
+            0x4000..=0x7fff => self.switchable_rom[self.rom_bank() - 1][address as usize - 0x4000],
             0x8000..=0x9fff => self.vram[address as usize - 0x8000],
             0xa000..=0xbfff => {
                 if self.ram_enable {
-                    if self.advanced_banking_mode {
-                        self.external_ram[self.secondary_banking][address as usize - 0xa000]
-                    } else {
-                        self.external_ram[0][address as usize - 0xa000]
-                    }
+                    self.external_ram[self.ram_bank()][address as usize - 0xa000]
                 } else {
                     0xff
                 }
@@ -151,6 +153,38 @@ impl Mbc for Mbc1 {
         }
     }
 
+    fn bank(&self, address: u16) -> Option<u16> {
+        match address {
+            0x0000..=0x3fff => Some(0),
+            0x4000..=0x7fff => Some(self.rom_bank() as u16),
+            0xa000..=0xbfff => Some(self.ram_bank() as u16),
+            // No WRAM banking on DMG, but keep the numbering CGB uses.
+            0xc000..=0xcfff => Some(0),
+            0xd000..=0xdfff => Some(1),
+            _ => None,
+        }
+    }
+
+    fn rom_bank_data(&self, bank: u16) -> Option<&[u8]> {
+        match bank {
+            0 => Some(&self.fixed_rom),
+            bank => self
+                .switchable_rom
+                .get(bank as usize - 1)
+                .map(|rom| rom.as_slice()),
+        }
+    }
+
+    fn rom_bank_data_mut(&mut self, bank: u16) -> Option<&mut [u8]> {
+        match bank {
+            0 => Some(&mut self.fixed_rom),
+            bank => self
+                .switchable_rom
+                .get_mut(bank as usize - 1)
+                .map(|rom| rom.as_mut_slice()),
+        }
+    }
+
     fn get_vram(&self) -> &VRam {
         &self.vram
     }
@@ -166,17 +200,8 @@ impl Mbc for Mbc1 {
 
 impl Into<BytesMut> for &Mbc1 {
     fn into(self) -> BytesMut {
-        let rom = if self.advanced_banking_mode {
-            let bank = ((self.secondary_banking & 0x3) << 4) | (self.primary_banking & 0xf);
-            &self.switchable_rom[bank - 1]
-        } else {
-            &self.switchable_rom[self.primary_banking - 1]
-        };
-        let ram = if self.advanced_banking_mode {
-            self.external_ram[self.secondary_banking]
-        } else {
-            self.external_ram[0]
-        };
+        let rom = &self.switchable_rom[self.rom_bank() - 1];
+        let ram = self.external_ram[self.ram_bank()];
         let iter = self
             .fixed_rom
             .iter()
@@ -11,6 +11,11 @@ where
 {
     fn set_u8(&mut self, address: impl Into<u16>, value: u8);
     fn get_u8(&self, address: impl Into<u16>) -> u8;
+    fn bank(&self, address: u16) -> Option<u16>;
+    /// Contents of a ROM bank, whether or not it is mapped in.
+    fn rom_bank_data(&self, bank: u16) -> Option<&[u8]>;
+    /// A ROM bank to patch, without going through the MBC registers.
+    fn rom_bank_data_mut(&mut self, bank: u16) -> Option<&mut [u8]>;
     fn get_vram(&self) -> &VRam;
     fn get_upper_ram(&self) -> &UpperRam;
     fn get_upper_ram_mut(&mut self) -> &mut UpperRam;
@@ -3,6 +3,7 @@ mod boot_rom;
 use bitflags::Flags;
 use bytes::BytesMut;
 
+use crate::address::Address;
 use crate::buttons::Buttons;
 use crate::mbc::{Mbc, Mbc1};
 use crate::memory_map::MemoryMap;
@@ -28,6 +29,10 @@ pub trait ProgramMemory {
         let h = self.get_u8(address.wrapping_add(1));
         ((h as u16) << 8) | (l as u16)
     }
+    /// Bank mapped at `address`, or `None` if the region isn't banked.
+    fn bank(&self, _address: u16) -> Option<u16> {
+        None
+    }
 }
 
 pub struct Memory {
@@ -78,6 +83,57 @@ impl Memory {
     pub fn get_upper_ram(&self) -> &UpperRam {
         self.mbc.get_upper_ram()
     }
+
+    /// Whether the boot ROM is still mapped over 0x0000-0x00ff.
+    pub fn boot_rom_mapped(&self) -> bool {
+        self.mbc.get_u8(MemoryMap::BootRomDisable) == 0
+    }
+
+    /// Read as a debugger inspecting memory: ROM is read from the bank given,
+    /// mapped in or not. Other banked memory can only be read in the bank
+    /// mapped in.
+    pub fn debug_get_u8(&self, addr: Address) -> Option<u8> {
+        let Some(bank) = addr.bank else {
+            return Some(ProgramMemory::get_u8(self, addr.addr));
+        };
+        match addr.addr {
+            0x0000..=0x3fff if bank == 0 => {
+                Some(self.mbc.rom_bank_data(0)?[usize::from(addr.addr)])
+            }
+            0x4000..=0x7fff => Some(self.mbc.rom_bank_data(bank)?[usize::from(addr.addr & 0x3fff)]),
+            _ if self.mbc.bank(addr.addr) == Some(bank) => {
+                Some(ProgramMemory::get_u8(self, addr.addr))
+            }
+            _ => None,
+        }
+    }
+
+    /// Write as a debugger patching code: ROM is changed in the bank given,
+    /// or the one mapped in, instead of the write going to the MBC.
+    pub fn debug_set_u8(&mut self, addr: Address, value: u8) {
+        let bank = match addr.addr {
+            0x0000..=0x3fff => 0,
+            0x4000..=0x7fff => match addr.bank.or_else(|| self.mbc.bank(addr.addr)) {
+                Some(bank) => bank,
+                None => return,
+            },
+            _ => return ProgramMemory::set_u8(self, addr.addr, value),
+        };
+        if let Some(rom) = self.mbc.rom_bank_data_mut(bank) {
+            rom[usize::from(addr.addr & 0x3fff)] = value;
+        }
+    }
+
+    /// The cartridge ROM, with any patches.
+    pub fn rom(&self) -> Vec<u8> {
+        let mut rom = Vec::new();
+        let mut bank = 0;
+        while let Some(data) = self.mbc.rom_bank_data(bank) {
+            rom.extend_from_slice(data);
+            bank += 1;
+        }
+        rom
+    }
 }
 
 impl From<&Memory> for BytesMut {
@@ -120,7 +176,7 @@ impl ProgramMemory for Memory {
         let address = address.into();
         match address {
             0x0000..=0x00FF => {
-                if self.mbc.get_u8(MemoryMap::BootRomDisable) == 0 {
+                if self.boot_rom_mapped() {
                     BOOT_ROM[address as usize]
                 } else {
                     self.mbc.get_u8(address)
@@ -139,4 +195,8 @@ impl ProgramMemory for Memory {
             _ => self.mbc.get_u8(address),
         }
     }
+
+    fn bank(&self, address: u16) -> Option<u16> {
+        self.mbc.bank(address)
+    }
 }
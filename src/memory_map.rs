@@ -1,3 +1,4 @@
+#[derive(Clone, Copy)]
 #[repr(u16)]
 pub enum MemoryMap {
     InterruptVBlank = 0x0040,
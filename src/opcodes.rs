@@ -0,0 +1,713 @@
+//! Opcode metadata shared by the CPU, the disassembler and the debugger.
+//!
+//! Cycle counts are in M-cycles and include the opcode fetch, and for CB
+//! instructions the prefix fetch as well.
+
+use crate::registers::CpuFlags;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Condition {
+    Nz,
+    Z,
+    Nc,
+    C,
+}
+
+impl Condition {
+    pub fn holds(self, flags: &CpuFlags) -> bool {
+        match self {
+            Condition::Nz => !flags.contains(CpuFlags::Z),
+            Condition::Z => flags.contains(CpuFlags::Z),
+            Condition::Nc => !flags.contains(CpuFlags::C),
+            Condition::C => flags.contains(CpuFlags::C),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operand {
+    A,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    Af,
+    Bc,
+    De,
+    Hl,
+    Sp,
+    IndBc,
+    IndDe,
+    IndHl,
+    IndHlInc,
+    IndHlDec,
+    /// (0xff00 + c)
+    IndHighC,
+    Imm8,
+    Imm16,
+    IndImm16,
+    /// (0xff00 + n)
+    IndHighImm8,
+    /// Signed offset added to SP.
+    Offset8,
+    /// SP plus a signed offset.
+    SpOffset8,
+    /// Signed offset from the next instruction.
+    Rel8,
+    Bit(u8),
+    Cond(Condition),
+    Vector(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlagEffect {
+    Unaffected,
+    Reset,
+    Set,
+    Affected,
+}
+
+/// How an instruction affects each of Z, N, H and C, in that order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FlagEffects(pub [FlagEffect; 4]);
+
+impl FlagEffects {
+    /// Parse the usual `Z0H-` notation.
+    const fn parse(s: &str) -> Self {
+        let bytes = s.as_bytes();
+        let mut effects = [FlagEffect::Unaffected; 4];
+        let mut i = 0;
+        while i < 4 {
+            effects[i] = match bytes[i] {
+                b'-' => FlagEffect::Unaffected,
+                b'0' => FlagEffect::Reset,
+                b'1' => FlagEffect::Set,
+                _ => FlagEffect::Affected,
+            };
+            i += 1;
+        }
+        Self(effects)
+    }
+}
+
+impl std::fmt::Display for FlagEffects {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (effect, name) in self.0.iter().zip(['Z', 'N', 'H', 'C']) {
+            let c = match effect {
+                FlagEffect::Unaffected => '-',
+                FlagEffect::Reset => '0',
+                FlagEffect::Set => '1',
+                FlagEffect::Affected => name,
+            };
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Flow {
+    /// Continues with the following instruction.
+    Next,
+    Jump,
+    Call,
+    Return,
+    Halt,
+    Stop,
+    /// 0xcb, the real instruction is in [`CB_OPCODES`].
+    Prefix,
+    Illegal,
+}
+
+impl Flow {
+    /// Whether PC simply moves past the instruction once it completes.
+    pub fn falls_through(self) -> bool {
+        matches!(self, Flow::Next | Flow::Halt | Flow::Stop)
+    }
+}
+
+pub struct Opcode {
+    pub mnemonic: &'static str,
+    pub operands: &'static [Operand],
+    pub length: u8,
+    pub cycles: u8,
+    /// Cycles when a conditional branch is not taken, otherwise the same as
+    /// `cycles`.
+    pub cycles_not_taken: u8,
+    pub flags: FlagEffects,
+    pub flow: Flow,
+}
+
+impl Opcode {
+    pub fn condition(&self) -> Option<Condition> {
+        self.operands.iter().find_map(|operand| match operand {
+            Operand::Cond(cond) => Some(*cond),
+            _ => None,
+        })
+    }
+
+    /// Destination of a jump or call that doesn't depend on registers.
+    /// `imm` is the instruction's immediate operand.
+    pub fn target(&self, addr: u16, imm: u16) -> Option<u16> {
+        if !matches!(self.flow, Flow::Jump | Flow::Call) {
+            return None;
+        }
+        self.operands.iter().find_map(|operand| match operand {
+            Operand::Imm16 => Some(imm),
+            Operand::Rel8 => Some(
+                addr.wrapping_add(self.length.into())
+                    .wrapping_add_signed((imm as u8 as i8).into()),
+            ),
+            Operand::Vector(vector) => Some(*vector as u16),
+            _ => None,
+        })
+    }
+}
+
+/// Look up an instruction from its first two bytes.
+pub fn decode(opcode: u8, next: u8) -> &'static Opcode {
+    if opcode == 0xcb {
+        &CB_OPCODES[next as usize]
+    } else {
+        &OPCODES[opcode as usize]
+    }
+}
+
+macro_rules! op {
+    ($mnemonic:literal, [$($operand:expr),*], $length:literal, $cycles:literal / $not_taken:literal, $flags:literal, $flow:ident) => {
+        Opcode {
+            mnemonic: $mnemonic,
+            operands: &[$($operand),*],
+            length: $length,
+            cycles: $cycles,
+            cycles_not_taken: $not_taken,
+            flags: FlagEffects::parse($flags),
+            flow: Flow::$flow,
+        }
+    };
+    ($mnemonic:literal, [$($operand:expr),*], $length:literal, $cycles:literal, $flags:literal, $flow:ident) => {
+        op!($mnemonic, [$($operand),*], $length, $cycles / $cycles, $flags, $flow)
+    };
+}
+
+use Operand::*;
+
+pub static OPCODES: [Opcode; 256] = [
+    op!("nop", [], 1, 1, "----", Next),            // 0x00
+    op!("ld", [Bc, Imm16], 3, 3, "----", Next),    // 0x01
+    op!("ld", [IndBc, A], 1, 2, "----", Next),     // 0x02
+    op!("inc", [Bc], 1, 2, "----", Next),          // 0x03
+    op!("inc", [B], 1, 1, "Z0H-", Next),           // 0x04
+    op!("dec", [B], 1, 1, "Z1H-", Next),           // 0x05
+    op!("ld", [B, Imm8], 2, 2, "----", Next),      // 0x06
+    op!("rlca", [], 1, 1, "000C", Next),           // 0x07
+    op!("ld", [IndImm16, Sp], 3, 5, "----", Next), // 0x08
+    op!("add", [Hl, Bc], 1, 2, "-0HC", Next),      // 0x09
+    op!("ld", [A, IndBc], 1, 2, "----", Next),     // 0x0a
+    op!("dec", [Bc], 1, 2, "----", Next),          // 0x0b
+    op!("inc", [C], 1, 1, "Z0H-", Next),           // 0x0c
+    op!("dec", [C], 1, 1, "Z1H-", Next),           // 0x0d
+    op!("ld", [C, Imm8], 2, 2, "----", Next),      // 0x0e
+    op!("rrca", [], 1, 1, "000C", Next),           // 0x0f
+    op!("stop", [], 2, 1, "----", Stop),           // 0x10
+    op!("ld", [De, Imm16], 3, 3, "----", Next),    // 0x11
+    op!("ld", [IndDe, A], 1, 2, "----", Next),     // 0x12
+    op!("inc", [De], 1, 2, "----", Next),          // 0x13
+    op!("inc", [D], 1, 1, "Z0H-", Next),           // 0x14
+    op!("dec", [D], 1, 1, "Z1H-", Next),           // 0x15
+    op!("ld", [D, Imm8], 2, 2, "----", Next),      // 0x16
+    op!("rla", [], 1, 1, "000C", Next),            // 0x17
+    op!("jr", [Rel8], 2, 3, "----", Jump),         // 0x18
+    op!("add", [Hl, De], 1, 2, "-0HC", Next),      // 0x19
+    op!("ld", [A, IndDe], 1, 2, "----", Next),     // 0x1a
+    op!("dec", [De], 1, 2, "----", Next),          // 0x1b
+    op!("inc", [E], 1, 1, "Z0H-", Next),           // 0x1c
+    op!("dec", [E], 1, 1, "Z1H-", Next),           // 0x1d
+    op!("ld", [E, Imm8], 2, 2, "----", Next),      // 0x1e
+    op!("rra", [], 1, 1, "000C", Next),            // 0x1f
+    op!("jr", [Cond(Condition::Nz), Rel8], 2, 3 / 2, "----", Jump), // 0x20
+    op!("ld", [Hl, Imm16], 3, 3, "----", Next),    // 0x21
+    op!("ld", [IndHlInc, A], 1, 2, "----", Next),  // 0x22
+    op!("inc", [Hl], 1, 2, "----", Next),          // 0x23
+    op!("inc", [H], 1, 1, "Z0H-", Next),           // 0x24
+    op!("dec", [H], 1, 1, "Z1H-", Next),           // 0x25
+    op!("ld", [H, Imm8], 2, 2, "----", Next),      // 0x26
+    op!("daa", [], 1, 1, "Z-0C", Next),            // 0x27
+    op!("jr", [Cond(Condition::Z), Rel8], 2, 3 / 2, "----", Jump), // 0x28
+    op!("add", [Hl, Hl], 1, 2, "-0HC", Next),      // 0x29
+    op!("ld", [A, IndHlInc], 1, 2, "----", Next),  // 0x2a
+    op!("dec", [Hl], 1, 2, "----", Next),          // 0x2b
+    op!("inc", [L], 1, 1, "Z0H-", Next),           // 0x2c
+    op!("dec", [L], 1, 1, "Z1H-", Next),           // 0x2d
+    op!("ld", [L, Imm8], 2, 2, "----", Next),      // 0x2e
+    op!("cpl", [], 1, 1, "-11-", Next),            // 0x2f
+    op!("jr", [Cond(Condition::Nc), Rel8], 2, 3 / 2, "----", Jump), // 0x30
+    op!("ld", [Sp, Imm16], 3, 3, "----", Next),    // 0x31
+    op!("ld", [IndHlDec, A], 1, 2, "----", Next),  // 0x32
+    op!("inc", [Sp], 1, 2, "----", Next),          // 0x33
+    op!("inc", [IndHl], 1, 3, "Z0H-", Next),       // 0x34
+    op!("dec", [IndHl], 1, 3, "Z1H-", Next),       // 0x35
+    op!("ld", [IndHl, Imm8], 2, 3, "----", Next),  // 0x36
+    op!("scf", [], 1, 1, "-001", Next),            // 0x37
+    op!("jr", [Cond(Condition::C), Rel8], 2, 3 / 2, "----", Jump), // 0x38
+    op!("add", [Hl, Sp], 1, 2, "-0HC", Next),      // 0x39
+    op!("ld", [A, IndHlDec], 1, 2, "----", Next),  // 0x3a
+    op!("dec", [Sp], 1, 2, "----", Next),          // 0x3b
+    op!("inc", [A], 1, 1, "Z0H-", Next),           // 0x3c
+    op!("dec", [A], 1, 1, "Z1H-", Next),           // 0x3d
+    op!("ld", [A, Imm8], 2, 2, "----", Next),      // 0x3e
+    op!("ccf", [], 1, 1, "-00C", Next),            // 0x3f
+    op!("ld", [B, B], 1, 1, "----", Next),         // 0x40
+    op!("ld", [B, C], 1, 1, "----", Next),         // 0x41
+    op!("ld", [B, D], 1, 1, "----", Next),         // 0x42
+    op!("ld", [B, E], 1, 1, "----", Next),         // 0x43
+    op!("ld", [B, H], 1, 1, "----", Next),         // 0x44
+    op!("ld", [B, L], 1, 1, "----", Next),         // 0x45
+    op!("ld", [B, IndHl], 1, 2, "----", Next),     // 0x46
+    op!("ld", [B, A], 1, 1, "----", Next),         // 0x47
+    op!("ld", [C, B], 1, 1, "----", Next),         // 0x48
+    op!("ld", [C, C], 1, 1, "----", Next),         // 0x49
+    op!("ld", [C, D], 1, 1, "----", Next),         // 0x4a
+    op!("ld", [C, E], 1, 1, "----", Next),         // 0x4b
+    op!("ld", [C, H], 1, 1, "----", Next),         // 0x4c
+    op!("ld", [C, L], 1, 1, "----", Next),         // 0x4d
+    op!("ld", [C, IndHl], 1, 2, "----", Next),     // 0x4e
+    op!("ld", [C, A], 1, 1, "----", Next),         // 0x4f
+    op!("ld", [D, B], 1, 1, "----", Next),         // 0x50
+    op!("ld", [D, C], 1, 1, "----", Next),         // 0x51
+    op!("ld", [D, D], 1, 1, "----", Next),         // 0x52
+    op!("ld", [D, E], 1, 1, "----", Next),         // 0x53
+    op!("ld", [D, H], 1, 1, "----", Next),         // 0x54
+    op!("ld", [D, L], 1, 1, "----", Next),         // 0x55
+    op!("ld", [D, IndHl], 1, 2, "----", Next),     // 0x56
+    op!("ld", [D, A], 1, 1, "----", Next),         // 0x57
+    op!("ld", [E, B], 1, 1, "----", Next),         // 0x58
+    op!("ld", [E, C], 1, 1, "----", Next),         // 0x59
+    op!("ld", [E, D], 1, 1, "----", Next),         // 0x5a
+    op!("ld", [E, E], 1, 1, "----", Next),         // 0x5b
+    op!("ld", [E, H], 1, 1, "----", Next),         // 0x5c
+    op!("ld", [E, L], 1, 1, "----", Next),         // 0x5d
+    op!("ld", [E, IndHl], 1, 2, "----", Next),     // 0x5e
+    op!("ld", [E, A], 1, 1, "----", Next),         // 0x5f
+    op!("ld", [H, B], 1, 1, "----", Next),         // 0x60
+    op!("ld", [H, C], 1, 1, "----", Next),         // 0x61
+    op!("ld", [H, D], 1, 1, "----", Next),         // 0x62
+    op!("ld", [H, E], 1, 1, "----", Next),         // 0x63
+    op!("ld", [H, H], 1, 1, "----", Next),         // 0x64
+    op!("ld", [H, L], 1, 1, "----", Next),         // 0x65
+    op!("ld", [H, IndHl], 1, 2, "----", Next),     // 0x66
+    op!("ld", [H, A], 1, 1, "----", Next),         // 0x67
+    op!("ld", [L, B], 1, 1, "----", Next),         // 0x68
+    op!("ld", [L, C], 1, 1, "----", Next),         // 0x69
+    op!("ld", [L, D], 1, 1, "----", Next),         // 0x6a
+    op!("ld", [L, E], 1, 1, "----", Next),         // 0x6b
+    op!("ld", [L, H], 1, 1, "----", Next),         // 0x6c
+    op!("ld", [L, L], 1, 1, "----", Next),         // 0x6d
+    op!("ld", [L, IndHl], 1, 2, "----", Next),     // 0x6e
+    op!("ld", [L, A], 1, 1, "----", Next),         // 0x6f
+    op!("ld", [IndHl, B], 1, 2, "----", Next),     // 0x70
+    op!("ld", [IndHl, C], 1, 2, "----", Next),     // 0x71
+    op!("ld", [IndHl, D], 1, 2, "----", Next),     // 0x72
+    op!("ld", [IndHl, E], 1, 2, "----", Next),     // 0x73
+    op!("ld", [IndHl, H], 1, 2, "----", Next),     // 0x74
+    op!("ld", [IndHl, L], 1, 2, "----", Next),     // 0x75
+    op!("halt", [], 1, 1, "----", Halt),           // 0x76
+    op!("ld", [IndHl, A], 1, 2, "----", Next),     // 0x77
+    op!("ld", [A, B], 1, 1, "----", Next),         // 0x78
+    op!("ld", [A, C], 1, 1, "----", Next),         // 0x79
+    op!("ld", [A, D], 1, 1, "----", Next),         // 0x7a
+    op!("ld", [A, E], 1, 1, "----", Next),         // 0x7b
+    op!("ld", [A, H], 1, 1, "----", Next),         // 0x7c
+    op!("ld", [A, L], 1, 1, "----", Next),         // 0x7d
+    op!("ld", [A, IndHl], 1, 2, "----", Next),     // 0x7e
+    op!("ld", [A, A], 1, 1, "----", Next),         // 0x7f
+    op!("add", [A, B], 1, 1, "Z0HC", Next),        // 0x80
+    op!("add", [A, C], 1, 1, "Z0HC", Next),        // 0x81
+    op!("add", [A, D], 1, 1, "Z0HC", Next),        // 0x82
+    op!("add", [A, E], 1, 1, "Z0HC", Next),        // 0x83
+    op!("add", [A, H], 1, 1, "Z0HC", Next),        // 0x84
+    op!("add", [A, L], 1, 1, "Z0HC", Next),        // 0x85
+    op!("add", [A, IndHl], 1, 2, "Z0HC", Next),    // 0x86
+    op!("add", [A, A], 1, 1, "Z0HC", Next),        // 0x87
+    op!("adc", [A, B], 1, 1, "Z0HC", Next),        // 0x88
+    op!("adc", [A, C], 1, 1, "Z0HC", Next),        // 0x89
+    op!("adc", [A, D], 1, 1, "Z0HC", Next),        // 0x8a
+    op!("adc", [A, E], 1, 1, "Z0HC", Next),        // 0x8b
+    op!("adc", [A, H], 1, 1, "Z0HC", Next),        // 0x8c
+    op!("adc", [A, L], 1, 1, "Z0HC", Next),        // 0x8d
+    op!("adc", [A, IndHl], 1, 2, "Z0HC", Next),    // 0x8e
+    op!("adc", [A, A], 1, 1, "Z0HC", Next),        // 0x8f
+    op!("sub", [A, B], 1, 1, "Z1HC", Next),        // 0x90
+    op!("sub", [A, C], 1, 1, "Z1HC", Next),        // 0x91
+    op!("sub", [A, D], 1, 1, "Z1HC", Next),        // 0x92
+    op!("sub", [A, E], 1, 1, "Z1HC", Next),        // 0x93
+    op!("sub", [A, H], 1, 1, "Z1HC", Next),        // 0x94
+    op!("sub", [A, L], 1, 1, "Z1HC", Next),        // 0x95
+    op!("sub", [A, IndHl], 1, 2, "Z1HC", Next),    // 0x96
+    op!("sub", [A, A], 1, 1, "Z1HC", Next),        // 0x97
+    op!("sbc", [A, B], 1, 1, "Z1HC", Next),        // 0x98
+    op!("sbc", [A, C], 1, 1, "Z1HC", Next),        // 0x99
+    op!("sbc", [A, D], 1, 1, "Z1HC", Next),        // 0x9a
+    op!("sbc", [A, E], 1, 1, "Z1HC", Next),        // 0x9b
+    op!("sbc", [A, H], 1, 1, "Z1HC", Next),        // 0x9c
+    op!("sbc", [A, L], 1, 1, "Z1HC", Next),        // 0x9d
+    op!("sbc", [A, IndHl], 1, 2, "Z1HC", Next),    // 0x9e
+    op!("sbc", [A, A], 1, 1, "Z1HC", Next),        // 0x9f
+    op!("and", [A, B], 1, 1, "Z010", Next),        // 0xa0
+    op!("and", [A, C], 1, 1, "Z010", Next),        // 0xa1
+    op!("and", [A, D], 1, 1, "Z010", Next),        // 0xa2
+    op!("and", [A, E], 1, 1, "Z010", Next),        // 0xa3
+    op!("and", [A, H], 1, 1, "Z010", Next),        // 0xa4
+    op!("and", [A, L], 1, 1, "Z010", Next),        // 0xa5
+    op!("and", [A, IndHl], 1, 2, "Z010", Next),    // 0xa6
+    op!("and", [A, A], 1, 1, "Z010", Next),        // 0xa7
+    op!("xor", [A, B], 1, 1, "Z000", Next),        // 0xa8
+    op!("xor", [A, C], 1, 1, "Z000", Next),        // 0xa9
+    op!("xor", [A, D], 1, 1, "Z000", Next),        // 0xaa
+    op!("xor", [A, E], 1, 1, "Z000", Next),        // 0xab
+    op!("xor", [A, H], 1, 1, "Z000", Next),        // 0xac
+    op!("xor", [A, L], 1, 1, "Z000", Next),        // 0xad
+    op!("xor", [A, IndHl], 1, 2, "Z000", Next),    // 0xae
+    op!("xor", [A, A], 1, 1, "Z000", Next),        // 0xaf
+    op!("or", [A, B], 1, 1, "Z000", Next),         // 0xb0
+    op!("or", [A, C], 1, 1, "Z000", Next),         // 0xb1
+    op!("or", [A, D], 1, 1, "Z000", Next),         // 0xb2
+    op!("or", [A, E], 1, 1, "Z000", Next),         // 0xb3
+    op!("or", [A, H], 1, 1, "Z000", Next),         // 0xb4
+    op!("or", [A, L], 1, 1, "Z000", Next),         // 0xb5
+    op!("or", [A, IndHl], 1, 2, "Z000", Next),     // 0xb6
+    op!("or", [A, A], 1, 1, "Z000", Next),         // 0xb7
+    op!("cp", [A, B], 1, 1, "Z1HC", Next),         // 0xb8
+    op!("cp", [A, C], 1, 1, "Z1HC", Next),         // 0xb9
+    op!("cp", [A, D], 1, 1, "Z1HC", Next),         // 0xba
+    op!("cp", [A, E], 1, 1, "Z1HC", Next),         // 0xbb
+    op!("cp", [A, H], 1, 1, "Z1HC", Next),         // 0xbc
+    op!("cp", [A, L], 1, 1, "Z1HC", Next),         // 0xbd
+    op!("cp", [A, IndHl], 1, 2, "Z1HC", Next),     // 0xbe
+    op!("cp", [A, A], 1, 1, "Z1HC", Next),         // 0xbf
+    op!("ret", [Cond(Condition::Nz)], 1, 5 / 2, "----", Return), // 0xc0
+    op!("pop", [Bc], 1, 3, "----", Next),          // 0xc1
+    op!("jp", [Cond(Condition::Nz), Imm16], 3, 4 / 3, "----", Jump), // 0xc2
+    op!("jp", [Imm16], 3, 4, "----", Jump),        // 0xc3
+    op!("call", [Cond(Condition::Nz), Imm16], 3, 6 / 3, "----", Call), // 0xc4
+    op!("push", [Bc], 1, 4, "----", Next),         // 0xc5
+    op!("add", [A, Imm8], 2, 2, "Z0HC", Next),     // 0xc6
+    op!("rst", [Vector(0x00)], 1, 4, "----", Call), // 0xc7
+    op!("ret", [Cond(Condition::Z)], 1, 5 / 2, "----", Return), // 0xc8
+    op!("ret", [], 1, 4, "----", Return),          // 0xc9
+    op!("jp", [Cond(Condition::Z), Imm16], 3, 4 / 3, "----", Jump), // 0xca
+    op!("prefix", [], 1, 1, "----", Prefix),       // 0xcb
+    op!("call", [Cond(Condition::Z), Imm16], 3, 6 / 3, "----", Call), // 0xcc
+    op!("call", [Imm16], 3, 6, "----", Call),      // 0xcd
+    op!("adc", [A, Imm8], 2, 2, "Z0HC", Next),     // 0xce
+    op!("rst", [Vector(0x08)], 1, 4, "----", Call), // 0xcf
+    op!("ret", [Cond(Condition::Nc)], 1, 5 / 2, "----", Return), // 0xd0
+    op!("pop", [De], 1, 3, "----", Next),          // 0xd1
+    op!("jp", [Cond(Condition::Nc), Imm16], 3, 4 / 3, "----", Jump), // 0xd2
+    op!("undefined", [], 1, 1, "----", Illegal),   // 0xd3
+    op!("call", [Cond(Condition::Nc), Imm16], 3, 6 / 3, "----", Call), // 0xd4
+    op!("push", [De], 1, 4, "----", Next),         // 0xd5
+    op!("sub", [A, Imm8], 2, 2, "Z1HC", Next),     // 0xd6
+    op!("rst", [Vector(0x10)], 1, 4, "----", Call), // 0xd7
+    op!("ret", [Cond(Condition::C)], 1, 5 / 2, "----", Return), // 0xd8
+    op!("reti", [], 1, 4, "----", Return),         // 0xd9
+    op!("jp", [Cond(Condition::C), Imm16], 3, 4 / 3, "----", Jump), // 0xda
+    op!("undefined", [], 1, 1, "----", Illegal),   // 0xdb
+    op!("call", [Cond(Condition::C), Imm16], 3, 6 / 3, "----", Call), // 0xdc
+    op!("undefined", [], 1, 1, "----", Illegal),   // 0xdd
+    op!("sbc", [A, Imm8], 2, 2, "Z1HC", Next),     // 0xde
+    op!("rst", [Vector(0x18)], 1, 4, "----", Call), // 0xdf
+    op!("ld", [IndHighImm8, A], 2, 3, "----", Next), // 0xe0
+    op!("pop", [Hl], 1, 3, "----", Next),          // 0xe1
+    op!("ld", [IndHighC, A], 1, 2, "----", Next),  // 0xe2
+    op!("undefined", [], 1, 1, "----", Illegal),   // 0xe3
+    op!("undefined", [], 1, 1, "----", Illegal),   // 0xe4
+    op!("push", [Hl], 1, 4, "----", Next),         // 0xe5
+    op!("and", [A, Imm8], 2, 2, "Z010", Next),     // 0xe6
+    op!("rst", [Vector(0x20)], 1, 4, "----", Call), // 0xe7
+    op!("add", [Sp, Offset8], 2, 4, "00HC", Next), // 0xe8
+    op!("jp", [Hl], 1, 1, "----", Jump),           // 0xe9
+    op!("ld", [IndImm16, A], 3, 4, "----", Next),  // 0xea
+    op!("undefined", [], 1, 1, "----", Illegal),   // 0xeb
+    op!("undefined", [], 1, 1, "----", Illegal),   // 0xec
+    op!("undefined", [], 1, 1, "----", Illegal),   // 0xed
+    op!("xor", [A, Imm8], 2, 2, "Z000", Next),     // 0xee
+    op!("rst", [Vector(0x28)], 1, 4, "----", Call), // 0xef
+    op!("ld", [A, IndHighImm8], 2, 3, "----", Next), // 0xf0
+    op!("pop", [Af], 1, 3, "ZNHC", Next),          // 0xf1
+    op!("ld", [A, IndHighC], 1, 2, "----", Next),  // 0xf2
+    op!("di", [], 1, 1, "----", Next),             // 0xf3
+    op!("undefined", [], 1, 1, "----", Illegal),   // 0xf4
+    op!("push", [Af], 1, 4, "----", Next),         // 0xf5
+    op!("or", [A, Imm8], 2, 2, "Z000", Next),      // 0xf6
+    op!("rst", [Vector(0x30)], 1, 4, "----", Call), // 0xf7
+    op!("ld", [Hl, SpOffset8], 2, 3, "00HC", Next), // 0xf8
+    op!("ld", [Sp, Hl], 1, 2, "----", Next),       // 0xf9
+    op!("ld", [A, IndImm16], 3, 4, "----", Next),  // 0xfa
+    op!("ei", [], 1, 1, "----", Next),             // 0xfb
+    op!("undefined", [], 1, 1, "----", Illegal),   // 0xfc
+    op!("undefined", [], 1, 1, "----", Illegal),   // 0xfd
+    op!("cp", [A, Imm8], 2, 2, "Z1HC", Next),      // 0xfe
+    op!("rst", [Vector(0x38)], 1, 4, "----", Call), // 0xff
+];
+
+pub static CB_OPCODES: [Opcode; 256] = [
+    op!("rlc", [B], 2, 2, "Z00C", Next),             // 0x00
+    op!("rlc", [C], 2, 2, "Z00C", Next),             // 0x01
+    op!("rlc", [D], 2, 2, "Z00C", Next),             // 0x02
+    op!("rlc", [E], 2, 2, "Z00C", Next),             // 0x03
+    op!("rlc", [H], 2, 2, "Z00C", Next),             // 0x04
+    op!("rlc", [L], 2, 2, "Z00C", Next),             // 0x05
+    op!("rlc", [IndHl], 2, 4, "Z00C", Next),         // 0x06
+    op!("rlc", [A], 2, 2, "Z00C", Next),             // 0x07
+    op!("rrc", [B], 2, 2, "Z00C", Next),             // 0x08
+    op!("rrc", [C], 2, 2, "Z00C", Next),             // 0x09
+    op!("rrc", [D], 2, 2, "Z00C", Next),             // 0x0a
+    op!("rrc", [E], 2, 2, "Z00C", Next),             // 0x0b
+    op!("rrc", [H], 2, 2, "Z00C", Next),             // 0x0c
+    op!("rrc", [L], 2, 2, "Z00C", Next),             // 0x0d
+    op!("rrc", [IndHl], 2, 4, "Z00C", Next),         // 0x0e
+    op!("rrc", [A], 2, 2, "Z00C", Next),             // 0x0f
+    op!("rl", [B], 2, 2, "Z00C", Next),              // 0x10
+    op!("rl", [C], 2, 2, "Z00C", Next),              // 0x11
+    op!("rl", [D], 2, 2, "Z00C", Next),              // 0x12
+    op!("rl", [E], 2, 2, "Z00C", Next),              // 0x13
+    op!("rl", [H], 2, 2, "Z00C", Next),              // 0x14
+    op!("rl", [L], 2, 2, "Z00C", Next),              // 0x15
+    op!("rl", [IndHl], 2, 4, "Z00C", Next),          // 0x16
+    op!("rl", [A], 2, 2, "Z00C", Next),              // 0x17
+    op!("rr", [B], 2, 2, "Z00C", Next),              // 0x18
+    op!("rr", [C], 2, 2, "Z00C", Next),              // 0x19
+    op!("rr", [D], 2, 2, "Z00C", Next),              // 0x1a
+    op!("rr", [E], 2, 2, "Z00C", Next),              // 0x1b
+    op!("rr", [H], 2, 2, "Z00C", Next),              // 0x1c
+    op!("rr", [L], 2, 2, "Z00C", Next),              // 0x1d
+    op!("rr", [IndHl], 2, 4, "Z00C", Next),          // 0x1e
+    op!("rr", [A], 2, 2, "Z00C", Next),              // 0x1f
+    op!("sla", [B], 2, 2, "Z00C", Next),             // 0x20
+    op!("sla", [C], 2, 2, "Z00C", Next),             // 0x21
+    op!("sla", [D], 2, 2, "Z00C", Next),             // 0x22
+    op!("sla", [E], 2, 2, "Z00C", Next),             // 0x23
+    op!("sla", [H], 2, 2, "Z00C", Next),             // 0x24
+    op!("sla", [L], 2, 2, "Z00C", Next),             // 0x25
+    op!("sla", [IndHl], 2, 4, "Z00C", Next),         // 0x26
+    op!("sla", [A], 2, 2, "Z00C", Next),             // 0x27
+    op!("sra", [B], 2, 2, "Z00C", Next),             // 0x28
+    op!("sra", [C], 2, 2, "Z00C", Next),             // 0x29
+    op!("sra", [D], 2, 2, "Z00C", Next),             // 0x2a
+    op!("sra", [E], 2, 2, "Z00C", Next),             // 0x2b
+    op!("sra", [H], 2, 2, "Z00C", Next),             // 0x2c
+    op!("sra", [L], 2, 2, "Z00C", Next),             // 0x2d
+    op!("sra", [IndHl], 2, 4, "Z00C", Next),         // 0x2e
+    op!("sra", [A], 2, 2, "Z00C", Next),             // 0x2f
+    op!("swap", [B], 2, 2, "Z000", Next),            // 0x30
+    op!("swap", [C], 2, 2, "Z000", Next),            // 0x31
+    op!("swap", [D], 2, 2, "Z000", Next),            // 0x32
+    op!("swap", [E], 2, 2, "Z000", Next),            // 0x33
+    op!("swap", [H], 2, 2, "Z000", Next),            // 0x34
+    op!("swap", [L], 2, 2, "Z000", Next),            // 0x35
+    op!("swap", [IndHl], 2, 4, "Z000", Next),        // 0x36
+    op!("swap", [A], 2, 2, "Z000", Next),            // 0x37
+    op!("srl", [B], 2, 2, "Z00C", Next),             // 0x38
+    op!("srl", [C], 2, 2, "Z00C", Next),             // 0x39
+    op!("srl", [D], 2, 2, "Z00C", Next),             // 0x3a
+    op!("srl", [E], 2, 2, "Z00C", Next),             // 0x3b
+    op!("srl", [H], 2, 2, "Z00C", Next),             // 0x3c
+    op!("srl", [L], 2, 2, "Z00C", Next),             // 0x3d
+    op!("srl", [IndHl], 2, 4, "Z00C", Next),         // 0x3e
+    op!("srl", [A], 2, 2, "Z00C", Next),             // 0x3f
+    op!("bit", [Bit(0), B], 2, 2, "Z01-", Next),     // 0x40
+    op!("bit", [Bit(0), C], 2, 2, "Z01-", Next),     // 0x41
+    op!("bit", [Bit(0), D], 2, 2, "Z01-", Next),     // 0x42
+    op!("bit", [Bit(0), E], 2, 2, "Z01-", Next),     // 0x43
+    op!("bit", [Bit(0), H], 2, 2, "Z01-", Next),     // 0x44
+    op!("bit", [Bit(0), L], 2, 2, "Z01-", Next),     // 0x45
+    op!("bit", [Bit(0), IndHl], 2, 3, "Z01-", Next), // 0x46
+    op!("bit", [Bit(0), A], 2, 2, "Z01-", Next),     // 0x47
+    op!("bit", [Bit(1), B], 2, 2, "Z01-", Next),     // 0x48
+    op!("bit", [Bit(1), C], 2, 2, "Z01-", Next),     // 0x49
+    op!("bit", [Bit(1), D], 2, 2, "Z01-", Next),     // 0x4a
+    op!("bit", [Bit(1), E], 2, 2, "Z01-", Next),     // 0x4b
+    op!("bit", [Bit(1), H], 2, 2, "Z01-", Next),     // 0x4c
+    op!("bit", [Bit(1), L], 2, 2, "Z01-", Next),     // 0x4d
+    op!("bit", [Bit(1), IndHl], 2, 3, "Z01-", Next), // 0x4e
+    op!("bit", [Bit(1), A], 2, 2, "Z01-", Next),     // 0x4f
+    op!("bit", [Bit(2), B], 2, 2, "Z01-", Next),     // 0x50
+    op!("bit", [Bit(2), C], 2, 2, "Z01-", Next),     // 0x51
+    op!("bit", [Bit(2), D], 2, 2, "Z01-", Next),     // 0x52
+    op!("bit", [Bit(2), E], 2, 2, "Z01-", Next),     // 0x53
+    op!("bit", [Bit(2), H], 2, 2, "Z01-", Next),     // 0x54
+    op!("bit", [Bit(2), L], 2, 2, "Z01-", Next),     // 0x55
+    op!("bit", [Bit(2), IndHl], 2, 3, "Z01-", Next), // 0x56
+    op!("bit", [Bit(2), A], 2, 2, "Z01-", Next),     // 0x57
+    op!("bit", [Bit(3), B], 2, 2, "Z01-", Next),     // 0x58
+    op!("bit", [Bit(3), C], 2, 2, "Z01-", Next),     // 0x59
+    op!("bit", [Bit(3), D], 2, 2, "Z01-", Next),     // 0x5a
+    op!("bit", [Bit(3), E], 2, 2, "Z01-", Next),     // 0x5b
+    op!("bit", [Bit(3), H], 2, 2, "Z01-", Next),     // 0x5c
+    op!("bit", [Bit(3), L], 2, 2, "Z01-", Next),     // 0x5d
+    op!("bit", [Bit(3), IndHl], 2, 3, "Z01-", Next), // 0x5e
+    op!("bit", [Bit(3), A], 2, 2, "Z01-", Next),     // 0x5f
+    op!("bit", [Bit(4), B], 2, 2, "Z01-", Next),     // 0x60
+    op!("bit", [Bit(4), C], 2, 2, "Z01-", Next),     // 0x61
+    op!("bit", [Bit(4), D], 2, 2, "Z01-", Next),     // 0x62
+    op!("bit", [Bit(4), E], 2, 2, "Z01-", Next),     // 0x63
+    op!("bit", [Bit(4), H], 2, 2, "Z01-", Next),     // 0x64
+    op!("bit", [Bit(4), L], 2, 2, "Z01-", Next),     // 0x65
+    op!("bit", [Bit(4), IndHl], 2, 3, "Z01-", Next), // 0x66
+    op!("bit", [Bit(4), A], 2, 2, "Z01-", Next),     // 0x67
+    op!("bit", [Bit(5), B], 2, 2, "Z01-", Next),     // 0x68
+    op!("bit", [Bit(5), C], 2, 2, "Z01-", Next),     // 0x69
+    op!("bit", [Bit(5), D], 2, 2, "Z01-", Next),     // 0x6a
+    op!("bit", [Bit(5), E], 2, 2, "Z01-", Next),     // 0x6b
+    op!("bit", [Bit(5), H], 2, 2, "Z01-", Next),     // 0x6c
+    op!("bit", [Bit(5), L], 2, 2, "Z01-", Next),     // 0x6d
+    op!("bit", [Bit(5), IndHl], 2, 3, "Z01-", Next), // 0x6e
+    op!("bit", [Bit(5), A], 2, 2, "Z01-", Next),     // 0x6f
+    op!("bit", [Bit(6), B], 2, 2, "Z01-", Next),     // 0x70
+    op!("bit", [Bit(6), C], 2, 2, "Z01-", Next),     // 0x71
+    op!("bit", [Bit(6), D], 2, 2, "Z01-", Next),     // 0x72
+    op!("bit", [Bit(6), E], 2, 2, "Z01-", Next),     // 0x73
+    op!("bit", [Bit(6), H], 2, 2, "Z01-", Next),     // 0x74
+    op!("bit", [Bit(6), L], 2, 2, "Z01-", Next),     // 0x75
+    op!("bit", [Bit(6), IndHl], 2, 3, "Z01-", Next), // 0x76
+    op!("bit", [Bit(6), A], 2, 2, "Z01-", Next),     // 0x77
+    op!("bit", [Bit(7), B], 2, 2, "Z01-", Next),     // 0x78
+    op!("bit", [Bit(7), C], 2, 2, "Z01-", Next),     // 0x79
+    op!("bit", [Bit(7), D], 2, 2, "Z01-", Next),     // 0x7a
+    op!("bit", [Bit(7), E], 2, 2, "Z01-", Next),     // 0x7b
+    op!("bit", [Bit(7), H], 2, 2, "Z01-", Next),     // 0x7c
+    op!("bit", [Bit(7), L], 2, 2, "Z01-", Next),     // 0x7d
+    op!("bit", [Bit(7), IndHl], 2, 3, "Z01-", Next), // 0x7e
+    op!("bit", [Bit(7), A], 2, 2, "Z01-", Next),     // 0x7f
+    op!("res", [Bit(0), B], 2, 2, "----", Next),     // 0x80
+    op!("res", [Bit(0), C], 2, 2, "----", Next),     // 0x81
+    op!("res", [Bit(0), D], 2, 2, "----", Next),     // 0x82
+    op!("res", [Bit(0), E], 2, 2, "----", Next),     // 0x83
+    op!("res", [Bit(0), H], 2, 2, "----", Next),     // 0x84
+    op!("res", [Bit(0), L], 2, 2, "----", Next),     // 0x85
+    op!("res", [Bit(0), IndHl], 2, 4, "----", Next), // 0x86
+    op!("res", [Bit(0), A], 2, 2, "----", Next),     // 0x87
+    op!("res", [Bit(1), B], 2, 2, "----", Next),     // 0x88
+    op!("res", [Bit(1), C], 2, 2, "----", Next),     // 0x89
+    op!("res", [Bit(1), D], 2, 2, "----", Next),     // 0x8a
+    op!("res", [Bit(1), E], 2, 2, "----", Next),     // 0x8b
+    op!("res", [Bit(1), H], 2, 2, "----", Next),     // 0x8c
+    op!("res", [Bit(1), L], 2, 2, "----", Next),     // 0x8d
+    op!("res", [Bit(1), IndHl], 2, 4, "----", Next), // 0x8e
+    op!("res", [Bit(1), A], 2, 2, "----", Next),     // 0x8f
+    op!("res", [Bit(2), B], 2, 2, "----", Next),     // 0x90
+    op!("res", [Bit(2), C], 2, 2, "----", Next),     // 0x91
+    op!("res", [Bit(2), D], 2, 2, "----", Next),     // 0x92
+    op!("res", [Bit(2), E], 2, 2, "----", Next),     // 0x93
+    op!("res", [Bit(2), H], 2, 2, "----", Next),     // 0x94
+    op!("res", [Bit(2), L], 2, 2, "----", Next),     // 0x95
+    op!("res", [Bit(2), IndHl], 2, 4, "----", Next), // 0x96
+    op!("res", [Bit(2), A], 2, 2, "----", Next),     // 0x97
+    op!("res", [Bit(3), B], 2, 2, "----", Next),     // 0x98
+    op!("res", [Bit(3), C], 2, 2, "----", Next),     // 0x99
+    op!("res", [Bit(3), D], 2, 2, "----", Next),     // 0x9a
+    op!("res", [Bit(3), E], 2, 2, "----", Next),     // 0x9b
+    op!("res", [Bit(3), H], 2, 2, "----", Next),     // 0x9c
+    op!("res", [Bit(3), L], 2, 2, "----", Next),     // 0x9d
+    op!("res", [Bit(3), IndHl], 2, 4, "----", Next), // 0x9e
+    op!("res", [Bit(3), A], 2, 2, "----", Next),     // 0x9f
+    op!("res", [Bit(4), B], 2, 2, "----", Next),     // 0xa0
+    op!("res", [Bit(4), C], 2, 2, "----", Next),     // 0xa1
+    op!("res", [Bit(4), D], 2, 2, "----", Next),     // 0xa2
+    op!("res", [Bit(4), E], 2, 2, "----", Next),     // 0xa3
+    op!("res", [Bit(4), H], 2, 2, "----", Next),     // 0xa4
+    op!("res", [Bit(4), L], 2, 2, "----", Next),     // 0xa5
+    op!("res", [Bit(4), IndHl], 2, 4, "----", Next), // 0xa6
+    op!("res", [Bit(4), A], 2, 2, "----", Next),     // 0xa7
+    op!("res", [Bit(5), B], 2, 2, "----", Next),     // 0xa8
+    op!("res", [Bit(5), C], 2, 2, "----", Next),     // 0xa9
+    op!("res", [Bit(5), D], 2, 2, "----", Next),     // 0xaa
+    op!("res", [Bit(5), E], 2, 2, "----", Next),     // 0xab
+    op!("res", [Bit(5), H], 2, 2, "----", Next),     // 0xac
+    op!("res", [Bit(5), L], 2, 2, "----", Next),     // 0xad
+    op!("res", [Bit(5), IndHl], 2, 4, "----", Next), // 0xae
+    op!("res", [Bit(5), A], 2, 2, "----", Next),     // 0xaf
+    op!("res", [Bit(6), B], 2, 2, "----", Next),     // 0xb0
+    op!("res", [Bit(6), C], 2, 2, "----", Next),     // 0xb1
+    op!("res", [Bit(6), D], 2, 2, "----", Next),     // 0xb2
+    op!("res", [Bit(6), E], 2, 2, "----", Next),     // 0xb3
+    op!("res", [Bit(6), H], 2, 2, "----", Next),     // 0xb4
+    op!("res", [Bit(6), L], 2, 2, "----", Next),     // 0xb5
+    op!("res", [Bit(6), IndHl], 2, 4, "----", Next), // 0xb6
+    op!("res", [Bit(6), A], 2, 2, "----", Next),     // 0xb7
+    op!("res", [Bit(7), B], 2, 2, "----", Next),     // 0xb8
+    op!("res", [Bit(7), C], 2, 2, "----", Next),     // 0xb9
+    op!("res", [Bit(7), D], 2, 2, "----", Next),     // 0xba
+    op!("res", [Bit(7), E], 2, 2, "----", Next),     // 0xbb
+    op!("res", [Bit(7), H], 2, 2, "----", Next),     // 0xbc
+    op!("res", [Bit(7), L], 2, 2, "----", Next),     // 0xbd
+    op!("res", [Bit(7), IndHl], 2, 4, "----", Next), // 0xbe
+    op!("res", [Bit(7), A], 2, 2, "----", Next),     // 0xbf
+    op!("set", [Bit(0), B], 2, 2, "----", Next),     // 0xc0
+    op!("set", [Bit(0), C], 2, 2, "----", Next),     // 0xc1
+    op!("set", [Bit(0), D], 2, 2, "----", Next),     // 0xc2
+    op!("set", [Bit(0), E], 2, 2, "----", Next),     // 0xc3
+    op!("set", [Bit(0), H], 2, 2, "----", Next),     // 0xc4
+    op!("set", [Bit(0), L], 2, 2, "----", Next),     // 0xc5
+    op!("set", [Bit(0), IndHl], 2, 4, "----", Next), // 0xc6
+    op!("set", [Bit(0), A], 2, 2, "----", Next),     // 0xc7
+    op!("set", [Bit(1), B], 2, 2, "----", Next),     // 0xc8
+    op!("set", [Bit(1), C], 2, 2, "----", Next),     // 0xc9
+    op!("set", [Bit(1), D], 2, 2, "----", Next),     // 0xca
+    op!("set", [Bit(1), E], 2, 2, "----", Next),     // 0xcb
+    op!("set", [Bit(1), H], 2, 2, "----", Next),     // 0xcc
+    op!("set", [Bit(1), L], 2, 2, "----", Next),     // 0xcd
+    op!("set", [Bit(1), IndHl], 2, 4, "----", Next), // 0xce
+    op!("set", [Bit(1), A], 2, 2, "----", Next),     // 0xcf
+    op!("set", [Bit(2), B], 2, 2, "----", Next),     // 0xd0
+    op!("set", [Bit(2), C], 2, 2, "----", Next),     // 0xd1
+    op!("set", [Bit(2), D], 2, 2, "----", Next),     // 0xd2
+    op!("set", [Bit(2), E], 2, 2, "----", Next),     // 0xd3
+    op!("set", [Bit(2), H], 2, 2, "----", Next),     // 0xd4
+    op!("set", [Bit(2), L], 2, 2, "----", Next),     // 0xd5
+    op!("set", [Bit(2), IndHl], 2, 4, "----", Next), // 0xd6
+    op!("set", [Bit(2), A], 2, 2, "----", Next),     // 0xd7
+    op!("set", [Bit(3), B], 2, 2, "----", Next),     // 0xd8
+    op!("set", [Bit(3), C], 2, 2, "----", Next),     // 0xd9
+    op!("set", [Bit(3), D], 2, 2, "----", Next),     // 0xda
+    op!("set", [Bit(3), E], 2, 2, "----", Next),     // 0xdb
+    op!("set", [Bit(3), H], 2, 2, "----", Next),     // 0xdc
+    op!("set", [Bit(3), L], 2, 2, "----", Next),     // 0xdd
+    op!("set", [Bit(3), IndHl], 2, 4, "----", Next), // 0xde
+    op!("set", [Bit(3), A], 2, 2, "----", Next),     // 0xdf
+    op!("set", [Bit(4), B], 2, 2, "----", Next),     // 0xe0
+    op!("set", [Bit(4), C], 2, 2, "----", Next),     // 0xe1
+    op!("set", [Bit(4), D], 2, 2, "----", Next),     // 0xe2
+    op!("set", [Bit(4), E], 2, 2, "----", Next),     // 0xe3
+    op!("set", [Bit(4), H], 2, 2, "----", Next),     // 0xe4
+    op!("set", [Bit(4), L], 2, 2, "----", Next),     // 0xe5
+    op!("set", [Bit(4), IndHl], 2, 4, "----", Next), // 0xe6
+    op!("set", [Bit(4), A], 2, 2, "----", Next),     // 0xe7
+    op!("set", [Bit(5), B], 2, 2, "----", Next),     // 0xe8
+    op!("set", [Bit(5), C], 2, 2, "----", Next),     // 0xe9
+    op!("set", [Bit(5), D], 2, 2, "----", Next),     // 0xea
+    op!("set", [Bit(5), E], 2, 2, "----", Next),     // 0xeb
+    op!("set", [Bit(5), H], 2, 2, "----", Next),     // 0xec
+    op!("set", [Bit(5), L], 2, 2, "----", Next),     // 0xed
+    op!("set", [Bit(5), IndHl], 2, 4, "----", Next), // 0xee
+    op!("set", [Bit(5), A], 2, 2, "----", Next),     // 0xef
+    op!("set", [Bit(6), B], 2, 2, "----", Next),     // 0xf0
+    op!("set", [Bit(6), C], 2, 2, "----", Next),     // 0xf1
+    op!("set", [Bit(6), D], 2, 2, "----", Next),     // 0xf2
+    op!("set", [Bit(6), E], 2, 2, "----", Next),     // 0xf3
+    op!("set", [Bit(6), H], 2, 2, "----", Next),     // 0xf4
+    op!("set", [Bit(6), L], 2, 2, "----", Next),     // 0xf5
+    op!("set", [Bit(6), IndHl], 2, 4, "----", Next), // 0xf6
+    op!("set", [Bit(6), A], 2, 2, "----", Next),     // 0xf7
+    op!("set", [Bit(7), B], 2, 2, "----", Next),     // 0xf8
+    op!("set", [Bit(7), C], 2, 2, "----", Next),     // 0xf9
+    op!("set", [Bit(7), D], 2, 2, "----", Next),     // 0xfa
+    op!("set", [Bit(7), E], 2, 2, "----", Next),     // 0xfb
+    op!("set", [Bit(7), H], 2, 2, "----", Next),     // 0xfc
+    op!("set", [Bit(7), L], 2, 2, "----", Next),     // 0xfd
+    op!("set", [Bit(7), IndHl], 2, 4, "----", Next), // 0xfe
+    op!("set", [Bit(7), A], 2, 2, "----", Next),     // 0xff
+];
@@ -34,6 +34,8 @@ enum PpuMode {
 pub struct Ppu {
     mode: PpuMode,
     dot: u32,
+    /// Dots since the start of the current line.
+    line_dot: u32,
     background: Buffers,
     screen: Buffers,
     tiles: Buffers,
@@ -45,6 +47,7 @@ impl Default for Ppu {
         Self {
             mode: PpuMode::default(),
             dot: 0,
+            line_dot: 0,
             background: Buffers::background(),
             screen: Buffers::screen(),
             tiles: Buffers::tiles(),
@@ -294,6 +297,10 @@ impl Ppu {
         (&self.objects.view).into()
     }
 
+    pub fn line_dot(&self) -> u32 {
+        self.line_dot
+    }
+
     pub fn tick(&mut self, mem: &mut Memory) {
         let mut regs = Registers::read(mem);
 
@@ -309,6 +316,7 @@ impl Ppu {
         if !regs.lcdc.contains(LcdControl::LcdPpuEnable) {
             // PPU is disabled. Reset.
             self.dot = 0;
+            self.line_dot = 0;
             self.mode = PpuMode::Mode2;
 
             regs.ly = 0;
@@ -316,12 +324,14 @@ impl Ppu {
         }
 
         self.dot += 1;
+        self.line_dot += 1;
 
         match self.mode {
             PpuMode::Mode0 => {
                 if self.dot > MODE_0_DOTS {
                     regs.ly += 1;
                     self.dot = 0;
+                    self.line_dot = 0;
 
                     if regs.ly >= LCD_HEIGHT {
                         self.mode = PpuMode::Mode1;
@@ -341,6 +351,7 @@ impl Ppu {
                 if self.dot > MODE_1_DOTS_PER_LINE {
                     regs.ly += 1;
                     self.dot = 0;
+                    self.line_dot = 0;
 
                     if regs.ly > SCAN_LINES {
                         self.draw_tiles(vram);
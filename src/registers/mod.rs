@@ -1,9 +1,12 @@
 pub mod graphics;
 pub mod timer;
 
+use std::fmt::Display;
+
 use bitflags::bitflags;
 
 bitflags! {
+    #[derive(Clone, Copy)]
     pub struct CpuFlags: u8 {
         /// Zero Flag
         const Z = 1 << 7;
@@ -24,8 +27,23 @@ impl Default for CpuFlags {
     }
 }
 
+/// Set flags by letter, e.g. `Z-H-`.
+impl Display for CpuFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (flag, c) in [
+            (Self::Z, 'Z'),
+            (Self::N, 'N'),
+            (Self::H, 'H'),
+            (Self::C, 'C'),
+        ] {
+            write!(f, "{}", if self.contains(flag) { c } else { '-' })?;
+        }
+        Ok(())
+    }
+}
+
 bitflags! {
-    #[derive(Clone, Copy, Debug)]
+    #[derive(Clone, Copy, Debug, PartialEq)]
     pub struct Interrupt: u8 {
         const Joypad = 1 << 4;
         const Serial = 1 << 3;
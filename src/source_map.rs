@@ -0,0 +1,270 @@
+//! Line info mapping ROM addresses to lines of assembly source, for
+//! source-level debugging.
+//!
+//! Each line gives an address and the source line assembled there, with `;`
+//! comments:
+//!
+//! ```text
+//! 00:0150 src/main.asm:12
+//! 01:4000 src/player.asm:3
+//! ```
+//!
+//! Addresses are `BB:AAAA` as in symbol files, or `0xAAAA`. Source paths are
+//! relative to the line info file.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::address::{parse_hex, Address};
+use crate::symbols::{address, ParseError};
+
+/// Source file and its lines, empty if it couldn't be read.
+#[derive(Clone)]
+pub struct SourceFile {
+    /// Path as given in the line info.
+    pub path: String,
+    /// Path to open, relative to the working directory.
+    full_path: PathBuf,
+    lines: Vec<String>,
+}
+
+impl SourceFile {
+    /// Text of a line, counting from 1.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        self.lines.get(line.checked_sub(1)?).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn full_path(&self) -> &Path {
+        &self.full_path
+    }
+
+    /// Whether `name` is this file, by its trailing path components, e.g.
+    /// `main.asm` for `src/main.asm`, or a longer path ending in it.
+    fn matches(&self, name: &str) -> bool {
+        Path::new(&self.path).ends_with(name) || Path::new(name).ends_with(&self.path)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    /// Source lines at each address, as the bank and the file and line.
+    lines: HashMap<u16, Vec<(Option<u16>, usize, usize)>>,
+    /// Mapped lines of each file, sorted, with the first address on them.
+    addresses: Vec<Vec<(usize, Address)>>,
+}
+
+impl SourceMap {
+    /// Read line info and the source files it refers to. Source files that
+    /// can't be read are logged and shown without their text.
+    pub fn load(path: &str) -> std::io::Result<Self> {
+        let s = std::fs::read_to_string(path)?;
+        let mut source_map: Self = s
+            .parse()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        for file in &mut source_map.files {
+            file.full_path = dir.join(&file.path);
+            match std::fs::read_to_string(&file.full_path) {
+                Ok(text) => file.lines = text.lines().map(str::to_owned).collect(),
+                Err(e) => log::warn!("{}: {}", file.path, e),
+            }
+        }
+        Ok(source_map)
+    }
+
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    /// File and line an address was assembled from, preferring one in its
+    /// bank.
+    pub fn location(&self, addr: Address) -> Option<(&SourceFile, usize)> {
+        let lines = self.lines.get(&addr.addr)?;
+        let in_bank = lines
+            .iter()
+            .find(|(bank, _, _)| addr.bank.is_some() && *bank == addr.bank);
+        let any_bank = || {
+            lines
+                .iter()
+                .find(|(bank, _, _)| bank.is_none() || addr.bank.is_none())
+        };
+        in_bank
+            .or_else(any_bank)
+            .map(|(_, file, line)| (&self.files[*file], *line))
+    }
+
+    /// Address of a source line, or of the next line with code on it, as a
+    /// breakpoint on a comment or blank line would stop there.
+    pub fn address(&self, name: &str, line: usize) -> Option<Address> {
+        let file = self.files.iter().position(|file| file.matches(name))?;
+        let addresses = &self.addresses[file];
+        let idx = addresses.partition_point(|(mapped, _)| *mapped < line);
+        addresses.get(idx).map(|(_, addr)| *addr)
+    }
+
+    /// Address of a `file:line` location.
+    pub fn parse_location(&self, s: &str) -> Option<Address> {
+        let (name, line) = s.trim().rsplit_once(':')?;
+        self.address(name, line.parse().ok()?)
+    }
+
+    fn insert(&mut self, addr: Address, path: &str, line: usize) {
+        let file = match self.files.iter().position(|file| file.path == path) {
+            Some(file) => file,
+            None => {
+                self.files.push(SourceFile {
+                    path: path.to_owned(),
+                    full_path: PathBuf::from(path),
+                    lines: Vec::new(),
+                });
+                self.addresses.push(Vec::new());
+                self.files.len() - 1
+            }
+        };
+        self.lines
+            .entry(addr.addr)
+            .or_default()
+            .push((addr.bank, file, line));
+        let addresses = &mut self.addresses[file];
+        let idx = addresses.partition_point(|(mapped, _)| *mapped < line);
+        // Keep the first address of lines that assemble to several.
+        if addresses.get(idx).is_none_or(|(mapped, _)| *mapped != line) {
+            addresses.insert(idx, (line, addr));
+        }
+    }
+}
+
+impl FromStr for SourceMap {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut source_map = Self::default();
+        for (idx, line) in s.lines().enumerate() {
+            let error = |message: String| ParseError {
+                line: idx + 1,
+                message,
+            };
+            let line = line.split(';').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (addr, location) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| error(format!("expected an address and file:line in {:?}", line)))?;
+            let addr = match addr.split_once(':') {
+                Some((bank, addr)) => address(
+                    parse_hex(bank).map_err(error)?,
+                    parse_hex(addr).map_err(error)?,
+                ),
+                None => Address::new(parse_hex(addr).map_err(error)?),
+            };
+            let (path, number) = location
+                .trim()
+                .rsplit_once(':')
+                .ok_or_else(|| error(format!("expected file:line in {:?}", location.trim())))?;
+            let number = number
+                .parse()
+                .map_err(|_| error(format!("invalid line number {:?}", number)))?;
+            source_map.insert(addr, path, number);
+        }
+        Ok(source_map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE_INFO: &str = "; line info\n\
+        00:0150 src/main.asm:12\n\
+        00:0151 src/main.asm:12\n\
+        00:0153 src/main.asm:15 ; after a comment line\n\
+        01:4000 src/player.asm:3\n\
+        02:4000 src/enemy.asm:7\n\
+        0xc000 src/main.asm:40\n";
+
+    #[test]
+    fn parse_line_info() {
+        let source_map: SourceMap = LINE_INFO.parse().unwrap();
+        let paths: Vec<_> = source_map.files().iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["src/main.asm", "src/player.asm", "src/enemy.asm"]);
+
+        let error = "00:0150 src/main.asm:12\n01:4000 src/player.asm\n"
+            .parse::<SourceMap>()
+            .err();
+        assert_eq!(error.map(|e| e.line), Some(2));
+        let error = "00:0150 src/main.asm:x\n".parse::<SourceMap>().err();
+        assert_eq!(
+            error,
+            Some(ParseError {
+                line: 1,
+                message: "invalid line number \"x\"".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn location_prefers_bank() {
+        let source_map: SourceMap = LINE_INFO.parse().unwrap();
+        let location = |addr| {
+            source_map
+                .location(addr)
+                .map(|(file, line)| (file.path.as_str(), line))
+        };
+        assert_eq!(
+            location(Address::banked(0, 0x151)),
+            Some(("src/main.asm", 12))
+        );
+        assert_eq!(
+            location(Address::banked(2, 0x4000)),
+            Some(("src/enemy.asm", 7))
+        );
+        assert_eq!(
+            location(Address::banked(1, 0x4000)),
+            Some(("src/player.asm", 3))
+        );
+        assert_eq!(location(Address::banked(3, 0x4000)), None);
+        // Without a bank, the first line at the address.
+        assert_eq!(location(Address::new(0x4000)), Some(("src/player.asm", 3)));
+        // Unbanked lines match any bank.
+        assert_eq!(
+            location(Address::banked(1, 0xc000)),
+            Some(("src/main.asm", 40))
+        );
+    }
+
+    #[test]
+    fn address_of_next_mapped_line() {
+        let source_map: SourceMap = LINE_INFO.parse().unwrap();
+        // The first address of a line that assembles to several.
+        assert_eq!(
+            source_map.address("src/main.asm", 12),
+            Some(Address::banked(0, 0x150))
+        );
+        // Comment and blank lines move on to the next line with code.
+        assert_eq!(
+            source_map.address("main.asm", 13),
+            Some(Address::banked(0, 0x153))
+        );
+        assert_eq!(
+            source_map.address("main.asm", 1),
+            Some(Address::banked(0, 0x150))
+        );
+        assert_eq!(source_map.address("main.asm", 41), None);
+        assert_eq!(source_map.address("other.asm", 1), None);
+        assert_eq!(
+            source_map.parse_location("player.asm:2"),
+            Some(Address::banked(1, 0x4000))
+        );
+    }
+}
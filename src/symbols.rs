@@ -1,50 +1,392 @@
+//! Symbol files, mapping label names to addresses.
+//!
+//! Supported formats, detected from the contents:
+//!
+//! - RGBDS, no$gmb and bgb `.sym` files: `BB:AAAA Label` lines with `;`
+//!   comments. Local labels are qualified by the global label before them,
+//!   e.g. `.loop` after `PlayerUpdate` becomes `PlayerUpdate.loop`.
+//! - RGBDS `.map` files, which also give the sections and their sizes.
+//! - `0xAAAA Label` lines without banks.
+
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use std::io::Read;
+use std::str::FromStr;
+
+use crate::address::{parse_hex, Address};
+
+/// no$gmb annotations marking code and data, e.g. `00:0104 .data:30`, which
+/// aren't labels.
+const ANNOTATIONS: &[&str] = &[".code:", ".data:", ".text:", ".byt:", ".wrd:"];
+
+/// Error in a symbol file, with the line number (from 1) it's on.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
 
-#[derive(Clone)]
+impl std::error::Error for ParseError {}
+
+/// Section from a `.map` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Section {
+    pub name: String,
+    /// Memory region, e.g. `ROMX` or `WRAM0`.
+    pub region: String,
+    pub start: Address,
+    pub size: u16,
+}
+
+impl Section {
+    pub fn contains(&self, addr: Address) -> bool {
+        let bank_matches = match (self.start.bank, addr.bank) {
+            (Some(bank), Some(other)) => bank == other,
+            _ => true,
+        };
+        bank_matches
+            && addr.addr >= self.start.addr
+            && u32::from(addr.addr) < u32::from(self.start.addr) + u32::from(self.size)
+    }
+}
+
+#[derive(Clone, Default)]
 pub struct Symbols {
-    address_to_symbol: HashMap<u16, String>,
+    /// Labels at each address, with the bank they are in.
+    labels: HashMap<u16, Vec<(Option<u16>, String)>>,
+    addresses: HashMap<String, Address>,
+    sections: Vec<Section>,
+}
+
+/// Address in `bank`, dropping the bank for regions that aren't banked so
+/// symbols there match any mapping, e.g. `00:ff80` in HRAM.
+pub(crate) fn address(bank: u16, addr: u16) -> Address {
+    match addr {
+        0x0000..=0x7fff | 0xa000..=0xdfff => Address::banked(bank, addr),
+        _ => Address::new(addr),
+    }
 }
 
 impl Symbols {
     pub fn get_symbol(&self, addr: u16) -> Option<String> {
-        self.address_to_symbol.get(&addr).cloned()
+        let labels = self.labels.get(&addr)?;
+        labels.first().map(|(_, name)| name.clone())
     }
-}
 
-impl TryFrom<File> for Symbols {
-    type Error = std::io::Error;
+    /// Label at the address, preferring one in its bank.
+    pub fn get_symbol_at(&self, addr: Address) -> Option<&str> {
+        let labels = self.labels.get(&addr.addr)?;
+        let in_bank = labels
+            .iter()
+            .find(|(bank, _)| addr.bank.is_some() && *bank == addr.bank);
+        let any_bank = || {
+            labels
+                .iter()
+                .find(|(bank, _)| bank.is_none() || addr.bank.is_none())
+        };
+        in_bank.or_else(any_bank).map(|(_, name)| name.as_str())
+    }
+
+    /// Addresses that have a label.
+    pub fn label_addresses(&self) -> impl Iterator<Item = Address> + '_ {
+        self.labels().map(|(addr, _)| addr)
+    }
+
+    /// Every label, with its address.
+    pub fn labels(&self) -> impl Iterator<Item = (Address, &str)> + '_ {
+        self.labels.iter().flat_map(|(addr, labels)| {
+            labels.iter().map(|(bank, name)| {
+                let addr = Address {
+                    bank: *bank,
+                    addr: *addr,
+                };
+                (addr, name.as_str())
+            })
+        })
+    }
+
+    pub fn get_address(&self, name: &str) -> Option<Address> {
+        self.addresses.get(name).copied()
+    }
+
+    /// Sections from a `.map` file.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
 
-    fn try_from(f: File) -> Result<Self, Self::Error> {
-        let mut address_to_symbol = HashMap::new();
-        let reader = BufReader::new(f);
-
-        for line in reader.lines() {
-            let line = line.unwrap();
-            let mut tokens = line.split(' ');
-            let addr = if let Some(s) = tokens.next() {
-                let without_prefix = s.trim_start_matches("0x");
-                match u16::from_str_radix(without_prefix, 16) {
-                    Ok(bp) => bp,
-                    Err(_) => {
-                        panic!("handle errors...");
+    pub fn section_at(&self, addr: Address) -> Option<&Section> {
+        self.sections.iter().find(|section| section.contains(addr))
+    }
+
+    fn insert(&mut self, addr: Address, name: String) {
+        self.addresses.entry(name.clone()).or_insert(addr);
+        self.labels
+            .entry(addr.addr)
+            .or_default()
+            .push((addr.bank, name));
+    }
+
+    fn parse_sym(s: &str) -> Result<Self, ParseError> {
+        let mut symbols = Self::default();
+        let mut scope: Option<String> = None;
+        for (idx, line) in s.lines().enumerate() {
+            let error = |message: String| ParseError {
+                line: idx + 1,
+                message,
+            };
+            let line = line.split(';').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (addr, name) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| error(format!("expected an address and a label in {:?}", line)))?;
+            let name = name.trim();
+            if ANNOTATIONS.iter().any(|prefix| name.starts_with(prefix)) {
+                continue;
+            }
+            if name.contains(char::is_whitespace) {
+                return Err(error(format!("unexpected text after label {:?}", name)));
+            }
+            let addr = match addr.split_once(':') {
+                Some((bank, addr)) => address(
+                    parse_hex(bank).map_err(error)?,
+                    parse_hex(addr).map_err(error)?,
+                ),
+                None => Address::new(parse_hex(addr).map_err(error)?),
+            };
+            let name = match (name.strip_prefix('.'), &scope) {
+                (Some(local), Some(scope)) => format!("{}.{}", scope, local),
+                _ => {
+                    if !name.starts_with('.') {
+                        let global = name.split('.').next().unwrap_or(name);
+                        scope = Some(global.to_owned());
                     }
+                    name.to_owned()
                 }
-            } else {
-                panic!("handle errors...");
             };
+            symbols.insert(addr, name);
+        }
+        Ok(symbols)
+    }
 
-            let symbol = if let Some(s) = tokens.next() {
-                s
-            } else {
-                panic!("handle errors...");
+    /// Parse a `.map` file, in the layout of RGBDS 0.5 and later:
+    ///
+    /// ```text
+    /// ROMX bank #1:
+    ///     SECTION: $4000-$41ff ($0200 bytes) ["Player"]
+    ///              $4000 = PlayerUpdate
+    /// ```
+    ///
+    /// or the one before, with `ROM Bank #1:` headers.
+    fn parse_map(s: &str) -> Result<Self, ParseError> {
+        let mut symbols = Self::default();
+        // Region and bank of the current bank header.
+        let mut bank: Option<(String, u16)> = None;
+        for (idx, line) in s.lines().enumerate() {
+            let error = |message: String| ParseError {
+                line: idx + 1,
+                message,
             };
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            if !line.starts_with(char::is_whitespace) {
+                let lower = trimmed.to_ascii_lowercase();
+                bank = match lower.find("bank #") {
+                    Some(start) => {
+                        let number: String = lower[start + 6..]
+                            .chars()
+                            .take_while(|c| c.is_ascii_digit())
+                            .collect();
+                        let number = number
+                            .parse()
+                            .map_err(|_| error(format!("invalid bank in {:?}", trimmed)))?;
+                        let region = trimmed.split_whitespace().next().unwrap_or_default();
+                        Some((region.to_owned(), number))
+                    }
+                    // SUMMARY and anything else that isn't a bank.
+                    None => None,
+                };
+                continue;
+            }
+            let Some((ref region, bank)) = bank else {
+                continue;
+            };
+            if let Some(section) = trimmed.strip_prefix("SECTION:") {
+                // `$4000-$41ff ($0200 bytes) ["Player"]`, without the end
+                // for empty sections.
+                let (range, rest) = section
+                    .trim()
+                    .split_once(' ')
+                    .ok_or_else(|| error(format!("invalid section {:?}", trimmed)))?;
+                let start = range.split('-').next().unwrap_or_default();
+                let start = parse_hex(start).map_err(error)?;
+                let size = rest
+                    .trim()
+                    .strip_prefix('(')
+                    .and_then(|rest| rest.split_whitespace().next())
+                    .ok_or_else(|| error(format!("expected the size in {:?}", trimmed)))?;
+                let size = parse_hex(size).map_err(error)?;
+                let name = rest
+                    .split_once("[\"")
+                    .and_then(|(_, name)| name.rsplit_once("\"]"))
+                    .map(|(name, _)| name)
+                    .ok_or_else(|| error(format!("expected the name in {:?}", trimmed)))?;
+                symbols.sections.push(Section {
+                    name: name.to_owned(),
+                    region: region.clone(),
+                    start: address(bank, start),
+                    size,
+                });
+            } else if trimmed.starts_with('$') {
+                let (addr, name) = trimmed
+                    .split_once('=')
+                    .ok_or_else(|| error(format!("expected $addr = label in {:?}", trimmed)))?;
+                let addr = parse_hex(addr.trim()).map_err(error)?;
+                symbols.insert(address(bank, addr), name.trim().to_owned());
+            }
+            // EMPTY, SLACK and TOTAL EMPTY lines only give free space.
+        }
+        Ok(symbols)
+    }
+}
+
+impl FromStr for Symbols {
+    type Err = ParseError;
 
-            address_to_symbol.insert(addr, symbol.to_string());
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_map = s
+            .lines()
+            .any(|line| line.trim_start().starts_with("SECTION:"));
+        if is_map {
+            Self::parse_map(s)
+        } else {
+            Self::parse_sym(s)
         }
+    }
+}
+
+impl TryFrom<File> for Symbols {
+    type Error = std::io::Error;
+
+    fn try_from(mut f: File) -> Result<Self, Self::Error> {
+        let mut s = String::new();
+        f.read_to_string(&mut s)?;
+        s.parse()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sym_labels() {
+        let symbols: Symbols = "; File created by rgblink\n\
+            00:0150 Main\n\
+            00:0158 .loop ; comment\n\
+            01:4000 PlayerUpdate\n\
+            01:4010 .done\n\
+            00:ff80 hTimer\n\
+            00:0104 .data:30\n"
+            .parse()
+            .unwrap();
+        assert_eq!(symbols.get_address("Main"), Some(Address::banked(0, 0x150)));
+        assert_eq!(
+            symbols.get_address("Main.loop"),
+            Some(Address::banked(0, 0x158))
+        );
+        assert_eq!(
+            symbols.get_address("PlayerUpdate.done"),
+            Some(Address::banked(1, 0x4010))
+        );
+        assert_eq!(symbols.get_address("hTimer"), Some(Address::new(0xff80)));
+        assert_eq!(
+            symbols.get_symbol_at(Address::banked(1, 0x4000)),
+            Some("PlayerUpdate")
+        );
+        assert_eq!(symbols.get_symbol_at(Address::banked(2, 0x4000)), None);
+        assert_eq!(symbols.get_symbol(0x0104), None);
+    }
+
+    #[test]
+    fn bankless_labels() {
+        let symbols: Symbols = "0x0150 Main\nc0a0 wCounter\n".parse().unwrap();
+        assert_eq!(symbols.get_address("Main"), Some(Address::new(0x150)));
+        assert_eq!(symbols.get_address("wCounter"), Some(Address::new(0xc0a0)));
+        assert_eq!(
+            symbols.get_symbol_at(Address::banked(3, 0xc0a0)),
+            Some("wCounter")
+        );
+    }
+
+    #[test]
+    fn map_sections() {
+        let symbols: Symbols = "SUMMARY:\n\
+            \tROM0: 336 bytes used / 16048 free\n\
+            \n\
+            ROMX bank #1:\n\
+            \tSECTION: $4000-$41ff ($0200 bytes) [\"Player\"]\n\
+            \t         $4000 = PlayerUpdate\n\
+            \t         $4010 = PlayerUpdate.done\n\
+            \tEMPTY: $4200-$7fff ($3e00 bytes)\n\
+            \n\
+            HRAM bank #0:\n\
+            \tSECTION: $ff80-$ff80 ($0001 byte) [\"Timer\"]\n\
+            \t         $ff80 = hTimer\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            symbols.sections(),
+            [
+                Section {
+                    name: "Player".to_owned(),
+                    region: "ROMX".to_owned(),
+                    start: Address::banked(1, 0x4000),
+                    size: 0x200,
+                },
+                Section {
+                    name: "Timer".to_owned(),
+                    region: "HRAM".to_owned(),
+                    start: Address::new(0xff80),
+                    size: 1,
+                },
+            ]
+        );
+        let section = symbols.section_at(Address::banked(1, 0x41ff)).unwrap();
+        assert_eq!(section.name, "Player");
+        assert!(symbols.section_at(Address::banked(1, 0x4200)).is_none());
+        assert!(symbols.section_at(Address::banked(2, 0x4000)).is_none());
+        assert_eq!(
+            symbols.get_address("PlayerUpdate.done"),
+            Some(Address::banked(1, 0x4010))
+        );
+        assert_eq!(symbols.get_address("hTimer"), Some(Address::new(0xff80)));
+    }
 
-        Ok(Self { address_to_symbol })
+    #[test]
+    fn malformed_lines() {
+        let error = "00:0150 Main\n\n00:zzzz Bad\n".parse::<Symbols>().err();
+        assert_eq!(error.map(|e| e.line), Some(3));
+        let error = "00:0150 Main\nMain\n".parse::<Symbols>().err();
+        assert_eq!(error.map(|e| e.line), Some(2));
+        let error = "00:0150 Main extra\n".parse::<Symbols>().err();
+        assert_eq!(
+            error,
+            Some(ParseError {
+                line: 1,
+                message: "unexpected text after label \"Main extra\"".to_owned(),
+            })
+        );
     }
 }
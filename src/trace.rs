@@ -0,0 +1,75 @@
+//! Instruction trace in the format used by Gameboy Doctor
+//! (https://github.com/robert/gameboy-doctor), one line per instruction:
+//!
+//! `A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02`
+//!
+//! Like the reference logs, it starts at 0x0100 once the boot ROM is done.
+//! They are also made with LY always reading 0x90, which a trace can stub
+//! as well, at the cost of changing how the game runs.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::cpu::Cpu;
+use crate::memory::ProgramMemory;
+use crate::memory_map::MemoryMap;
+
+pub struct Trace {
+    path: String,
+    out: BufWriter<File>,
+    stub_ly: bool,
+}
+
+impl Trace {
+    pub fn create(path: &str) -> Result<Self, std::io::Error> {
+        let file = File::create(path)?;
+        Ok(Self {
+            path: path.to_owned(),
+            out: BufWriter::new(file),
+            stub_ly: false,
+        })
+    }
+
+    /// Have the CPU read LY as 0x90 while tracing.
+    pub fn with_stub_ly(mut self, stub_ly: bool) -> Self {
+        self.stub_ly = stub_ly;
+        self
+    }
+
+    pub fn stubs_ly(&self) -> bool {
+        self.stub_ly
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Log the state the CPU is in before executing the instruction at PC.
+    pub fn log(&mut self, cpu: &Cpu, mem: &impl ProgramMemory) {
+        if mem.get_u8(MemoryMap::BootRomDisable) == 0 {
+            return;
+        }
+        let pcmem = |offset: u16| mem.get_u8(cpu.pc.wrapping_add(offset));
+        let result = writeln!(
+            self.out,
+            "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+            cpu.a,
+            cpu.f.bits(),
+            cpu.b,
+            cpu.c,
+            cpu.d,
+            cpu.e,
+            cpu.h,
+            cpu.l,
+            cpu.sp,
+            cpu.pc,
+            pcmem(0),
+            pcmem(1),
+            pcmem(2),
+            pcmem(3),
+        );
+        if let Err(e) = result {
+            log::error!("failed to write trace to {}: {}", self.path, e);
+        }
+    }
+}
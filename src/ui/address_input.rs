@@ -3,11 +3,16 @@ use std::time::Duration;
 use egui::*;
 use egui_notify::Toasts;
 
+use crate::address::Address;
+use crate::source_map::SourceMap;
+use crate::symbols::Symbols;
 use crate::ui::*;
 
 pub struct AddressInput<'a> {
     toasts: &'a mut Toasts,
     edit_string: &'a mut String,
+    symbols: Option<&'a Symbols>,
+    source_map: Option<&'a SourceMap>,
 }
 
 impl<'a> AddressInput<'a> {
@@ -15,20 +20,60 @@ impl<'a> AddressInput<'a> {
         Self {
             toasts,
             edit_string,
+            symbols: None,
+            source_map: None,
         }
     }
 
-    pub fn ui(&mut self, ui: &mut Ui) -> Option<u16> {
+    /// Also accept label names.
+    pub fn with_symbols(mut self, symbols: Option<&'a Symbols>) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Also accept `file:line` source locations.
+    pub fn with_source_map(mut self, source_map: Option<&'a SourceMap>) -> Self {
+        self.source_map = source_map;
+        self
+    }
+
+    fn parse(&self) -> Result<Address, String> {
+        let label = self
+            .symbols
+            .and_then(|symbols| symbols.get_address(self.edit_string.trim()));
+        if let Some(addr) = label {
+            return Ok(addr);
+        }
+        let addr = self.edit_string.parse();
+        let location = || {
+            self.source_map
+                .and_then(|source_map| source_map.parse_location(self.edit_string))
+        };
+        match location() {
+            Some(location) if addr.is_err() => Ok(location),
+            _ => addr,
+        }
+    }
+
+    /// Accepts `addr` or `bank:addr`, in hex, a label or `file:line`.
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<Address> {
         ui.horizontal(|ui| {
-            ui.label(monospace("0x"));
-            let tb_response = ui.add(TextEdit::singleline(self.edit_string).desired_width(30.));
+            let (hint, width) = match (self.symbols, self.source_map) {
+                (_, Some(_)) => ("[bank:]addr, label or file:line", 180.),
+                (Some(_), None) => ("[bank:]addr or label", 120.),
+                (None, None) => ("[bank:]addr", 60.),
+            };
+            let tb_response = ui.add(
+                TextEdit::singleline(self.edit_string)
+                    .hint_text(hint)
+                    .desired_width(width),
+            );
             let tb_submit =
                 tb_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
             let button_response = ui.button(monospace("⮩"));
             let button_submit = button_response.clicked();
             if tb_submit || button_submit {
-                let without_prefix = self.edit_string.trim_start_matches("0x");
-                match u16::from_str_radix(without_prefix, 16) {
+                match self.parse() {
                     Ok(address) => {
                         return Some(address);
                     }
@@ -1,48 +1,165 @@
+use std::time::Duration;
+
 use egui::*;
 use egui_notify::Toasts;
 
-use crate::debugger::{Command, Debugger};
+use crate::address::Address;
+use crate::debugger::{Breakpoint, Command, Debugger};
+use crate::source_map::SourceMap;
+use crate::symbols::Symbols;
 use crate::ui::*;
 
+pub struct BreakpointsState {
+    addr: String,
+    condition: String,
+    hit_count: String,
+    log: String,
+}
+
+impl Default for BreakpointsState {
+    fn default() -> Self {
+        Self {
+            addr: "c000".to_owned(),
+            condition: String::default(),
+            hit_count: String::default(),
+            log: String::default(),
+        }
+    }
+}
+
 pub struct Breakpoints<'a> {
     debugger: &'a mut Option<Debugger>,
     toasts: &'a mut Toasts,
-    bp_string: &'a mut String,
+    state: &'a mut BreakpointsState,
+    symbols: Option<&'a Symbols>,
+    source_map: Option<&'a SourceMap>,
 }
 
 impl<'a> Breakpoints<'a> {
     pub fn new(
         debugger: &'a mut Option<Debugger>,
         toasts: &'a mut Toasts,
-        bp_string: &'a mut String,
+        state: &'a mut BreakpointsState,
+        symbols: Option<&'a Symbols>,
     ) -> Self {
         Self {
             debugger,
             toasts,
-            bp_string,
+            state,
+            symbols,
+            source_map: None,
+        }
+    }
+
+    /// Accept `file:line` breakpoints and show the lines of breakpoints.
+    pub fn with_source_map(mut self, source_map: Option<&'a SourceMap>) -> Self {
+        self.source_map = source_map;
+        self
+    }
+
+    fn breakpoint(&self, addr: Address) -> Result<Breakpoint, String> {
+        let mut breakpoint = Breakpoint::new(addr);
+        if !self.state.condition.trim().is_empty() {
+            breakpoint = breakpoint.with_condition(&self.state.condition)?;
         }
+        if !self.state.hit_count.trim().is_empty() {
+            let hit_count = self
+                .state
+                .hit_count
+                .trim()
+                .parse()
+                .map_err(|e| format!("hit count: {}", e))?;
+            breakpoint = breakpoint.with_hit_count(hit_count);
+        }
+        if !self.state.log.trim().is_empty() {
+            breakpoint = breakpoint.with_log(&self.state.log)?;
+        }
+        Ok(breakpoint)
     }
 
     pub fn ui(&mut self, ui: &mut Ui) {
         ui.label(title(ui, "breakpoints"));
-        if let Some(debugger) = self.debugger.as_mut() {
-            ui.horizontal(|ui| {
-                let mut input = AddressInput::new(self.toasts, self.bp_string);
-                if let Some(addr) = input.ui(ui) {
-                    debugger.command(Command::InsertBreakpoint(addr));
+        if self.debugger.is_none() {
+            return;
+        }
+
+        let addr = ui
+            .horizontal(|ui| {
+                AddressInput::new(self.toasts, &mut self.state.addr)
+                    .with_symbols(self.symbols)
+                    .with_source_map(self.source_map)
+                    .ui(ui)
+            })
+            .inner;
+        ui.horizontal(|ui| {
+            ui.label(monospace("if"));
+            ui.add(
+                TextEdit::singleline(&mut self.state.condition)
+                    .hint_text("a == 0x3f && ly > 100")
+                    .desired_width(150.),
+            );
+            ui.label(monospace("hit"));
+            ui.add(
+                TextEdit::singleline(&mut self.state.hit_count)
+                    .hint_text("1")
+                    .desired_width(30.),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label(monospace("log"));
+            ui.add(
+                TextEdit::singleline(&mut self.state.log)
+                    .hint_text("x={b} type={[c0a3]}")
+                    .desired_width(220.),
+            );
+        });
+        if let Some(addr) = addr {
+            match self.breakpoint(addr) {
+                Ok(breakpoint) => {
+                    if let Some(debugger) = self.debugger.as_mut() {
+                        debugger.command(Command::InsertBreakpoint(breakpoint));
+                    }
+                }
+                Err(e) => {
+                    self.toasts
+                        .error(format!("Error: {}", e))
+                        .set_duration(Some(Duration::from_secs(5)));
                 }
-            });
+            }
+        }
+
+        if let Some(debugger) = self.debugger.as_mut() {
             egui::ScrollArea::vertical()
                 .id_source("breakpoints")
                 .show(ui, |ui| {
-                    for bp in debugger.get_breakpoints().iter() {
+                    let mut commands = Vec::new();
+                    for bp in debugger.get_breakpoints() {
                         ui.horizontal(|ui| {
                             if ui.button(monospace("➖")).clicked() {
-                                debugger.command(Command::RemoveBreakpoint(*bp));
+                                commands.push(Command::RemoveBreakpoint(bp.addr));
+                            }
+                            let mut enabled = bp.enabled;
+                            if ui.checkbox(&mut enabled, "").changed() {
+                                commands.push(Command::EnableBreakpoint(bp.addr, enabled));
+                            }
+                            let label = self
+                                .symbols
+                                .and_then(|symbols| symbols.get_symbol_at(bp.addr));
+                            let response = match label {
+                                Some(label) => ui.label(monospace(format!("{} {}", bp, label))),
+                                None => ui.label(monospace(format!("{}", bp))),
+                            };
+                            let location = self
+                                .source_map
+                                .and_then(|source_map| source_map.location(bp.addr));
+                            if let Some((file, line)) = location {
+                                response.on_hover_text(format!("{}:{}", file.path, line));
                             }
-                            ui.label(monospace(format!("0x{:04x}", bp)));
                         });
                     }
+                    for command in commands {
+                        debugger.command(command);
+                    }
                 });
         }
     }
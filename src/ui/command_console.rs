@@ -0,0 +1,138 @@
+use egui::*;
+
+use crate::console::Console;
+use crate::gameboy::Gameboy;
+use crate::loader::Loader;
+use crate::source_map::SourceMap;
+use crate::symbols::Symbols;
+use crate::ui::serial_console::keep_last_lines;
+use crate::ui::*;
+
+const LINES: usize = 200;
+
+#[derive(Default)]
+pub struct CommandConsoleState {
+    console: Console,
+    input: String,
+    output: String,
+    history: Vec<String>,
+    /// Position while browsing the history with the arrow keys.
+    history_pos: Option<usize>,
+}
+
+impl CommandConsoleState {
+    pub fn new(symbols: Option<Symbols>, source_map: Option<SourceMap>) -> Self {
+        Self {
+            console: Console::new(symbols).with_source_map(source_map),
+            ..Default::default()
+        }
+    }
+
+    fn print(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+        self.output.push_str(text);
+    }
+}
+
+/// Debugger commands typed at a prompt, see [`Console`].
+pub struct CommandConsole<'a> {
+    gameboy: &'a mut Gameboy,
+    loader: &'a Loader,
+    state: &'a mut CommandConsoleState,
+}
+
+impl<'a> CommandConsole<'a> {
+    pub fn new(
+        gameboy: &'a mut Gameboy,
+        loader: &'a Loader,
+        state: &'a mut CommandConsoleState,
+    ) -> Self {
+        Self {
+            gameboy,
+            loader,
+            state,
+        }
+    }
+
+    fn browse_history(&mut self, ui: &Ui) {
+        let history = &self.state.history;
+        let pos = if ui.input(|i| i.key_pressed(Key::ArrowUp)) {
+            match self.state.history_pos {
+                Some(pos) => Some(pos.saturating_sub(1)),
+                None => history.len().checked_sub(1),
+            }
+        } else if ui.input(|i| i.key_pressed(Key::ArrowDown)) {
+            self.state
+                .history_pos
+                .map(|pos| pos + 1)
+                .filter(|pos| *pos < history.len())
+        } else {
+            return;
+        };
+        self.state.input = pos.map(|pos| history[pos].clone()).unwrap_or_default();
+        self.state.history_pos = pos;
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        if let Some(stop) = self.state.console.poll(self.gameboy) {
+            self.state.print(&stop);
+        }
+        keep_last_lines(&mut self.state.output, LINES);
+
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.label(title(ui, "console"));
+                ui.with_layout(egui::Layout::right_to_left(Align::Center), |ui| {
+                    if ui.button(monospace("🗋")).clicked() {
+                        self.state.output.clear()
+                    }
+                });
+            });
+
+            let input_height = ui.spacing().interact_size.y;
+            let size = vec2(ui.available_width(), ui.available_height() - input_height);
+            ui.allocate_ui(size, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_source("command_console")
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        ui.add_sized(
+                            ui.available_size(),
+                            TextEdit::multiline(&mut self.state.output.as_str())
+                                .code_editor()
+                                .vertical_align(Align::Max),
+                        );
+                    });
+            });
+
+            let response = ui.add(
+                TextEdit::singleline(&mut self.state.input)
+                    .code_editor()
+                    .hint_text("help")
+                    .desired_width(f32::INFINITY),
+            );
+            if response.has_focus() {
+                self.browse_history(ui);
+            }
+            if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                let line = std::mem::take(&mut self.state.input);
+                self.state.print(&format!("> {}", line));
+                let result = self.state.console.execute(&line, self.gameboy, self.loader);
+                match result {
+                    Ok(output) => self.state.print(&output),
+                    Err(e) => self.state.print(&format!("Error: {}", e)),
+                }
+                if !line.trim().is_empty() && self.state.history.last() != Some(&line) {
+                    self.state.history.push(line);
+                }
+                self.state.history_pos = None;
+                response.request_focus();
+            }
+        });
+    }
+}
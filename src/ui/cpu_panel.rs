@@ -1,26 +1,101 @@
+use std::time::Duration;
+
 use egui::*;
+use egui_notify::Toasts;
 
 use crate::cpu::Cpu;
 use crate::registers::CpuFlags;
+use crate::trace::Trace;
 use crate::ui::*;
 
 pub struct CpuPanel<'a> {
-    cpu: &'a Cpu,
+    cpu: &'a mut Cpu,
+    trace_path: &'a mut String,
+    toasts: &'a mut Toasts,
+    /// Whether registers can be edited, i.e. the debugger is paused.
+    editable: bool,
 }
 
 impl<'a> CpuPanel<'a> {
-    pub fn new(cpu: &'a Cpu) -> Self {
-        Self { cpu }
+    pub fn new(
+        cpu: &'a mut Cpu,
+        trace_path: &'a mut String,
+        toasts: &'a mut Toasts,
+        editable: bool,
+    ) -> Self {
+        Self {
+            cpu,
+            trace_path,
+            toasts,
+            editable,
+        }
+    }
+
+    fn register(&mut self, ui: &mut Ui, label: &str, get: fn(&Cpu) -> u16, set: fn(&mut Cpu, u16)) {
+        // Changing registers halfway through an instruction would confuse it.
+        let editable = self.editable && self.cpu.m_cycle == 0;
+        ui.horizontal(|ui| {
+            ui.label(monospace(format!("{}:", label)));
+            let mut value = get(self.cpu);
+            if HexEdit::new(&mut value, 4, editable).ui(ui) {
+                set(self.cpu, value);
+            }
+        });
+    }
+
+    fn flag(&mut self, ui: &mut Ui, flag: CpuFlags, label: &str) {
+        let clicked = Indicator::new(self.cpu.f.contains(flag), label)
+            .ui(ui)
+            .clicked();
+        if clicked && self.editable && self.cpu.m_cycle == 0 {
+            self.cpu.f.toggle(flag);
+        }
     }
 
     pub fn ui(&mut self, ui: &mut Ui) {
         ui.label(title(ui, "cpu"));
-        ui.label(monospace(format!("{}", self.cpu)));
+        // The low nibble of F always reads as zero.
+        self.register(ui, "af", Cpu::get_af, |cpu, af| cpu.set_af(af & 0xfff0));
+        self.register(ui, "bc", Cpu::get_bc, Cpu::set_bc);
+        self.register(ui, "de", Cpu::get_de, Cpu::set_de);
+        self.register(ui, "hl", Cpu::get_hl, Cpu::set_hl);
+        self.register(ui, "sp", Cpu::get_sp, Cpu::set_sp);
+        self.register(ui, "pc", |cpu| cpu.pc, |cpu, pc| cpu.pc = pc);
+        ui.label(monospace(format!("state: {:?}", self.cpu.state)));
+
+        self.flag(ui, CpuFlags::Z, "Z");
+        self.flag(ui, CpuFlags::N, "N");
+        self.flag(ui, CpuFlags::H, "H");
+        self.flag(ui, CpuFlags::C, "C");
+        let clicked = Indicator::new(self.cpu.ie, "IME").ui(ui).clicked();
+        if clicked && self.editable && self.cpu.m_cycle == 0 {
+            self.cpu.ie = !self.cpu.ie;
+        }
 
-        Indicator::new(self.cpu.f.contains(CpuFlags::Z), "Z").ui(ui);
-        Indicator::new(self.cpu.f.contains(CpuFlags::N), "N").ui(ui);
-        Indicator::new(self.cpu.f.contains(CpuFlags::H), "H").ui(ui);
-        Indicator::new(self.cpu.f.contains(CpuFlags::C), "C").ui(ui);
-        Indicator::new(self.cpu.ie, "IME").ui(ui);
+        ui.horizontal(|ui| {
+            let mut tracing = self.cpu.trace.is_some();
+            let checkbox = ui.checkbox(&mut tracing, monospace("trace")).on_hover_text(
+                "Gameboy Doctor trace, from 0x0100. LY reads as usual, start with \
+                 --trace-doctor to stub it as the reference logs do",
+            );
+            if checkbox.changed() {
+                if tracing {
+                    match Trace::create(self.trace_path) {
+                        Ok(trace) => self.cpu.trace = Some(trace),
+                        Err(e) => {
+                            self.toasts
+                                .error(format!("Could not open {}: {}", self.trace_path, e))
+                                .set_duration(Some(Duration::from_secs(5)));
+                        }
+                    }
+                } else {
+                    self.cpu.trace = None;
+                }
+            }
+            ui.add_enabled(
+                !tracing,
+                TextEdit::singleline(self.trace_path).font(TextStyle::Monospace),
+            );
+        });
     }
 }
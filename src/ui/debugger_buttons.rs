@@ -3,18 +3,25 @@ use egui::*;
 use crate::debugger::{Command, Debugger};
 use crate::ui::*;
 
-macro_rules! button {
-    ($ui:ident, $dbg:ident, $cmd:expr, $label:literal, $tf:literal) => {
-        if $ui
-            .add_enabled(
-                $dbg.is_running() == $tf,
-                egui::Button::new(monospace($label)),
-            )
-            .clicked()
-        {
-            $dbg.command($cmd);
-        }
-    };
+const fn key(key: Key) -> KeyboardShortcut {
+    KeyboardShortcut::new(Modifiers::NONE, key)
+}
+
+const STEP_OUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::SHIFT, Key::F11);
+
+/// Show a button, returning whether it was clicked or its shortcut pressed.
+fn button(ui: &mut Ui, enabled: bool, label: &str, hint: &str, shortcut: KeyboardShortcut) -> bool {
+    // Exact modifiers so F11 doesn't also fire on Shift+F11.
+    let pressed = enabled
+        && ui.input_mut(|i| {
+            i.modifiers.matches_exact(shortcut.modifiers) && i.consume_shortcut(&shortcut)
+        });
+    let hint = format!("{} ({})", hint, ui.ctx().format_shortcut(&shortcut));
+    let clicked = ui
+        .add_enabled(enabled, Button::new(monospace(label)))
+        .on_hover_text(hint)
+        .clicked();
+    clicked || pressed
 }
 
 pub struct DebuggerButtons<'a> {
@@ -30,11 +37,34 @@ impl<'a> DebuggerButtons<'a> {
         ui.horizontal(|ui| {
             ui.label(title(ui, "debugger"));
             if let Some(debugger) = self.debugger.as_mut() {
-                button!(ui, debugger, Command::Pause, "⏸", true);
-                button!(ui, debugger, Command::Continue, "▶", false);
-                button!(ui, debugger, Command::Step, "⏭", false);
-                button!(ui, debugger, Command::StepIn, "⮫", false);
-                button!(ui, debugger, Command::StepOut, "⮩", false);
+                let running = debugger.is_running();
+                let buttons = [
+                    (running, "⏸", "pause", key(Key::F5), Command::Pause),
+                    (!running, "▶", "continue", key(Key::F5), Command::Continue),
+                    (!running, "⏭", "step", key(Key::F11), Command::Step),
+                    (!running, "⤼", "step over", key(Key::F10), Command::StepOver),
+                    (!running, "⮫", "next call", key(Key::F9), Command::StepIn),
+                    (!running, "⮩", "step out", STEP_OUT, Command::StepOut),
+                    (
+                        !running,
+                        "🖵",
+                        "next frame",
+                        key(Key::F7),
+                        Command::RunToFrame,
+                    ),
+                    (
+                        !running,
+                        "⚡",
+                        "next interrupt",
+                        key(Key::F8),
+                        Command::RunToInterrupt,
+                    ),
+                ];
+                for (enabled, label, hint, shortcut, command) in buttons {
+                    if button(ui, enabled, label, hint, shortcut) {
+                        debugger.command(command);
+                    }
+                }
             }
         });
     }
@@ -1,10 +1,16 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::mem;
+use std::time::Duration;
 
 use egui::*;
 use egui_notify::Toasts;
 
-use crate::dasm::Disassembly;
+use crate::address::Address;
+use crate::dasm::{assemble, export_rgbds, Analysis, Disassembly, Kind};
+use crate::debugger::{Breakpoint, Command, Debugger, RomUse};
 use crate::memory::Memory;
+use crate::source_map::{SourceFile, SourceMap};
 use crate::symbols::Symbols;
 use crate::ui::*;
 
@@ -18,33 +24,67 @@ pub enum DisasmPanelSetting {
 #[derive(Default)]
 pub struct DisasmPanelState {
     setting: DisasmPanelSetting,
-    address: u16,
+    address: Address,
     edit_string: String,
     symbols: Option<Symbols>,
+    /// File to export RGBDS source to.
+    export_path: String,
+    /// Instruction to assemble over the one right-clicked.
+    patch: String,
+    /// Whether ROM has been patched, and the file to export it to.
+    patched: bool,
+    rom_path: String,
+    /// Whether rows are coloured by coverage, and the file to write the
+    /// coverage report to.
+    heatmap: bool,
+    coverage_path: String,
 }
 
 impl DisasmPanelState {
     pub fn new(symbols: Option<Symbols>) -> Self {
         Self {
             symbols,
+            export_path: "disassembly.asm".to_owned(),
+            rom_path: "patched.gb".to_owned(),
+            coverage_path: "coverage.txt".to_owned(),
             ..Default::default()
         }
     }
+
+    /// Show the disassembly at `addr`.
+    pub fn go_to(&mut self, addr: Address) {
+        self.address = addr;
+        self.setting = DisasmPanelSetting::Manual;
+    }
+
+    /// Address shown. Without a bank, it follows whichever bank is mapped.
+    pub fn address(&self, pc: u16, mem: &Memory) -> Address {
+        match self.setting {
+            DisasmPanelSetting::PC => Address::current(pc, mem),
+            DisasmPanelSetting::Manual if self.address.bank.is_none() => {
+                Address::current(self.address.addr, mem)
+            }
+            DisasmPanelSetting::Manual => self.address,
+        }
+    }
 }
 
 pub struct DisasmPanel<'a> {
     disassembly: &'a mut Option<Disassembly>,
-    mem: &'a Memory,
+    mem: &'a mut Memory,
     pc: u16,
+    debugger: &'a mut Option<Debugger>,
     state: &'a mut DisasmPanelState,
     toasts: &'a mut Toasts,
+    source_map: Option<&'a SourceMap>,
 }
 
 impl<'a> DisasmPanel<'a> {
     pub fn new(
         disassembly: &'a mut Option<Disassembly>,
-        mem: &'a Memory,
+        mem: &'a mut Memory,
         pc: u16,
+        debugger: &'a mut Option<Debugger>,
         state: &'a mut DisasmPanelState,
         toasts: &'a mut Toasts,
     ) -> Self {
@@ -52,69 +92,398 @@ impl<'a> DisasmPanel<'a> {
             disassembly,
             mem,
             pc,
+            debugger,
             state,
             toasts,
+            source_map: None,
+        }
+    }
+
+    /// Show the source lines next to the disassembly.
+    pub fn with_source_map(mut self, source_map: Option<&'a SourceMap>) -> Self {
+        self.source_map = source_map;
+        self
+    }
+
+    /// Source around the line `addr` was assembled from, with the line
+    /// highlighted.
+    fn source(&mut self, file: &SourceFile, current: usize, ui: &mut Ui) {
+        let Some(source_map) = self.source_map else {
+            return;
+        };
+        ui.label(monospace(format!("{}:{}", file.path, current)));
+        let start = current.saturating_sub(5).max(1);
+        for line in start..start + 15 {
+            let Some(text) = file.line(line) else {
+                ui.label("");
+                continue;
+            };
+            let addr = source_map.address(&file.path, line);
+            // Only lines with code have an address of their own.
+            let addr = addr.filter(|addr| {
+                source_map
+                    .location(*addr)
+                    .is_some_and(|(_, mapped)| mapped == line)
+            });
+            let breakpoint = addr.is_some_and(|addr| {
+                self.debugger
+                    .as_ref()
+                    .is_some_and(|debugger| debugger.get_breakpoints().any(|bp| bp.addr == addr))
+            });
+            let marker = if breakpoint { "●" } else { " " };
+            let mut rt = monospace(format!("{}{:>4} {}", marker, line, text));
+            if line == current {
+                rt = rt.color(highlight(ui));
+            }
+            let response = ui.add(Label::new(rt).sense(Sense::click()));
+            if let (Some(addr), Some(debugger)) = (addr, self.debugger.as_mut()) {
+                response.context_menu(|ui| {
+                    let toggle = if breakpoint {
+                        "remove breakpoint"
+                    } else {
+                        "add breakpoint"
+                    };
+                    if ui.button(toggle).clicked() {
+                        if breakpoint {
+                            debugger.command(Command::RemoveBreakpoint(addr));
+                        } else {
+                            debugger.command(Command::InsertBreakpoint(Breakpoint::new(addr)));
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("run to here").clicked() {
+                        debugger.command(Command::RunTo(addr));
+                        ui.close_menu();
+                    }
+                });
+            }
+        }
+    }
+
+    /// Write the ROM as RGBDS source, with the analysis shown.
+    fn export(&mut self) {
+        let Some(analysis) = self.disassembly.as_ref().and_then(Disassembly::analysis) else {
+            return;
+        };
+        let path = &self.state.export_path;
+        let result = File::create(path).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            export_rgbds(self.mem, analysis, self.state.symbols.as_ref(), &mut out)?;
+            out.flush()
+        });
+        match result {
+            Ok(()) => {
+                self.toasts
+                    .info(format!("Exported {}", path))
+                    .set_duration(Some(Duration::from_secs(5)));
+            }
+            Err(e) => {
+                self.toasts
+                    .error(format!("Could not write {}: {}", path, e))
+                    .set_duration(Some(Duration::from_secs(5)));
+            }
+        }
+    }
+
+    /// Assemble the patch over the instruction at `addr`, padding the rest
+    /// of it with `nop` so the code after it stays where it was. Patches
+    /// longer than the instruction are refused.
+    fn patch(&mut self, addr: Address, len: u16) {
+        if addr.addr < 0x100 && self.mem.boot_rom_mapped() {
+            self.toasts
+                .error("Error: can't patch the boot ROM")
+                .set_duration(Some(Duration::from_secs(5)));
+            return;
+        }
+        let mut bytes = match assemble(&self.state.patch, addr.addr, self.state.symbols.as_ref()) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.toasts
+                    .error(format!("Error: {}", e))
+                    .set_duration(Some(Duration::from_secs(5)));
+                return;
+            }
+        };
+        // Longer code would overwrite the instructions after it.
+        if bytes.len() > len.into() {
+            self.toasts
+                .error(format!(
+                    "Error: the patch takes {} bytes, more than the {} of the instruction",
+                    bytes.len(),
+                    len
+                ))
+                .set_duration(Some(Duration::from_secs(5)));
+            return;
+        }
+        bytes.resize(len.into(), 0x00);
+        for (offset, byte) in bytes.into_iter().enumerate() {
+            let addr = Address {
+                bank: addr.bank,
+                addr: addr.addr.wrapping_add(offset as u16),
+            };
+            self.mem.debug_set_u8(addr, byte);
+        }
+        self.state.patched |= addr.addr < 0x8000;
+        self.state.patch.clear();
+        // Decode and analyse the new code.
+        *self.disassembly = None;
+    }
+
+    /// Write the ROM with the patches made to it.
+    fn export_rom(&mut self) {
+        let path = &self.state.rom_path;
+        match std::fs::write(path, self.mem.rom()) {
+            Ok(()) => {
+                self.toasts
+                    .info(format!("Exported {}", path))
+                    .set_duration(Some(Duration::from_secs(5)));
+            }
+            Err(e) => {
+                self.toasts
+                    .error(format!("Could not write {}: {}", path, e))
+                    .set_duration(Some(Duration::from_secs(5)));
+            }
+        }
+    }
+
+    /// Write the code coverage report, analysing again from the code run
+    /// so far.
+    fn export_coverage(&mut self) {
+        let Some(debugger) = self.debugger.as_ref() else {
+            return;
+        };
+        let analysis = Analysis::new(self.mem, debugger.get_executed().iter().copied());
+        let path = &self.state.coverage_path;
+        let result = File::create(path).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            debugger.coverage().report(
+                self.mem,
+                &analysis,
+                self.state.symbols.as_ref(),
+                self.source_map,
+                &mut out,
+            )?;
+            out.flush()
+        });
+        match result {
+            Ok(()) => {
+                self.toasts
+                    .info(format!("Exported {}", path))
+                    .set_duration(Some(Duration::from_secs(5)));
+            }
+            Err(e) => {
+                self.toasts
+                    .error(format!("Could not write {}: {}", path, e))
+                    .set_duration(Some(Duration::from_secs(5)));
+            }
+        }
+    }
+
+    /// Heatmap background for a row: green if it ran, red if it's code that
+    /// never has, and otherwise how its bytes were read or written.
+    fn heat(&self, row: Address, len: u16) -> Option<Color32> {
+        let coverage = self.debugger.as_ref()?.coverage();
+        if !self.state.heatmap || row.addr >= 0x8000 {
+            return None;
+        }
+        let used = (0..len)
+            .map(|offset| Address {
+                bank: row.bank,
+                addr: row.addr.wrapping_add(offset),
+            })
+            .fold(RomUse::empty(), |used, addr| used | coverage.rom(addr));
+        let analysis = self.disassembly.as_ref()?.analysis()?;
+        let code = analysis.kind(row) == Some(Kind::Code);
+        if code && !used.contains(RomUse::EXECUTED) {
+            return Some(heat(1.));
         }
+        Some(rom_use(used))
     }
 
     pub fn ui(&mut self, ui: &mut Ui) {
         ui.horizontal(|ui| {
             ui.label(title(ui, "disassembly"));
             ui.with_layout(egui::Layout::right_to_left(Align::Center), |ui| {
-                if ui.button(monospace("🔃")).clicked() {
+                let refresh = ui
+                    .button(monospace("🔃"))
+                    .on_hover_text("analyse again, with the code run so far");
+                if refresh.clicked() {
                     let mut disassembly = None;
                     mem::swap(self.disassembly, &mut disassembly);
                 }
+                let export = ui
+                    .button(monospace("💾"))
+                    .on_hover_text("export as RGBDS source that assembles to the ROM");
+                if export.clicked() {
+                    self.export();
+                }
+                ui.add(
+                    TextEdit::singleline(&mut self.state.export_path)
+                        .font(TextStyle::Monospace)
+                        .desired_width(150.0),
+                );
             });
         });
 
+        if self.state.patched {
+            ui.horizontal(|ui| {
+                ui.label(monospace("patched ROM"));
+                ui.add(
+                    TextEdit::singleline(&mut self.state.rom_path)
+                        .font(TextStyle::Monospace)
+                        .desired_width(150.0),
+                );
+                let export = ui
+                    .button(monospace("💾"))
+                    .on_hover_text("export the ROM with the patches");
+                if export.clicked() {
+                    self.export_rom();
+                }
+            });
+        }
+
+        if self.debugger.is_some() {
+            ui.horizontal(|ui| {
+                if let Some(debugger) = self.debugger.as_mut() {
+                    let mut enabled = debugger.coverage().is_enabled();
+                    let record = ui
+                        .checkbox(&mut enabled, monospace("record"))
+                        .on_hover_text(
+                            "record the code that runs and the memory it reads and writes",
+                        );
+                    if record.changed() {
+                        debugger.command(Command::Coverage(enabled));
+                    }
+                }
+                ui.checkbox(&mut self.state.heatmap, monospace("coverage"))
+                    .on_hover_text(
+                        "colour code green if it ran and red if not, and data blue if read",
+                    );
+                ui.add(
+                    TextEdit::singleline(&mut self.state.coverage_path)
+                        .font(TextStyle::Monospace)
+                        .desired_width(150.0),
+                );
+                let export = ui
+                    .button(monospace("💾"))
+                    .on_hover_text("export a report of the code that ran and the code that didn't");
+                if export.clicked() {
+                    self.export_coverage();
+                }
+                let clear = ui
+                    .button(monospace("🗑"))
+                    .on_hover_text("clear the coverage");
+                if let (true, Some(debugger)) = (clear.clicked(), self.debugger.as_mut()) {
+                    debugger.command(Command::ClearCoverage);
+                }
+            });
+        }
+
         ui.horizontal(|ui| {
             ui.selectable_value(&mut self.state.setting, DisasmPanelSetting::PC, "PC");
             ui.selectable_value(
                 &mut self.state.setting,
                 DisasmPanelSetting::Manual,
-                monospace(format!("0x{:04x}", self.state.address)),
+                monospace(format!("{}", self.state.address)),
             );
             ui.separator();
-            let mut input = AddressInput::new(self.toasts, &mut self.state.edit_string);
+            let mut input = AddressInput::new(self.toasts, &mut self.state.edit_string)
+                .with_symbols(self.state.symbols.as_ref())
+                .with_source_map(self.source_map);
             if let Some(addr) = input.ui(ui) {
-                self.state.address = addr;
-                self.state.setting = DisasmPanelSetting::Manual;
+                self.state.go_to(addr);
             }
         });
 
-        let addr = match self.state.setting {
-            DisasmPanelSetting::PC => self.pc,
-            DisasmPanelSetting::Manual => self.state.address,
-        };
+        let addr = self.state.address(self.pc, self.mem);
 
         if self.disassembly.is_none() {
-            let mut disassembly = Some(Disassembly::from_bytes(
-                self.mem.into(),
-                self.state.symbols.as_ref(),
-            ));
+            let executed = self
+                .debugger
+                .iter()
+                .flat_map(|debugger| debugger.get_executed())
+                .copied();
+            let analysis = Analysis::new(self.mem, executed);
+            let mut disassembly = Some(Disassembly::default().with_analysis(analysis));
             mem::swap(self.disassembly, &mut disassembly);
         }
 
-        if let Some(ref disassembly) = self.disassembly {
-            if let Some(instructions) = disassembly.get_instructions_near(addr, -5..10) {
-                let count = instructions.len();
-                assert!(count >= 10);
-                for instr in instructions.into_iter() {
-                    let mut rt = monospace(format!("{}", instr));
-                    if instr.addr == addr {
-                        rt = rt.color(highlight(ui));
+        let location = self
+            .source_map
+            .and_then(|source_map| source_map.location(addr));
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| self.instructions(addr, ui));
+            if let Some((file, line)) = location {
+                ui.separator();
+                ui.vertical(|ui| self.source(file, line, ui));
+            }
+        });
+    }
+
+    fn instructions(&mut self, addr: Address, ui: &mut Ui) {
+        let Some(ref mut disassembly) = self.disassembly else {
+            return;
+        };
+        let instructions =
+            disassembly.get_instructions_near(self.mem, addr, -5..10, self.state.symbols.as_ref());
+        let count = instructions.len();
+        // Code can be patched while paused.
+        let paused = self
+            .debugger
+            .as_ref()
+            .is_some_and(|debugger| !debugger.is_running());
+        let mut patch = None;
+        for instr in instructions.into_iter() {
+            // Rows in the switchable bank are in the bank being shown.
+            let row = match instr.addr {
+                0x4000..=0x7fff => Address {
+                    bank: addr.bank,
+                    addr: instr.addr,
+                },
+                _ => Address::current(instr.addr, self.mem),
+            };
+            let mut rt = monospace(format!("{}", instr));
+            if instr.addr == addr.addr {
+                rt = rt.color(highlight(ui));
+            }
+            if let Some(color) = self.heat(row, instr.length()) {
+                rt = rt.background_color(color);
+            }
+            let response = ui
+                .add(Label::new(rt).sense(Sense::click()))
+                .on_hover_text(instr.details());
+            if response.clicked() {
+                self.state.go_to(row);
+            }
+            if self.debugger.is_none() {
+                continue;
+            }
+            response.context_menu(|ui| {
+                if let Some(debugger) = self.debugger.as_mut() {
+                    if ui.button("run to here").clicked() {
+                        debugger.command(Command::RunTo(row));
+                        ui.close_menu();
                     }
-                    ui.label(rt);
                 }
-                for _ in 0..(15 - count) {
-                    ui.label("");
-                }
-            } else {
-                for _ in 0..15 {
-                    ui.label("");
+                if paused {
+                    let edit = TextEdit::singleline(&mut self.state.patch)
+                        .font(TextStyle::Monospace)
+                        .hint_text("patch, e.g. ld a, $05");
+                    let response = ui.add(edit).on_hover_text(
+                        "assemble over this instruction, at most as long, filling the rest with nop",
+                    );
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                        patch = Some((row, instr.length()));
+                        ui.close_menu();
+                    }
                 }
-            }
+            });
+        }
+        for _ in count..15 {
+            ui.label("");
+        }
+        if let Some((row, len)) = patch {
+            self.patch(row, len);
         }
     }
 }
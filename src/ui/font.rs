@@ -1,5 +1,7 @@
 use egui::*;
 
+use crate::debugger::RomUse;
+
 pub fn highlight(ui: &Ui) -> Color32 {
     if ui.style().visuals == Visuals::dark() {
         Color32::from_rgb(155, 188, 15)
@@ -17,3 +19,23 @@ pub fn title(ui: &Ui, s: impl Into<String>) -> RichText {
 pub fn monospace(s: impl Into<String>) -> RichText {
     RichText::new(s).font(FontId::monospace(12.))
 }
+
+/// Heatmap background, from clear at 0 to red at 1, translucent so the text
+/// stays readable.
+pub fn heat(amount: f32) -> Color32 {
+    Color32::from_rgba_unmultiplied(255, 32, 0, (amount.clamp(0., 1.) * 160.) as u8)
+}
+
+/// Background showing how a ROM byte was used: green if executed, orange if
+/// written and blue if read.
+pub fn rom_use(used: RomUse) -> Color32 {
+    if used.contains(RomUse::EXECUTED) {
+        Color32::from_rgba_unmultiplied(0, 192, 0, 80)
+    } else if used.contains(RomUse::WRITTEN) {
+        Color32::from_rgba_unmultiplied(255, 160, 0, 100)
+    } else if used.contains(RomUse::READ) {
+        Color32::from_rgba_unmultiplied(0, 96, 255, 80)
+    } else {
+        Color32::TRANSPARENT
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use egui::*;
+use egui_notify::Toasts;
+
+use crate::debugger::{Command, Debugger, HardwareEvent};
+use crate::ui::*;
+
+pub struct HardwareEvents<'a> {
+    debugger: &'a mut Option<Debugger>,
+    toasts: &'a mut Toasts,
+    event_string: &'a mut String,
+}
+
+impl<'a> HardwareEvents<'a> {
+    pub fn new(
+        debugger: &'a mut Option<Debugger>,
+        toasts: &'a mut Toasts,
+        event_string: &'a mut String,
+    ) -> Self {
+        Self {
+            debugger,
+            toasts,
+            event_string,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.label(title(ui, "break on event"));
+        if let Some(debugger) = self.debugger.as_mut() {
+            ui.horizontal(|ui| {
+                let tb_response = ui.add(
+                    TextEdit::singleline(self.event_string)
+                        .hint_text("int vblank, lcd off, line 144")
+                        .desired_width(150.),
+                );
+                let tb_submit =
+                    tb_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if ui.button(monospace("⮩")).clicked() || tb_submit {
+                    match self.event_string.parse::<HardwareEvent>() {
+                        Ok(event) => debugger.command(Command::InsertHardwareEvent(event)),
+                        Err(e) => {
+                            self.toasts
+                                .error(format!("Error: {}", e))
+                                .set_duration(Some(Duration::from_secs(5)));
+                        }
+                    }
+                }
+            });
+            egui::ScrollArea::vertical()
+                .id_source("hardware_events")
+                .show(ui, |ui| {
+                    let mut remove = None;
+                    for event in debugger.get_hardware_events().iter() {
+                        ui.horizontal(|ui| {
+                            if ui.button(monospace("➖")).clicked() {
+                                remove = Some(*event);
+                            }
+                            ui.label(monospace(format!("{}", event)));
+                        });
+                    }
+                    if let Some(event) = remove {
+                        debugger.command(Command::RemoveHardwareEvent(event));
+                    }
+                });
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use egui::*;
+
+/// Hex value that can be clicked and typed over when enabled.
+pub struct HexEdit<'a, T: emath::Numeric> {
+    value: &'a mut T,
+    digits: usize,
+    enabled: bool,
+}
+
+impl<'a, T: emath::Numeric> HexEdit<'a, T> {
+    pub fn new(value: &'a mut T, digits: usize, enabled: bool) -> Self {
+        Self {
+            value,
+            digits,
+            enabled,
+        }
+    }
+
+    /// Returns whether the value was changed.
+    pub fn ui(&mut self, ui: &mut Ui) -> bool {
+        let drag_value = DragValue::new(self.value)
+            .hexadecimal(self.digits, false, false)
+            .prefix("0x")
+            .speed(0.)
+            // Only write once the edit is done, so a half typed value
+            // never reaches an MBC or I/O register.
+            .update_while_editing(false);
+        ui.add_enabled(self.enabled, drag_value).changed()
+    }
+}
@@ -12,13 +12,12 @@ impl<'a> Indicator<'a> {
         Self { lit, label }
     }
 
-    pub fn ui(&mut self, ui: &mut Ui) {
-        if self.lit {
-            let s = monospace(format!("🔴 {}", self.label)).color(highlight(ui));
-            ui.label(s);
+    pub fn ui(&mut self, ui: &mut Ui) -> Response {
+        let s = if self.lit {
+            monospace(format!("🔴 {}", self.label)).color(highlight(ui))
         } else {
-            let s = monospace(format!("⭕ {}", self.label));
-            ui.label(s);
-        }
+            monospace(format!("⭕ {}", self.label))
+        };
+        ui.add(Label::new(s).sense(Sense::click()))
     }
 }
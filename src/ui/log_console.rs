@@ -0,0 +1,38 @@
+use egui::*;
+
+use crate::ui::serial_console::keep_last_lines;
+use crate::ui::*;
+
+const LINES: usize = 12;
+
+/// Messages printed by logpoints.
+pub struct LogConsole<'a> {
+    log: &'a mut String,
+}
+
+impl<'a> LogConsole<'a> {
+    pub fn new(log: &'a mut String) -> Self {
+        Self { log }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        keep_last_lines(self.log, LINES);
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.label(title(ui, "log"));
+                ui.with_layout(egui::Layout::right_to_left(Align::Center), |ui| {
+                    if ui.button(monospace("🗋")).clicked() {
+                        self.log.clear()
+                    }
+                });
+            });
+
+            let te = TextEdit::multiline(self.log)
+                .code_editor()
+                .frame(true)
+                .vertical_align(Align::Max)
+                .interactive(false);
+            ui.add_sized(ui.available_size(), te);
+        });
+    }
+}
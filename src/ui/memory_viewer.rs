@@ -1,10 +1,18 @@
+use std::time::Duration;
+
 use egui::*;
 use egui_notify::Toasts;
 
+use crate::address::Address;
 use crate::cpu::Cpu;
+use crate::debugger::{Coverage, RomUse};
 use crate::memory::{Memory, ProgramMemory};
+use crate::symbols::Symbols;
 use crate::ui::*;
 
+/// Width of the label gutter, in characters.
+const GUTTER: usize = 12;
+
 #[derive(Default, PartialEq)]
 pub enum MemoryViewerSetting {
     #[default]
@@ -16,41 +24,166 @@ pub enum MemoryViewerSetting {
     Manual,
 }
 
+/// What the heatmap over the bytes shows.
+#[derive(Default, PartialEq)]
+enum Heatmap {
+    #[default]
+    Off,
+    /// ROM executed or read, and RAM by how often it was read.
+    Reads,
+    /// ROM written, and RAM by how often it was written.
+    Writes,
+}
+
 #[derive(Default)]
 pub struct MemoryViewerState {
     setting: MemoryViewerSetting,
+    heatmap: Heatmap,
     address: u16,
     edit_string: String,
+    /// Cell being edited and the text typed into it.
+    editing: Option<(u16, String)>,
+    write_addr: String,
+    write_bytes: String,
 }
 
 pub struct MemoryViewer<'a> {
-    mem: &'a Memory,
+    mem: &'a mut Memory,
     cpu: &'a Cpu,
     toasts: &'a mut Toasts,
     state: &'a mut MemoryViewerState,
+    /// Whether memory can be edited, i.e. the debugger is paused.
+    editable: bool,
+    symbols: Option<&'a Symbols>,
+    coverage: Option<&'a Coverage>,
+}
+
+fn parse_hex_bytes(s: &str) -> Result<Vec<u8>, String> {
+    let digits: String = s.split_whitespace().collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err("odd number of hex digits".to_owned());
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("invalid byte {:?}", &digits[i..i + 2]))
+        })
+        .collect()
 }
 
 impl<'a> MemoryViewer<'a> {
     pub fn new(
-        mem: &'a Memory,
+        mem: &'a mut Memory,
         cpu: &'a Cpu,
         toasts: &'a mut Toasts,
         state: &'a mut MemoryViewerState,
+        editable: bool,
+        symbols: Option<&'a Symbols>,
     ) -> Self {
         Self {
             mem,
             cpu,
             toasts,
             state,
+            editable,
+            symbols,
+            coverage: None,
+        }
+    }
+
+    /// Show how the code used memory as a heatmap.
+    pub fn with_coverage(mut self, coverage: Option<&'a Coverage>) -> Self {
+        self.coverage = coverage;
+        self
+    }
+
+    /// Heatmap background for a byte. RAM is scaled logarithmically to the
+    /// most used byte, so rarely used bytes still show.
+    fn heat(&self, addr: u16) -> Option<Color32> {
+        let coverage = self.coverage?;
+        if addr < 0x8000 {
+            let used = coverage.rom(Address::current(addr, &*self.mem));
+            let used = match self.state.heatmap {
+                Heatmap::Off => return None,
+                Heatmap::Reads => used & (RomUse::EXECUTED | RomUse::READ),
+                Heatmap::Writes => used & RomUse::WRITTEN,
+            };
+            return Some(rom_use(used));
+        }
+        let (used, most) = (coverage.ram(addr), coverage.most());
+        let (count, most) = match self.state.heatmap {
+            Heatmap::Off => return None,
+            Heatmap::Reads => (used.reads, most.reads),
+            Heatmap::Writes => (used.writes, most.writes),
+        };
+        let amount = (count as f32).ln_1p() / (most as f32).ln_1p().max(1.);
+        (count > 0).then(|| heat(amount))
+    }
+
+    fn label(&self, addr: u16) -> Option<&'a str> {
+        let addr = Address::current(addr, &*self.mem);
+        self.symbols?.get_symbol_at(addr)
+    }
+
+    /// Write bytes through the memory map, as the CPU would.
+    fn write_bytes(&mut self) -> Result<(), String> {
+        let addr: Address = self.state.write_addr.parse()?;
+        if addr.bank.is_some() && addr.bank != self.mem.bank(addr.addr) {
+            return Err(format!("{} is not mapped in", addr));
+        }
+        let bytes = parse_hex_bytes(&self.state.write_bytes)?;
+        if addr.addr as usize + bytes.len() > 0x10000 {
+            return Err("write runs past 0xffff".to_owned());
         }
+        for (offset, byte) in bytes.into_iter().enumerate() {
+            ProgramMemory::set_u8(self.mem, addr.addr + offset as u16, byte);
+        }
+        Ok(())
     }
 
-    fn memory_cell(&self, mem: &impl ProgramMemory, cell_addr: u16, addr: u16, ui: &mut Ui) {
-        let mut text = monospace(format!("{:02x}", mem.get_u8(cell_addr)));
+    fn memory_cell(&mut self, cell_addr: u16, addr: u16, ui: &mut Ui) {
+        if let Some((editing, ref mut text)) = self.state.editing {
+            if editing == cell_addr {
+                let response = ui.add(
+                    TextEdit::singleline(text)
+                        .font(TextStyle::Monospace)
+                        .char_limit(2)
+                        .desired_width(16.),
+                );
+                response.request_focus();
+                if ui.input(|i| i.key_pressed(Key::Enter)) {
+                    match u8::from_str_radix(text, 16) {
+                        Ok(value) => ProgramMemory::set_u8(self.mem, cell_addr, value),
+                        Err(_) => {
+                            self.toasts
+                                .error(format!("Error: invalid byte {:?}", text))
+                                .set_duration(Some(Duration::from_secs(5)));
+                        }
+                    }
+                    self.state.editing = None;
+                } else if ui.input(|i| i.key_pressed(Key::Escape)) {
+                    self.state.editing = None;
+                }
+                return;
+            }
+        }
+
+        let value = ProgramMemory::get_u8(self.mem, cell_addr);
+        let mut text = monospace(format!("{:02x}", value));
         if cell_addr == addr {
             text = text.color(highlight(ui));
         }
-        ui.label(text);
+        if let Some(color) = self.heat(cell_addr) {
+            text = text.background_color(color);
+        }
+        let mut response = ui.add(Label::new(text).sense(Sense::click()));
+        if let Some(label) = self.label(cell_addr) {
+            response = response.on_hover_text(label);
+        }
+        if response.clicked() && self.editable {
+            self.state.editing = Some((cell_addr, format!("{:02x}", value)));
+        }
     }
 
     fn memory_cell_ascii(&self, mem: &impl ProgramMemory, cell_addr: u16, addr: u16, ui: &mut Ui) {
@@ -63,23 +196,45 @@ impl<'a> MemoryViewer<'a> {
         ui.label(text);
     }
 
-    fn memory_row(&self, row_addr: u16, addr: u16, ui: &mut Ui) {
+    fn memory_row(&mut self, row_addr: u16, addr: u16, ui: &mut Ui) {
         ui.horizontal(|ui| {
+            if self.symbols.is_some() {
+                // First label in the row, with all of them on hover.
+                let labels: Vec<_> = (row_addr..=row_addr + 0xf)
+                    .filter_map(|addr| self.label(addr).map(|label| (addr, label)))
+                    .collect();
+                let gutter = match labels.first() {
+                    Some((_, label)) if label.chars().count() > GUTTER => {
+                        let label: String = label.chars().take(GUTTER - 1).collect();
+                        format!("{}…", label)
+                    }
+                    Some((_, label)) => label.to_string(),
+                    None => String::new(),
+                };
+                let response = ui.label(monospace(format!("{:>width$} ", gutter, width = GUTTER)));
+                if !labels.is_empty() {
+                    let hover: Vec<_> = labels
+                        .iter()
+                        .map(|(addr, label)| format!("0x{:04x} {}", addr, label))
+                        .collect();
+                    response.on_hover_text(monospace(hover.join("\n")));
+                }
+            }
             ui.label(monospace(format!("0x{:04x}: ", row_addr)));
 
             for i in 0..8 {
-                self.memory_cell(self.mem, row_addr + i * 2, addr, ui);
-                self.memory_cell(self.mem, row_addr + i * 2 + 1, addr, ui);
+                self.memory_cell(row_addr + i * 2, addr, ui);
+                self.memory_cell(row_addr + i * 2 + 1, addr, ui);
                 ui.label(monospace(" "));
             }
 
             for i in 0..16 {
-                self.memory_cell_ascii(self.mem, row_addr + i, addr, ui);
+                self.memory_cell_ascii(&*self.mem, row_addr + i, addr, ui);
             }
         });
     }
 
-    fn memory_at(&self, addr: u16, ui: &mut Ui) {
+    fn memory_at(&mut self, addr: u16, ui: &mut Ui) {
         let rem = addr as u32 % 0x10;
         let row_addr = addr as u32 - rem;
 
@@ -101,6 +256,9 @@ impl<'a> MemoryViewer<'a> {
     }
 
     pub fn ui(&mut self, ui: &mut Ui) {
+        if !self.editable {
+            self.state.editing = None;
+        }
         ui.vertical(|ui| {
             ui.label(title(ui, "memory viewer"));
             ui.horizontal(|ui| {
@@ -115,11 +273,44 @@ impl<'a> MemoryViewer<'a> {
                     monospace(format!("0x{:04x}", self.state.address)),
                 );
                 ui.separator();
-                let mut input = AddressInput::new(self.toasts, &mut self.state.edit_string);
+                let mut input = AddressInput::new(self.toasts, &mut self.state.edit_string)
+                    .with_symbols(self.symbols);
                 if let Some(addr) = input.ui(ui) {
-                    self.state.address = addr;
+                    self.state.address = addr.addr;
                     self.state.setting = MemoryViewerSetting::Manual;
                 }
+                if self.coverage.is_some() {
+                    ui.separator();
+                    ui.label(monospace("heatmap"));
+                    ui.selectable_value(&mut self.state.heatmap, Heatmap::Off, "off");
+                    ui.selectable_value(&mut self.state.heatmap, Heatmap::Reads, "reads")
+                        .on_hover_text("ROM executed (green) or read (blue), and RAM by reads");
+                    ui.selectable_value(&mut self.state.heatmap, Heatmap::Writes, "writes")
+                        .on_hover_text("ROM written to select banks, and RAM by writes");
+                }
+            });
+
+            ui.add_enabled_ui(self.editable, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(monospace("write"));
+                    ui.add(
+                        TextEdit::singleline(&mut self.state.write_addr)
+                            .hint_text("[bank:]addr")
+                            .desired_width(60.),
+                    );
+                    ui.add(
+                        TextEdit::singleline(&mut self.state.write_bytes)
+                            .hint_text("3e 01 c9")
+                            .desired_width(150.),
+                    );
+                    if ui.button(monospace("⮩")).clicked() {
+                        if let Err(e) = self.write_bytes() {
+                            self.toasts
+                                .error(format!("Error: {}", e))
+                                .set_duration(Some(Duration::from_secs(5)));
+                        }
+                    }
+                });
             });
 
             let addr = match self.state.setting {
@@ -2,32 +2,46 @@ mod about;
 mod address_input;
 mod breakpoints;
 mod callstack;
+mod command_console;
 mod cpu_panel;
 mod debugger_buttons;
 mod disasm_panel;
 mod font;
 mod graphics;
+mod hardware_events;
+mod hex_edit;
 mod indicator;
+mod log_console;
 mod memory_viewer;
+mod profiler;
+mod references;
 mod registers;
 mod serial_console;
 mod speed_input;
 mod status;
 mod toolbar;
+mod watchpoints;
 
 pub use about::About;
 pub use address_input::AddressInput;
-pub use breakpoints::Breakpoints;
+pub use breakpoints::{Breakpoints, BreakpointsState};
 pub use callstack::Callstack;
+pub use command_console::{CommandConsole, CommandConsoleState};
 pub use cpu_panel::CpuPanel;
 pub use debugger_buttons::DebuggerButtons;
 pub use disasm_panel::{DisasmPanel, DisasmPanelState};
-pub use font::{highlight, monospace, title};
+pub use font::{heat, highlight, monospace, rom_use, title};
 pub use graphics::{ViewerSelect, ViewerSelectState, Viewer, ViewerContext};
+pub use hardware_events::HardwareEvents;
+pub use hex_edit::HexEdit;
 pub use indicator::Indicator;
+pub use log_console::LogConsole;
 pub use memory_viewer::{MemoryViewer, MemoryViewerState};
+pub use profiler::{ProfilerPanel, ProfilerPanelState};
+pub use references::References;
 pub use registers::Registers;
 pub use serial_console::SerialConsole;
 pub use speed_input::SpeedInput;
 pub use status::Status;
 pub use toolbar::Toolbar;
+pub use watchpoints::Watchpoints;
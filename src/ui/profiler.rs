@@ -0,0 +1,185 @@
+use std::cmp::Reverse;
+use std::time::Duration;
+
+use egui::*;
+use egui_notify::Toasts;
+
+use crate::debugger::{function_name, Command, Debugger, FunctionProfile, Profile};
+use crate::symbols::Symbols;
+use crate::ui::*;
+
+/// M-cycles from one VBlank to the next.
+const FRAME_CYCLES: u64 = 17556;
+
+#[derive(Default, PartialEq)]
+enum ProfileView {
+    #[default]
+    LastFrame,
+    Session,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Column {
+    Function,
+    #[default]
+    Inclusive,
+    Exclusive,
+    Calls,
+}
+
+pub struct ProfilerPanelState {
+    view: ProfileView,
+    sort: Column,
+    /// File to export collapsed stacks to.
+    export_path: String,
+}
+
+impl Default for ProfilerPanelState {
+    fn default() -> Self {
+        Self {
+            view: ProfileView::default(),
+            sort: Column::default(),
+            export_path: "profile.folded".to_owned(),
+        }
+    }
+}
+
+/// Cycles spent in each function over the last frame or the session, with
+/// the session exported as collapsed stacks for flame graphs.
+pub struct ProfilerPanel<'a> {
+    debugger: &'a mut Option<Debugger>,
+    state: &'a mut ProfilerPanelState,
+    toasts: &'a mut Toasts,
+    symbols: Option<&'a Symbols>,
+}
+
+impl<'a> ProfilerPanel<'a> {
+    pub fn new(
+        debugger: &'a mut Option<Debugger>,
+        state: &'a mut ProfilerPanelState,
+        toasts: &'a mut Toasts,
+        symbols: Option<&'a Symbols>,
+    ) -> Self {
+        Self {
+            debugger,
+            state,
+            toasts,
+            symbols,
+        }
+    }
+
+    fn export(toasts: &mut Toasts, path: &str, profile: &Profile, symbols: Option<&Symbols>) {
+        match std::fs::write(path, profile.collapsed(symbols)) {
+            Ok(()) => {
+                toasts
+                    .info(format!("Exported {}", path))
+                    .set_duration(Some(Duration::from_secs(5)));
+            }
+            Err(e) => {
+                toasts
+                    .error(format!("Could not write {}: {}", path, e))
+                    .set_duration(Some(Duration::from_secs(5)));
+            }
+        }
+    }
+
+    fn table(sort: &mut Column, profile: &Profile, symbols: Option<&Symbols>, ui: &mut Ui) {
+        let mut rows: Vec<(String, &FunctionProfile)> = profile
+            .functions()
+            .map(|(function, stats)| (function_name(function, symbols), stats))
+            .collect();
+        match sort {
+            Column::Function => rows.sort_by(|a, b| a.0.cmp(&b.0)),
+            Column::Inclusive => rows.sort_by_key(|(_, stats)| Reverse(stats.inclusive)),
+            Column::Exclusive => rows.sort_by_key(|(_, stats)| Reverse(stats.exclusive)),
+            Column::Calls => rows.sort_by_key(|(_, stats)| Reverse(stats.calls)),
+        }
+
+        let percent = |cycles: u64| 100. * cycles as f64 / profile.cycles().max(1) as f64;
+        egui::ScrollArea::vertical()
+            .id_source("profiler")
+            .max_height(250.)
+            .show(ui, |ui| {
+                Grid::new("profile").striped(true).show(ui, |ui| {
+                    for (column, name) in [
+                        (Column::Function, "function"),
+                        (Column::Inclusive, "inclusive"),
+                        (Column::Exclusive, "exclusive"),
+                        (Column::Calls, "calls"),
+                    ] {
+                        ui.selectable_value(sort, column, monospace(name))
+                            .on_hover_text("sort by this");
+                    }
+                    ui.end_row();
+                    for (name, stats) in rows {
+                        ui.label(monospace(name));
+                        ui.label(monospace(format!(
+                            "{:>7} {:>5.1}%",
+                            stats.inclusive,
+                            percent(stats.inclusive)
+                        )));
+                        ui.label(monospace(format!(
+                            "{:>7} {:>5.1}%",
+                            stats.exclusive,
+                            percent(stats.exclusive)
+                        )));
+                        ui.label(monospace(format!("{:>5}", stats.calls)));
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.label(title(ui, "profiler"));
+        let Some(debugger) = self.debugger.as_mut() else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            let mut enabled = debugger.profiler().is_enabled();
+            let checkbox = ui
+                .checkbox(&mut enabled, monospace("profile"))
+                .on_hover_text("start a new session");
+            if checkbox.changed() {
+                debugger.command(Command::Profile(enabled));
+            }
+            ui.selectable_value(&mut self.state.view, ProfileView::LastFrame, "last frame");
+            ui.selectable_value(&mut self.state.view, ProfileView::Session, "session");
+        });
+
+        let profiler = debugger.profiler();
+        let profile = match self.state.view {
+            ProfileView::LastFrame => profiler.last_frame(),
+            ProfileView::Session => profiler.session(),
+        };
+        let summary = match self.state.view {
+            ProfileView::LastFrame => format!(
+                "{} M-cycles, {:.1}% of a frame",
+                profile.cycles(),
+                100. * profile.cycles() as f64 / FRAME_CYCLES as f64
+            ),
+            ProfileView::Session => format!(
+                "{} M-cycles over {} frames",
+                profile.cycles(),
+                profile.frames()
+            ),
+        };
+        ui.label(monospace(summary));
+        Self::table(&mut self.state.sort, profile, self.symbols, ui);
+
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut self.state.export_path)
+                    .font(TextStyle::Monospace)
+                    .desired_width(150.0),
+            );
+            let export = ui
+                .button(monospace("💾"))
+                .on_hover_text("export the session as collapsed stacks for flame graphs");
+            if export.clicked() {
+                let path = &self.state.export_path;
+                Self::export(self.toasts, path, profiler.session(), self.symbols);
+            }
+        });
+    }
+}
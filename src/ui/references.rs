@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+
+use egui::*;
+
+use crate::address::Address;
+use crate::dasm::{function_name, Disassembly};
+use crate::debugger::Debugger;
+use crate::memory::Memory;
+use crate::symbols::Symbols;
+use crate::ui::*;
+
+/// Where a call graph edge was found: by analysis, seen running, or both.
+#[derive(Clone, Copy, Default)]
+struct Found {
+    analysis: bool,
+    running: bool,
+}
+
+impl Found {
+    fn describe(self) -> &'static str {
+        match (self.analysis, self.running) {
+            (true, true) => "found by analysis and seen running",
+            (true, false) => "found by analysis",
+            _ => "seen running",
+        }
+    }
+}
+
+/// Instructions referring to the address shown in the disassembly, and the
+/// functions calling and called by the function it's in. Clicking one shows
+/// it in the disassembly.
+pub struct References<'a> {
+    disassembly: &'a Option<Disassembly>,
+    mem: &'a Memory,
+    pc: u16,
+    debugger: &'a Option<Debugger>,
+    state: &'a mut DisasmPanelState,
+    symbols: Option<&'a Symbols>,
+}
+
+impl<'a> References<'a> {
+    pub fn new(
+        disassembly: &'a Option<Disassembly>,
+        mem: &'a Memory,
+        pc: u16,
+        debugger: &'a Option<Debugger>,
+        state: &'a mut DisasmPanelState,
+        symbols: Option<&'a Symbols>,
+    ) -> Self {
+        Self {
+            disassembly,
+            mem,
+            pc,
+            debugger,
+            state,
+            symbols,
+        }
+    }
+
+    fn name(&self, addr: Address) -> String {
+        self.symbols
+            .and_then(|symbols| symbols.get_symbol_at(addr))
+            .map_or_else(|| function_name(addr), str::to_owned)
+    }
+
+    /// Row that shows `addr` in the disassembly when clicked.
+    fn link(&mut self, ui: &mut Ui, text: String, hover: &str, addr: Address) {
+        let response = ui
+            .add(Label::new(monospace(text)).sense(Sense::click()))
+            .on_hover_text(hover);
+        if response.clicked() {
+            self.state.go_to(addr);
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.label(title(ui, "references"));
+        let Some(analysis) = self.disassembly.as_ref().and_then(Disassembly::analysis) else {
+            return;
+        };
+        let addr = self.state.address(self.pc, self.mem);
+        let references: Vec<_> = analysis.references_to(addr).copied().collect();
+        let function = analysis.function_at(addr);
+
+        // Calls seen running are between call instructions and functions.
+        let seen = self
+            .debugger
+            .iter()
+            .flat_map(|debugger| debugger.get_calls());
+        let seen: Vec<_> = seen
+            .map(|(site, target)| (analysis.function_at(*site).unwrap_or(*site), *target))
+            .collect();
+        let mut callers: BTreeMap<Address, Found> = BTreeMap::new();
+        let mut callees: BTreeMap<Address, Found> = BTreeMap::new();
+        if let Some(function) = function {
+            for (caller, callee) in analysis.calls() {
+                if *callee == function {
+                    callers.entry(*caller).or_default().analysis = true;
+                }
+                if *caller == function {
+                    callees.entry(*callee).or_default().analysis = true;
+                }
+            }
+            for (caller, callee) in &seen {
+                if *callee == function {
+                    callers.entry(*caller).or_default().running = true;
+                }
+                if *caller == function {
+                    callees.entry(*callee).or_default().running = true;
+                }
+            }
+        }
+
+        let name = self
+            .symbols
+            .and_then(|symbols| symbols.get_symbol_at(addr))
+            .map_or_else(|| format!("{}", addr), str::to_owned);
+        egui::ScrollArea::vertical()
+            .id_source("references")
+            .max_height(200.)
+            .show(ui, |ui| {
+                ui.label(monospace(format!("to {}", name)));
+                if references.is_empty() {
+                    ui.label(monospace("  none found"));
+                }
+                for reference in references {
+                    let from = analysis.function_at(reference.from);
+                    let text = format!(
+                        "  {:<7} {} {}",
+                        reference.access,
+                        reference.from,
+                        from.map(|from| self.name(from)).unwrap_or_default()
+                    );
+                    self.link(ui, text, "show in disassembly", reference.from);
+                }
+
+                let Some(function) = function else {
+                    return;
+                };
+                let text = format!("in {} {}", self.name(function), function);
+                self.link(ui, text, "show in disassembly", function);
+                ui.label(monospace("  called by"));
+                for (caller, found) in callers {
+                    let text = format!("    {} {}", caller, self.name(caller));
+                    self.link(ui, text, found.describe(), caller);
+                }
+                ui.label(monospace("  calls"));
+                for (callee, found) in callees {
+                    let text = format!("    {} {}", callee, self.name(callee));
+                    self.link(ui, text, found.describe(), callee);
+                }
+            });
+    }
+}
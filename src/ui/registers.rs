@@ -2,29 +2,41 @@ use bitflags::Flags;
 
 use egui::*;
 
-use crate::memory::Memory;
+use crate::memory::{Memory, ProgramMemory};
 use crate::memory_map::MemoryMap;
 use crate::registers::{graphics::*, timer::*, *};
 use crate::ui::*;
 
 pub struct Registers<'a> {
-    mem: &'a Memory,
+    mem: &'a mut Memory,
+    /// Whether registers can be edited, i.e. the debugger is paused.
+    editable: bool,
 }
 
+// Edits are written through the memory map, with the side effects a write
+// from the CPU would have.
 macro_rules! reg {
     ($self:ident, $ui:ident, $label:literal, $address:expr) => {
-        let reg = $self.mem.get_u8($address);
-        $ui.label(monospace(format!("{}: 0x{:02x}", $label, reg)));
+        $ui.horizontal(|ui| {
+            ui.label(monospace(format!("{}:", $label)));
+            let mut reg = ProgramMemory::get_u8($self.mem, $address);
+            if HexEdit::new(&mut reg, 2, $self.editable).ui(ui) {
+                ProgramMemory::set_u8($self.mem, $address, reg);
+            }
+        });
     };
     ($self:ident, $ui:ident, $label:literal, $address:expr, $type:ident) => {
         egui::CollapsingHeader::new($label)
             .default_open(true)
             .show($ui, |ui| {
-                let reg = $self.mem.get_reg::<$type>($address);
+                let mut reg = $self.mem.get_reg::<$type>($address);
                 for flag in $type::FLAGS.iter() {
                     if flag.is_named() {
                         let set = reg.contains(*flag.value());
-                        Indicator::new(set, flag.name()).ui(ui);
+                        if Indicator::new(set, flag.name()).ui(ui).clicked() && $self.editable {
+                            reg.toggle(*flag.value());
+                            ProgramMemory::set_u8($self.mem, $address, reg.bits());
+                        }
                     }
                 }
             });
@@ -32,8 +44,8 @@ macro_rules! reg {
 }
 
 impl<'a> Registers<'a> {
-    pub fn new(mem: &'a Memory) -> Self {
-        Self { mem }
+    pub fn new(mem: &'a mut Memory, editable: bool) -> Self {
+        Self { mem, editable }
     }
 
     pub fn ui(&mut self, ui: &mut Ui) {
@@ -4,6 +4,21 @@ use crate::ui::*;
 
 const LINES: usize = 12;
 
+/// Drop all but the last `count` lines of `console`.
+pub(super) fn keep_last_lines(console: &mut String, count: usize) {
+    let lines = console.split('\n').collect::<Vec<_>>();
+    if lines.len() > count {
+        let mut new = String::default();
+        for (idx, line) in lines.into_iter().rev().take(count).rev().enumerate() {
+            new.push_str(line);
+            if idx < count - 1 {
+                new.push('\n');
+            }
+        }
+        std::mem::swap(console, &mut new);
+    }
+}
+
 pub struct SerialConsole<'a> {
     console: &'a mut String,
 }
@@ -14,17 +29,7 @@ impl<'a> SerialConsole<'a> {
     }
 
     pub fn ui(&mut self, ui: &mut Ui) {
-        let lines = self.console.split('\n').collect::<Vec<_>>();
-        if lines.len() > LINES {
-            let mut new = String::default();
-            for (idx, line) in lines.into_iter().rev().take(LINES).rev().enumerate() {
-                new.push_str(line);
-                if idx < LINES - 1 {
-                    new.push('\n');
-                }
-            }
-            std::mem::swap(self.console, &mut new);
-        }
+        keep_last_lines(self.console, LINES);
         ui.vertical(|ui| {
             ui.horizontal(|ui| {
                 ui.label(title(ui, "serial monitor"));
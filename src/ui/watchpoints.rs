@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use egui::*;
+use egui_notify::Toasts;
+
+use crate::debugger::{Command, Debugger, Watchpoint};
+use crate::ui::*;
+
+pub struct Watchpoints<'a> {
+    debugger: &'a mut Option<Debugger>,
+    toasts: &'a mut Toasts,
+    wp_string: &'a mut String,
+}
+
+impl<'a> Watchpoints<'a> {
+    pub fn new(
+        debugger: &'a mut Option<Debugger>,
+        toasts: &'a mut Toasts,
+        wp_string: &'a mut String,
+    ) -> Self {
+        Self {
+            debugger,
+            toasts,
+            wp_string,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.label(title(ui, "watchpoints"));
+        if let Some(debugger) = self.debugger.as_mut() {
+            ui.horizontal(|ui| {
+                let tb_response = ui.add(
+                    TextEdit::singleline(self.wp_string)
+                        .hint_text("w c0a0 changed")
+                        .desired_width(150.),
+                );
+                let tb_submit =
+                    tb_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if ui.button(monospace("⮩")).clicked() || tb_submit {
+                    match self.wp_string.parse::<Watchpoint>() {
+                        Ok(watchpoint) => debugger.command(Command::InsertWatchpoint(watchpoint)),
+                        Err(e) => {
+                            self.toasts
+                                .error(format!("Error: {}", e))
+                                .set_duration(Some(Duration::from_secs(5)));
+                        }
+                    }
+                }
+            });
+            egui::ScrollArea::vertical()
+                .id_source("watchpoints")
+                .show(ui, |ui| {
+                    let mut remove = None;
+                    for wp in debugger.get_watchpoints().iter() {
+                        ui.horizontal(|ui| {
+                            if ui.button(monospace("➖")).clicked() {
+                                remove = Some(wp.clone());
+                            }
+                            ui.label(monospace(format!("{}", wp)));
+                        });
+                    }
+                    if let Some(wp) = remove {
+                        debugger.command(Command::RemoveWatchpoint(wp));
+                    }
+                });
+        }
+    }
+}